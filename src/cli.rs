@@ -1,3 +1,14 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use crate::config::Config;
+#[cfg(unix)]
+use crate::ipc::{self, IpcRequest, IpcResponse};
+use crate::library::{Library, SearchQuery, SortColumn};
+use crate::player::list_output_devices;
+#[cfg(unix)]
+use crate::remote::RemoteStatus;
+
 #[derive(argh::FromArgs, Debug)]
 /// A cozy crossplatform music player built in rust
 pub struct CliOptions {
@@ -8,4 +19,277 @@ pub struct CliOptions {
     /// run without graphical interface
     #[argh(switch)]
     pub no_gui: bool,
+
+    /// output device to play on for this run, see --list-devices
+    #[argh(option)]
+    pub device: Option<String>,
+
+    /// frames per audio callback for this run, lower means less latency
+    #[argh(option)]
+    pub buffer_size: Option<u32>,
+
+    /// list audio output devices and what they support
+    #[argh(switch)]
+    pub list_devices: bool,
+
+    #[argh(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(argh::FromArgs, Debug)]
+#[argh(subcommand)]
+pub enum Command {
+    Search(SearchCommand),
+    History(HistoryCommand),
+    Ctl(CtlCommand),
+    Config(ConfigCommand),
+}
+
+#[derive(argh::FromArgs, Debug)]
+/// Search the library, e.g. `search artist:radiohead year:>2000 genre:rock -live`
+#[argh(subcommand, name = "search")]
+pub struct SearchCommand {
+    /// library folder to search, defaults to the configured folders
+    #[argh(option)]
+    pub folder: Vec<PathBuf>,
+
+    /// search terms; put `--` first if the query starts with a negated term
+    #[argh(positional, greedy)]
+    pub query: Vec<String>,
+}
+
+#[derive(argh::FromArgs, Debug)]
+/// Show recently played tracks or export the listening history
+#[argh(subcommand, name = "history")]
+pub struct HistoryCommand {
+    /// number of plays to show
+    #[argh(option, default = "20")]
+    pub limit: usize,
+
+    /// write the whole history to a .csv or .json file instead
+    #[argh(option)]
+    pub export: Option<PathBuf>,
+}
+
+#[derive(argh::FromArgs, Debug)]
+/// Control the running instance: play, pause, toggle, stop, next, previous, status,
+/// enqueue <file>... or open <file>
+#[argh(subcommand, name = "ctl")]
+pub struct CtlCommand {
+    /// what to do
+    #[argh(positional)]
+    pub action: String,
+
+    /// files for enqueue and open
+    #[argh(positional)]
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(argh::FromArgs, Debug)]
+/// Check the config file for mistakes
+#[argh(subcommand, name = "config")]
+pub struct ConfigCommand {
+    /// print the default config instead, as a starting point
+    #[argh(switch)]
+    pub defaults: bool,
+}
+
+impl Command {
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Command::Search(cmd) => cmd.run(),
+            Command::History(cmd) => cmd.run(),
+            Command::Ctl(cmd) => cmd.run(),
+            Command::Config(cmd) => cmd.run(),
+        }
+    }
+}
+
+impl SearchCommand {
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        let query: SearchQuery = self.query.join(" ").parse()?;
+        let folders = match self.folder.is_empty() {
+            true => load_config()?.library_folders(),
+            false => self.folder,
+        };
+
+        let library = Library::new(folders);
+        library.scan()?;
+
+        for track in library.read().search(&query, SortColumn::default(), true) {
+            println!(
+                "{} - {} ({})\t{}",
+                track.display_artist(),
+                track.display_title(),
+                track.display_album(),
+                track.path.display()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl ConfigCommand {
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        if self.defaults {
+            print!("{}", toml::to_string_pretty(&Config::default())?);
+            return Ok(());
+        }
+
+        let path = Config::default_path().ok_or("No config directory on this system")?;
+
+        match path.is_file() {
+            true => {
+                Config::load(&path)?;
+                println!("{} is valid", path.display());
+            }
+            false => println!("{} doesn't exist, using the defaults", path.display()),
+        }
+
+        Ok(())
+    }
+}
+
+impl HistoryCommand {
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        let library = Library::new(Vec::new()).with_history(Library::default_history_path());
+        library.load_history()?;
+
+        let index = library.read();
+
+        if let Some(path) = self.export {
+            index.export_history(&path)?;
+            println!(
+                "Exported {} plays to {}",
+                index.history().len(),
+                path.display()
+            );
+            return Ok(());
+        }
+
+        for record in index.history().iter().rev().take(self.limit) {
+            println!(
+                "{}\t{} - {}\t{:.0}s\t{}",
+                record.started_at,
+                record.artist.as_deref().unwrap_or("Unknown artist"),
+                record.title.as_deref().unwrap_or("Unknown title"),
+                record.listened,
+                record.outcome.label()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl CtlCommand {
+    #[cfg(unix)]
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        let mut files = self
+            .files
+            .iter()
+            .map(|file| {
+                file.canonicalize()
+                    .map_err(|err| format!("{}: {err}", file.display()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let request = match (self.action.as_str(), files.len()) {
+            ("play", 0) => IpcRequest::Play,
+            ("pause", 0) => IpcRequest::Pause,
+            ("toggle", 0) => IpcRequest::Toggle,
+            ("stop", 0) => IpcRequest::Stop,
+            ("next", 0) => IpcRequest::Next,
+            ("previous", 0) => IpcRequest::Previous,
+            ("status", 0) => IpcRequest::Status,
+            ("enqueue", 1..) => IpcRequest::Enqueue { paths: files },
+            ("open", 1) => IpcRequest::Open {
+                path: files.remove(0),
+            },
+            ("enqueue" | "open", _) => {
+                return Err(format!("`{}` needs a file.", self.action).into());
+            }
+            (action, _) => return Err(format!("Unknown action `{action}`.").into()),
+        };
+
+        if let IpcResponse::Status(status) = ipc::send(&request)? {
+            print_status(&status);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        Err("`ctl` is only available on Unix.".into())
+    }
+}
+
+pub fn list_devices() {
+    let devices = list_output_devices();
+
+    if devices.is_empty() {
+        println!("No output devices found.");
+    }
+
+    for device in devices {
+        let marker = match device.is_default {
+            true => "*",
+            false => " ",
+        };
+        println!("{marker} {}", device.id);
+
+        let mut configs: Vec<_> = device
+            .configs
+            .iter()
+            .map(|c| {
+                format!(
+                    "{} ch, {}-{} Hz, {}",
+                    c.channels, c.min_sample_rate, c.max_sample_rate, c.sample_format
+                )
+            })
+            .collect();
+        configs.dedup();
+
+        for config in configs {
+            println!("      {config}");
+        }
+    }
+}
+
+fn load_config() -> Result<Config, Box<dyn Error>> {
+    match Config::default_path() {
+        Some(path) => Ok(Config::load(&path)?),
+        None => Ok(Config::default()),
+    }
+}
+
+#[cfg(unix)]
+fn print_status(status: &RemoteStatus) {
+    let playing = match (&status.track, &status.path) {
+        (Some(track), _) => format!("{} - {}", track.display_artist(), track.display_title()),
+        (None, Some(path)) => path.display().to_string(),
+        (None, None) => "nothing".to_string(),
+    };
+
+    println!("{:?}: {playing}", status.status);
+    println!(
+        "{} / {}\tvolume {:.0}%\tx{:.2}\t{}\tshuffle {}",
+        format_seconds(status.position),
+        format_seconds(status.duration),
+        status.volume * 100.0,
+        status.speed,
+        status.loop_mode.label(),
+        match status.shuffle {
+            true => "on",
+            false => "off",
+        }
+    );
+}
+
+#[cfg(unix)]
+fn format_seconds(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
 }
@@ -0,0 +1,275 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+mod error;
+mod keys;
+mod watcher;
+
+pub use error::*;
+pub use keys::*;
+pub use watcher::*;
+
+use crate::library::Library;
+use crate::player::{DeviceId, DitherMode, ResamplerQuality};
+use crate::remote::RemoteConfig;
+use crate::scrobble::ScrobbleConfig;
+
+/// Frames per callback accepted for `audio.buffer_size` and `--buffer-size`.
+pub const BUFFER_SIZES: RangeInclusive<u32> = 16..=16_384;
+
+/// Settings from `config.toml`. Missing sections and keys keep their defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub audio: AudioConfig,
+    pub playback: PlaybackConfig,
+    pub library: LibraryConfig,
+    pub ui: UiConfig,
+    pub keybindings: Keybindings,
+    /// Scrobbling is off without this section.
+    pub scrobble: Option<ScrobbleConfig>,
+    /// The remote control server only runs with this section.
+    pub remote: Option<RemoteConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioConfig {
+    /// Audio system such as "ALSA", "JACK" or "WASAPI", the platform default when unset.
+    pub host: Option<String>,
+    /// Output device name, the host's default when unset.
+    pub device: Option<String>,
+    /// Preferred output sample rate, 44.1 kHz when unset.
+    pub sample_rate: Option<u32>,
+    /// Frames per callback, left to the driver when unset.
+    pub buffer_size: Option<u32>,
+    /// Reopen the output at each track's own sample rate when the device supports it.
+    pub bit_perfect: bool,
+    pub volume: f32,
+    pub speed: f64,
+    pub resampler: ResamplerQuality,
+    pub dither: DitherConfig,
+}
+
+/// Dither for each integer output format, float outputs are never dithered.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DitherConfig {
+    pub u8: DitherMode,
+    pub i8: DitherMode,
+    pub i16: DitherMode,
+    pub u16: DitherMode,
+    pub i24: DitherMode,
+}
+
+/// Audio settings given on the command line, applied over the file for one run.
+#[derive(Debug, Clone, Default)]
+pub struct AudioOverrides {
+    pub device: Option<String>,
+    pub buffer_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlaybackConfig {
+    /// Tracks at least this long, in seconds, pick up where they were left off.
+    pub resume_threshold: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LibraryConfig {
+    /// The music directory when empty.
+    pub folders: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    /// One of the built-in themes, such as "Dark", "Light" or "Tokyo Night".
+    pub theme: String,
+    pub window_width: f32,
+    pub window_height: f32,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            host: None,
+            device: None,
+            sample_rate: None,
+            buffer_size: None,
+            bit_perfect: false,
+            volume: 0.4,
+            speed: 0.97,
+            resampler: ResamplerQuality::default(),
+            dither: DitherConfig::default(),
+        }
+    }
+}
+
+impl Default for PlaybackConfig {
+    fn default() -> Self {
+        Self {
+            resume_threshold: 900.0,
+        }
+    }
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            theme: iced::Theme::default().to_string(),
+            window_width: 1200.0,
+            window_height: 640.0,
+        }
+    }
+}
+
+impl DitherConfig {
+    pub fn mode(&self, format: cpal::SampleFormat) -> DitherMode {
+        match format {
+            cpal::SampleFormat::U8 => self.u8,
+            cpal::SampleFormat::I8 => self.i8,
+            cpal::SampleFormat::I16 => self.i16,
+            cpal::SampleFormat::U16 => self.u16,
+            cpal::SampleFormat::I24 => self.i24,
+            _ => DitherMode::Off,
+        }
+    }
+}
+
+impl AudioOverrides {
+    pub fn apply(&self, audio: &mut AudioConfig) {
+        if self.device.is_some() {
+            audio.device = self.device.clone();
+        }
+
+        if self.buffer_size.is_some() {
+            audio.buffer_size = self.buffer_size;
+        }
+    }
+}
+
+impl Config {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("cozy-music").join("config.toml"))
+    }
+
+    /// Falls back to the defaults when the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let config: Self = match fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => Self::default(),
+            Err(err) => return Err(err.into()),
+        };
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Reports every problem at once rather than stopping at the first.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        let audio = &self.audio;
+
+        if !(0.0..=1.0).contains(&audio.volume) {
+            problems.push("audio.volume must be between 0 and 1".to_string());
+        }
+
+        if !(0.5..=2.0).contains(&audio.speed) {
+            problems.push("audio.speed must be between 0.5 and 2".to_string());
+        }
+
+        if let Some(rate) = audio.sample_rate
+            && !(8_000..=768_000).contains(&rate)
+        {
+            problems.push(format!("audio.sample_rate {rate} is not a usable rate"));
+        }
+
+        if let Some(size) = audio.buffer_size
+            && !BUFFER_SIZES.contains(&size)
+        {
+            problems.push("audio.buffer_size must be between 16 and 16384 frames".to_string());
+        }
+
+        if self.playback.resume_threshold < 0.0 {
+            problems.push("playback.resume_threshold must not be negative".to_string());
+        }
+
+        if !iced::Theme::ALL
+            .iter()
+            .any(|theme| theme.to_string() == self.ui.theme)
+        {
+            problems.push(format!(
+                "ui.theme \"{}\" is not a known theme",
+                self.ui.theme
+            ));
+        }
+
+        if self.ui.window_width < 200.0 || self.ui.window_height < 200.0 {
+            problems.push("ui.window_width and ui.window_height must be at least 200".to_string());
+        }
+
+        problems.extend(self.keybindings.problems());
+
+        if let Some(remote) = &self.remote
+            && remote.token.trim().is_empty()
+        {
+            problems.push("remote.token must not be empty".to_string());
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigError::Invalid(problems)),
+        }
+    }
+
+    /// Stores the output device picked in the app, leaving the rest of the file as written.
+    pub fn save_output_device(path: &Path, device: &DeviceId) -> Result<(), ConfigError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut document: toml_edit::DocumentMut = content.parse()?;
+        let audio = document
+            .entry("audio")
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| ConfigError::Invalid(vec!["audio must be a table".to_string()]))?;
+
+        audio.insert("host", toml_edit::value(&device.host));
+        audio.insert("device", toml_edit::value(&device.name));
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, document.to_string())?;
+        Ok(())
+    }
+
+    pub fn library_folders(&self) -> Vec<PathBuf> {
+        match self.library.folders.is_empty() {
+            true => Library::default_folders(),
+            false => self.library.folders.clone(),
+        }
+    }
+}
+
+impl UiConfig {
+    pub fn theme(&self) -> iced::Theme {
+        iced::Theme::ALL
+            .iter()
+            .find(|theme| theme.to_string() == self.theme)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,17 @@
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Toml(#[from] toml::de::Error),
+
+    #[error("{0}")]
+    TomlEdit(#[from] toml_edit::TomlError),
+
+    #[error("{0}")]
+    Watch(#[from] notify::Error),
+
+    #[error("Invalid settings: {}.", .0.join("; "))]
+    Invalid(Vec<String>),
+}
@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Named keys, spelled like `iced`'s named keys in lowercase without the `arrow` prefix.
+const NAMED_KEYS: &[&str] = &[
+    "space",
+    "enter",
+    "escape",
+    "tab",
+    "backspace",
+    "delete",
+    "insert",
+    "home",
+    "end",
+    "pageup",
+    "pagedown",
+    "up",
+    "down",
+    "left",
+    "right",
+    "f1",
+    "f2",
+    "f3",
+    "f4",
+    "f5",
+    "f6",
+    "f7",
+    "f8",
+    "f9",
+    "f10",
+    "f11",
+    "f12",
+    "mediaplaypause",
+    "mediastop",
+    "mediatracknext",
+    "mediatrackprevious",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    PlayPause,
+    Stop,
+    Next,
+    Previous,
+    VolumeUp,
+    VolumeDown,
+    SeekForward,
+    SeekBackward,
+}
+
+/// A key with optional modifiers, written like `ctrl+shift+n`, `space` or `f5`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyBinding {
+    pub key: String,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub logo: bool,
+}
+
+/// Actions bound in the config file replace their default binding, the rest keep it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    from = "BTreeMap<KeyAction, KeyBinding>",
+    into = "BTreeMap<KeyAction, KeyBinding>"
+)]
+pub struct Keybindings(BTreeMap<KeyAction, KeyBinding>);
+
+impl KeyAction {
+    /// How the action is spelled in the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyAction::PlayPause => "play_pause",
+            KeyAction::Stop => "stop",
+            KeyAction::Next => "next",
+            KeyAction::Previous => "previous",
+            KeyAction::VolumeUp => "volume_up",
+            KeyAction::VolumeDown => "volume_down",
+            KeyAction::SeekForward => "seek_forward",
+            KeyAction::SeekBackward => "seek_backward",
+        }
+    }
+}
+
+impl KeyBinding {
+    pub fn new(key: &str) -> Self {
+        Self {
+            key: key.to_lowercase(),
+            ctrl: false,
+            shift: false,
+            alt: false,
+            logo: false,
+        }
+    }
+
+    /// Shift is left out for symbols, which are typed with it or not depending on the
+    /// layout, so `ctrl++` matches however `+` is typed.
+    pub fn shift_matters(key: &str) -> bool {
+        key.chars().count() != 1 || key.chars().all(char::is_alphanumeric)
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = value.split('+').map(str::trim).collect();
+        let (key, modifiers) = parts.split_last().ok_or("empty key binding")?;
+
+        // `ctrl++` binds the plus key.
+        let key = match (*key, modifiers.last()) {
+            ("", Some(&"")) => "+",
+            (key, _) => key,
+        };
+        let modifiers = match key {
+            "+" => &modifiers[..modifiers.len() - 1],
+            _ => modifiers,
+        };
+
+        let mut binding = KeyBinding::new(key);
+
+        if binding.key.chars().count() != 1 && !NAMED_KEYS.contains(&binding.key.as_str()) {
+            return Err(format!("unknown key \"{key}\" in \"{value}\""));
+        }
+
+        for modifier in modifiers {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => binding.ctrl = true,
+                "shift" => binding.shift = Self::shift_matters(&binding.key),
+                "alt" => binding.alt = true,
+                "super" | "logo" | "cmd" => binding.logo = true,
+                _ => return Err(format!("unknown modifier \"{modifier}\" in \"{value}\"")),
+            }
+        }
+
+        Ok(binding)
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.ctrl, "ctrl+"),
+            (self.shift, "shift+"),
+            (self.alt, "alt+"),
+            (self.logo, "super+"),
+        ];
+
+        for (_, name) in modifiers.iter().filter(|(on, _)| *on) {
+            f.write_str(name)?;
+        }
+
+        f.write_str(&self.key)
+    }
+}
+
+impl From<KeyBinding> for String {
+    fn from(value: KeyBinding) -> Self {
+        value.to_string()
+    }
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        let defaults = [
+            (KeyAction::PlayPause, "space"),
+            (KeyAction::Stop, "s"),
+            (KeyAction::Next, "ctrl+right"),
+            (KeyAction::Previous, "ctrl+left"),
+            (KeyAction::VolumeUp, "up"),
+            (KeyAction::VolumeDown, "down"),
+            (KeyAction::SeekForward, "right"),
+            (KeyAction::SeekBackward, "left"),
+        ];
+
+        Self(
+            defaults
+                .into_iter()
+                .filter_map(|(action, key)| Some((action, key.parse().ok()?)))
+                .collect(),
+        )
+    }
+}
+
+impl From<BTreeMap<KeyAction, KeyBinding>> for Keybindings {
+    fn from(bindings: BTreeMap<KeyAction, KeyBinding>) -> Self {
+        let mut merged = Self::default();
+        merged.0.extend(bindings);
+        merged
+    }
+}
+
+impl From<Keybindings> for BTreeMap<KeyAction, KeyBinding> {
+    fn from(value: Keybindings) -> Self {
+        value.0
+    }
+}
+
+impl Keybindings {
+    pub fn action(&self, binding: &KeyBinding) -> Option<KeyAction> {
+        self.0
+            .iter()
+            .find(|(_, bound)| *bound == binding)
+            .map(|(action, _)| *action)
+    }
+
+    /// Keys bound to more than one action.
+    pub(super) fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (idx, (action, binding)) in self.0.iter().enumerate() {
+            if let Some((other, _)) = self.0.iter().skip(idx + 1).find(|(_, b)| *b == binding) {
+                problems.push(format!(
+                    "keybindings: \"{binding}\" is bound to both {} and {}",
+                    action.name(),
+                    other.name()
+                ));
+            }
+        }
+
+        problems
+    }
+}
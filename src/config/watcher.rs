@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{DebounceEventResult, Debouncer, RecommendedCache, new_debouncer};
+
+use super::{Config, ConfigError};
+
+/// Editors often save in several steps, wait for them to settle.
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Reloads the config file whenever it changes, for as long as it is alive.
+pub struct ConfigWatcher {
+    _debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+}
+
+impl ConfigWatcher {
+    pub fn spawn<F>(path: PathBuf, mut on_change: F) -> Result<Self, ConfigError>
+    where
+        F: FnMut(Result<Config, ConfigError>) + Send + 'static,
+    {
+        // Watching the folder catches editors that replace the file instead of writing to it.
+        let dir = path
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."));
+        std::fs::create_dir_all(&dir)?;
+
+        let mut debouncer = new_debouncer(
+            DEBOUNCE_TIMEOUT,
+            None,
+            move |result: DebounceEventResult| match result {
+                Ok(events) => {
+                    if events.iter().any(|e| e.paths.contains(&path)) {
+                        on_change(Config::load(&path));
+                    }
+                }
+                Err(errors) => {
+                    for err in errors {
+                        on_change(Err(err.into()));
+                    }
+                }
+            },
+        )?;
+
+        debouncer.watch(&dir, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _debouncer: debouncer,
+        })
+    }
+}
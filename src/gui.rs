@@ -1,42 +1,246 @@
-use iced::widget::{Column, Text, column};
-use iced::{Center, Element, Subscription, Task};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use iced::futures::SinkExt;
+use iced::widget::{button, column, stack, text};
+use iced::{Center, Element, Fill, Subscription, Task, Theme, time};
+
+mod config;
 mod events;
+#[cfg(unix)]
+mod ipc;
+#[cfg(target_os = "linux")]
+mod mpris;
+mod remote;
+mod session;
 mod widgets;
 
+use crate::config::{AudioOverrides, Config};
 use crate::gui::events::AppEvent;
 use crate::gui::widgets::gen_svg_icon;
-use crate::gui::widgets::player::PlayerWidget;
-use crate::player::AudioController;
+use crate::gui::widgets::library::{LibraryWidget, LibraryWidgetEvent};
+use crate::gui::widgets::player::{PlayerWidget, PlayerWidgetEvent};
+use crate::gui::widgets::smart_playlist::SmartPlaylistEvent;
+use crate::gui::widgets::tag_editor::TagEditorEvent;
+use crate::gui::widgets::toasts::Toasts;
+#[cfg(unix)]
+use crate::ipc::Ipc;
+use crate::library::event::LibraryEvent;
+use crate::library::{Library, LibraryWatcher, PlayRecord};
+#[cfg(target_os = "linux")]
+use crate::mpris::Mpris;
+use crate::player::{AudioController, AudioError};
+use crate::playlist::{PlaylistEntry, PlaylistFormat};
+use crate::remote::Remote;
+use crate::scrobble::{Listen, ListenQueue, Scrobbler};
+use crate::session::Session;
+
+/// How often listens queued during an outage are sent again.
+const SCROBBLE_RETRY_INTERVAL: Duration = Duration::from_secs(300);
 
-pub fn run() -> Result<(), iced::Error> {
+/// `input` may be an audio file to play or a playlist to load into the queue. The
+/// overridden device is used until another one is picked.
+pub fn run(input: Option<String>, overrides: AudioOverrides) -> Result<(), iced::Error> {
     tracing_subscriber::fmt::init();
 
+    let config_path = Config::default_path();
+    let mut config = load_config(config_path.as_deref());
+
+    overrides.apply(&mut config.audio);
+
+    let window_size = (config.ui.window_width, config.ui.window_height);
+
     iced::application("Cozy music", CozyApp::update, CozyApp::view)
         .subscription(CozyApp::subscription)
-        .window_size((1200.0, 640.0))
-        .run()
+        .theme(CozyApp::theme)
+        .window_size(window_size)
+        .exit_on_close_request(false)
+        .run_with(move || CozyApp::new(config, config_path, overrides, input))
 }
 
 pub struct CozyApp {
+    config: Config,
+    config_path: Option<PathBuf>,
+    /// From the command line, kept across config reloads.
+    overrides: AudioOverrides,
+    library: Library,
+    /// Set once the folders are scanned, the watcher only starts then so the scan can't
+    /// replace what it has already applied.
+    library_scanned: bool,
+    library_widget: LibraryWidget,
     player: Option<AudioController>,
+    /// Why there is no player, shown in its place.
+    player_error: Option<AudioError>,
     player_widget: PlayerWidget,
+    toasts: Toasts,
+    scrobbler: Option<Scrobbler>,
+    session_path: Option<PathBuf>,
+    /// The last session written to disk, to skip saves when nothing changed.
+    saved_session: Option<Session>,
+    remote: Option<Remote>,
+    #[cfg(unix)]
+    ipc: Option<Ipc>,
+    #[cfg(target_os = "linux")]
+    mpris: Option<Mpris>,
 }
 
-impl Default for CozyApp {
-    fn default() -> Self {
-        Self {
-            player: AudioController::create().ok(),
+impl CozyApp {
+    const LOGO: &[u8] = include_bytes!("assets/logo.svg");
+
+    fn new(
+        config: Config,
+        config_path: Option<PathBuf>,
+        overrides: AudioOverrides,
+        input: Option<String>,
+    ) -> (Self, Task<AppEvent>) {
+        let mut app = Self {
+            library: Library::new(config.library_folders())
+                .with_history(Library::default_history_path()),
+            library_scanned: false,
+            library_widget: LibraryWidget::default(),
+            player: None,
+            player_error: None,
             player_widget: PlayerWidget::default(),
+            toasts: Toasts::default(),
+            scrobbler: load_scrobbler(&config),
+            session_path: Session::default_path(),
+            saved_session: None,
+            remote: None,
+            #[cfg(unix)]
+            ipc: None,
+            #[cfg(target_os = "linux")]
+            mpris: None,
+            config,
+            config_path,
+            overrides,
+        };
+        app.player_widget
+            .set_resume_threshold(app.config.playback.resume_threshold);
+
+        let scan = app.load_library();
+        let restore = app.start_player(input.is_some());
+
+        let open = match input {
+            Some(path) => Task::done(open_event(PathBuf::from(path)).into()),
+            None => Task::none(),
+        };
+
+        // Send whatever was queued while offline last time.
+        let flush = app.flush_scrobbles();
+
+        (app, Task::batch([scan, restore, open, flush]))
+    }
+
+    /// Opens the audio output, keeping the error to show when that fails. The last session is
+    /// restored once there is a player to restore it into, see `restore_session`.
+    fn start_player(&mut self, has_input: bool) -> Task<AppEvent> {
+        match AudioController::create(&self.config.audio) {
+            Ok(player) => {
+                let first = self.player.replace(player).is_none();
+                self.player_error = None;
+
+                if first {
+                    return self.restore_session(has_input);
+                }
+            }
+            Err(err) => {
+                log::error!("Could not start audio playback: {err}");
+                self.player_error = Some(err);
+            }
         }
+
+        Task::none()
     }
-}
 
-impl CozyApp {
-    const LOGO: &[u8] = include_bytes!("assets/logo.svg");
+    /// Reads the history and scans the folders of the current library.
+    fn load_library(&mut self) -> Task<AppEvent> {
+        let library = self.library.clone();
+        self.library_scanned = false;
+
+        Task::perform(
+            async move {
+                if let Err(err) = library.load_history() {
+                    log::warn!("Could not load the listening history: {err}");
+                }
+
+                library.scan()
+            },
+            |res| match res {
+                Ok(count) => LibraryEvent::Scanned(count),
+                Err(err) => err.into(),
+            },
+        )
+        .map(AppEvent::Library)
+    }
 
     pub fn update(&mut self, event: AppEvent) -> Task<AppEvent> {
+        let seeked = matches!(event, AppEvent::Player(PlayerWidgetEvent::Seek(_)));
+        let task = self.handle(event);
+
+        self.publish_status(seeked);
+
+        #[cfg(target_os = "linux")]
+        let task = Task::batch([task, self.sync_mpris(seeked)]);
+
+        task
+    }
+
+    fn handle(&mut self, event: AppEvent) -> Task<AppEvent> {
         match event {
+            AppEvent::Player(PlayerWidgetEvent::SaveQueueRequested) => {
+                let library = self.library.read();
+                let entries = self
+                    .player_widget
+                    .queue()
+                    .tracks()
+                    .iter()
+                    .map(|path| match library.get_by_path(path) {
+                        Some(track) => PlaylistEntry::from(track),
+                        None => PlaylistEntry::from(path.clone()),
+                    })
+                    .collect();
+
+                return Task::done(PlayerWidgetEvent::SaveQueue(entries).into());
+            }
+            AppEvent::Player(PlayerWidgetEvent::Played(record)) => {
+                let scrobble = self.scrobble(&record);
+                let library = self.library.clone();
+
+                let recorded = Task::perform(async move { library.record_play(record) }, |res| {
+                    AppEvent::PlayRecorded(res.map_err(Arc::new))
+                });
+
+                return Task::batch([recorded, scrobble]);
+            }
+            AppEvent::PlayRecorded(res) => {
+                if let Err(err) = res {
+                    self.toasts
+                        .push(format!("Could not save the listening history: {err}"));
+                }
+
+                return self
+                    .library_widget
+                    .library_changed(&self.library.read())
+                    .map(AppEvent::LibraryView);
+            }
+            AppEvent::Player(PlayerWidgetEvent::SelectDevice(device)) => {
+                self.select_output(device);
+            }
+            AppEvent::Player(PlayerWidgetEvent::NowPlaying(path)) => {
+                return self.now_playing(&path);
+            }
+            AppEvent::Player(PlayerWidgetEvent::Error(path, err)) => {
+                self.toasts
+                    .push(format!("Could not play {}: {err}", path.display()));
+
+                if let Some(player) = self.player.as_ref() {
+                    return self
+                        .player_widget
+                        .update(player, PlayerWidgetEvent::Error(path, err))
+                        .map(AppEvent::Player);
+                }
+            }
             AppEvent::Player(event) => {
                 if let Some(player) = self.player.as_ref() {
                     return self
@@ -45,24 +249,276 @@ impl CozyApp {
                         .map(AppEvent::Player);
                 }
             }
+            AppEvent::Library(LibraryEvent::Error(err)) => self.toasts.push(err.to_string()),
+            AppEvent::Library(event) => {
+                if let LibraryEvent::Scanned(_) = event {
+                    self.library_scanned = true;
+                }
+
+                return self
+                    .library_widget
+                    .library_changed(&self.library.read())
+                    .map(AppEvent::LibraryView);
+            }
+            AppEvent::LibraryView(LibraryWidgetEvent::Play(path)) => {
+                return Task::done(PlayerWidgetEvent::PlayFile(path).into());
+            }
+            AppEvent::LibraryView(LibraryWidgetEvent::Enqueue(path)) => {
+                return Task::done(PlayerWidgetEvent::Enqueue(path).into());
+            }
+            AppEvent::LibraryView(LibraryWidgetEvent::Playlist(SmartPlaylistEvent::Play(
+                paths,
+            ))) => {
+                return Task::done(PlayerWidgetEvent::PlayAll(paths).into());
+            }
+            AppEvent::LibraryView(LibraryWidgetEvent::Editor(TagEditorEvent::Write(
+                paths,
+                edit,
+            ))) => {
+                let library = self.library.clone();
+
+                return Task::perform(async move { library.edit_tags(&paths, &edit) }, |e| e)
+                    .then(|events| Task::batch(events.into_iter().map(Task::done)))
+                    .map(AppEvent::Library);
+            }
+            AppEvent::LibraryView(event) => {
+                return self
+                    .library_widget
+                    .update(event, &self.library.read())
+                    .map(AppEvent::LibraryView);
+            }
+            AppEvent::Toast(event) => self.toasts.update(event),
+            AppEvent::RetryAudio => return self.start_player(false),
+            AppEvent::FlushScrobbles => return self.flush_scrobbles(),
+            AppEvent::ScrobblesSent(res) => match res {
+                Ok(0) => {}
+                Ok(sent) => log::info!("Submitted {sent} queued listens"),
+                // Listens stay queued until the token is fixed, which only the user can do.
+                Err(err) if err.is_auth_failure() => self.toasts.push(format!(
+                    "The scrobble server refused the token, listens stay queued: {err}"
+                )),
+                Err(err) => log::warn!("Listens queued, could not submit them yet: {err}"),
+            },
+            AppEvent::Config(event) => return self.on_config(event),
+            AppEvent::Remote(event) => return self.on_remote(event),
+            AppEvent::Session(event) => return self.on_session(event),
+            #[cfg(unix)]
+            AppEvent::Ipc(event) => return self.on_ipc(event),
+            #[cfg(target_os = "linux")]
+            AppEvent::Mpris(event) => return self.on_mpris(event),
         }
 
         Task::none()
     }
 
+    /// Shares the player's state with remote clients and other instances after every update.
+    fn publish_status(&self, seeked: bool) {
+        let Some(player) = self.player.as_ref() else {
+            return;
+        };
+
+        #[cfg(unix)]
+        if let Some(ipc) = self.ipc.as_ref() {
+            ipc.update_status(self.player_widget.remote_status(player));
+        }
+
+        if let Some(remote) = self.remote.as_ref() {
+            remote.update_status(self.player_widget.remote_status(player), seeked);
+            remote.update_queue(self.player_widget.queue());
+        }
+    }
+
+    /// Like listens, only sent for tracks in the library.
+    fn now_playing(&self, path: &Path) -> Task<AppEvent> {
+        let Some(scrobbler) = self.scrobbler.clone() else {
+            return Task::none();
+        };
+        let listen = self
+            .library
+            .read()
+            .get_by_path(path)
+            .and_then(|track| Listen::from_track(track, unix_now()));
+        let Some(listen) = listen else {
+            return Task::none();
+        };
+
+        Task::perform(async move { scrobbler.now_playing(&listen) }, |res| {
+            if let Err(err) = res {
+                log::warn!("Could not submit now playing: {err}");
+            }
+        })
+        .discard()
+    }
+
+    fn scrobble(&self, record: &PlayRecord) -> Task<AppEvent> {
+        let Some(scrobbler) = self.scrobbler.clone() else {
+            return Task::none();
+        };
+
+        let library = self.library.read();
+        let listen = library
+            .get(record.track)
+            .filter(|track| Listen::should_scrobble(track.duration, record.listened))
+            .and_then(|track| Listen::from_track(track, record.started_at));
+
+        let Some(listen) = listen else {
+            return Task::none();
+        };
+
+        Task::perform(async move { scrobbler.scrobble(listen) }, |res| {
+            AppEvent::ScrobblesSent(res.map_err(Arc::new))
+        })
+    }
+
+    fn flush_scrobbles(&self) -> Task<AppEvent> {
+        let Some(scrobbler) = self.scrobbler.clone() else {
+            return Task::none();
+        };
+
+        Task::perform(async move { scrobbler.flush() }, |res| {
+            AppEvent::ScrobblesSent(res.map_err(Arc::new))
+        })
+    }
+
     fn subscription(&self) -> Subscription<AppEvent> {
-        PlayerWidget::subscription().map(AppEvent::Player)
+        let mut subscriptions = vec![
+            PlayerWidget::subscription().map(AppEvent::Player),
+            config::keys(),
+            session::subscription(),
+            self.toasts.subscription().map(AppEvent::Toast),
+        ];
+
+        if self.library_scanned {
+            subscriptions.push(watch_library(&self.library).map(AppEvent::Library));
+        }
+
+        if let Some(player) = self.player.as_ref() {
+            subscriptions.push(PlayerWidget::output_subscription(player).map(AppEvent::Player));
+        }
+
+        if self.scrobbler.is_some() {
+            subscriptions
+                .push(time::every(SCROBBLE_RETRY_INTERVAL).map(|_| AppEvent::FlushScrobbles));
+        }
+
+        if let Some(path) = self.config_path.clone() {
+            subscriptions.push(config::subscription(path));
+        }
+
+        if let Some(config) = self.config.remote.as_ref() {
+            subscriptions.push(remote::subscription(config, &self.library));
+        }
+
+        #[cfg(unix)]
+        subscriptions.push(ipc::subscription());
+
+        #[cfg(target_os = "linux")]
+        subscriptions.push(mpris::subscription());
+
+        Subscription::batch(subscriptions)
+    }
+
+    fn theme(&self) -> Theme {
+        self.config.ui.theme()
     }
 
-    pub fn view(&self) -> Column<'_, AppEvent> {
-        let player_view: Element<_> = self
-            .player
-            .as_ref()
-            .map(|p| self.player_widget.view(p).map(AppEvent::Player))
-            .unwrap_or_else(|| Text::new("").into());
+    pub fn view(&self) -> Element<'_, AppEvent> {
+        let player_view: Element<_> = match self.player.as_ref() {
+            Some(player) => self.player_widget.view(player).map(AppEvent::Player),
+            None => self.audio_unavailable(),
+        };
 
-        column![player_view, gen_svg_icon(Self::LOGO)]
+        let library = self.library.read();
+        let library_view: Element<_> = match library.is_empty() {
+            true => gen_svg_icon(Self::LOGO).into(),
+            false => self
+                .library_widget
+                .view(&library)
+                .map(AppEvent::LibraryView),
+        };
+
+        let content = column![player_view, library_view]
             .padding(20)
-            .align_x(Center)
+            .spacing(20)
+            .height(Fill)
+            .align_x(Center);
+
+        stack![content, self.toasts.view().map(AppEvent::Toast)].into()
+    }
+
+    fn audio_unavailable(&self) -> Element<'_, AppEvent> {
+        let reason = match &self.player_error {
+            Some(err) => err.to_string(),
+            None => String::new(),
+        };
+
+        column![
+            text("Audio output unavailable").size(20),
+            text(reason).size(14).style(text::danger),
+            button("Retry").on_press(AppEvent::RetryAudio),
+        ]
+        .spacing(8)
+        .align_x(Center)
+        .into()
+    }
+}
+
+/// What to do with a file given on the command line or by another instance.
+fn open_event(path: PathBuf) -> PlayerWidgetEvent {
+    match PlaylistFormat::from_path(&path) {
+        Some(_) => PlayerWidgetEvent::OpenPlaylist(path),
+        None => PlayerWidgetEvent::PlayFile(path),
     }
 }
+
+/// Starts with the defaults when the file can't be used, so a typo doesn't keep the app closed.
+fn load_config(path: Option<&Path>) -> Config {
+    let Some(path) = path else {
+        return Config::default();
+    };
+
+    Config::load(path).unwrap_or_else(|err| {
+        log::warn!(
+            "Could not load {}, using the defaults: {err}",
+            path.display()
+        );
+        Config::default()
+    })
+}
+
+fn load_scrobbler(config: &Config) -> Option<Scrobbler> {
+    Some(Scrobbler::new(
+        config.scrobble.clone()?,
+        ListenQueue::new(ListenQueue::default_path()?),
+    ))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn watch_library(library: &Library) -> Subscription<LibraryEvent> {
+    let id = ("library-watcher", library.folders().to_vec());
+    let library = library.clone();
+
+    Subscription::run_with_id(
+        id,
+        iced::stream::channel(100, move |mut output| async move {
+            let mut sender = output.clone();
+            // Waits for room rather than dropping events, which would leave the index stale.
+            let watcher = LibraryWatcher::spawn(library, move |event| {
+                iced::futures::executor::block_on(sender.send(event)).ok();
+            });
+
+            match watcher {
+                Ok(_watcher) => std::future::pending().await,
+                Err(err) => {
+                    output.send(err.into()).await.ok();
+                }
+            }
+        }),
+    )
+}
@@ -0,0 +1,242 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use iced::futures::SinkExt;
+use iced::keyboard::{self, Key, Modifiers};
+use iced::{Subscription, Task};
+
+use super::events::AppEvent;
+use super::widgets::player::{PlayerWidget, PlayerWidgetEvent};
+use super::{CozyApp, load_scrobbler};
+use crate::config::{AudioConfig, Config, ConfigError, ConfigWatcher, KeyAction, KeyBinding};
+use crate::library::Library;
+use crate::player::DeviceId;
+use crate::player::event::AtomicEvent;
+
+/// How far the seek keys jump, in seconds.
+const SEEK_STEP: f64 = 5.0;
+const VOLUME_STEP: f32 = 0.05;
+
+#[derive(Debug, Clone)]
+pub enum ConfigEvent {
+    Reloaded(Box<Config>),
+    Error(Arc<ConfigError>),
+    KeyPressed(KeyBinding),
+}
+
+impl From<ConfigEvent> for AppEvent {
+    fn from(val: ConfigEvent) -> Self {
+        AppEvent::Config(val)
+    }
+}
+
+impl CozyApp {
+    pub(super) fn on_config(&mut self, event: ConfigEvent) -> Task<AppEvent> {
+        match event {
+            ConfigEvent::Reloaded(mut config) => {
+                self.overrides.apply(&mut config.audio);
+                self.apply_config(*config)
+            }
+            ConfigEvent::Error(err) => {
+                self.toasts
+                    .push(format!("Keeping the current settings: {err}"));
+                Task::none()
+            }
+            ConfigEvent::KeyPressed(binding) => self.on_key(&binding),
+        }
+    }
+
+    /// Applies what can change while running.
+    fn apply_config(&mut self, config: Config) -> Task<AppEvent> {
+        let old = std::mem::replace(&mut self.config, config);
+        log::info!("Settings reloaded");
+
+        let output = |c: &AudioConfig| {
+            (
+                c.host.clone(),
+                c.device.clone(),
+                c.sample_rate,
+                c.buffer_size,
+                c.bit_perfect,
+                c.dither.clone(),
+            )
+        };
+
+        let restore = match self.player.as_ref() {
+            Some(player) => {
+                if output(&old.audio) != output(&self.config.audio)
+                    && let Err(err) = player.switch_output(&self.config.audio)
+                {
+                    self.toasts
+                        .push(format!("Could not switch the output device: {err}"));
+                }
+
+                Task::none()
+            }
+            // The new settings may name a device that works.
+            None => self.start_player(false),
+        };
+
+        let new = &self.config;
+
+        if let Some(player) = self.player.as_ref() {
+            if old.audio.resampler != new.audio.resampler {
+                player.set_resampler_quality(new.audio.resampler);
+            }
+
+            if old.audio.volume != new.audio.volume {
+                player.send_event(AtomicEvent::SetVolume(new.audio.volume));
+            }
+
+            if old.audio.speed != new.audio.speed {
+                player.send_event(AtomicEvent::SetSpeed(new.audio.speed));
+            }
+        }
+
+        self.player_widget
+            .set_resume_threshold(new.playback.resume_threshold);
+
+        if old.scrobble != new.scrobble {
+            self.scrobbler = load_scrobbler(new);
+        }
+
+        // The subscription restarts the server under the new settings.
+        if old.remote != new.remote {
+            self.remote = None;
+        }
+
+        if old.library_folders() == new.library_folders() {
+            return restore;
+        }
+
+        self.library =
+            Library::new(new.library_folders()).with_history(Library::default_history_path());
+
+        Task::batch([restore, self.load_library()])
+    }
+
+    /// Moves playback to a device picked in the app and saves the choice.
+    pub(super) fn select_output(&mut self, device: DeviceId) {
+        let Some(player) = self.player.as_ref() else {
+            return;
+        };
+
+        let settings = AudioConfig {
+            host: Some(device.host.clone()),
+            device: Some(device.name.clone()),
+            ..self.config.audio.clone()
+        };
+
+        if let Err(err) = player.switch_output(&settings) {
+            self.toasts
+                .push(format!("Could not switch to {device}: {err}"));
+            return;
+        }
+
+        self.config.audio = settings;
+        self.overrides.device = None;
+
+        if let Some(path) = self.config_path.as_deref()
+            && let Err(err) = Config::save_output_device(path, &device)
+        {
+            log::warn!("Could not save the output device: {err}");
+        }
+    }
+
+    fn on_key(&self, binding: &KeyBinding) -> Task<AppEvent> {
+        let (Some(action), Some(player)) = (
+            self.config.keybindings.action(binding),
+            self.player.as_ref(),
+        ) else {
+            return Task::none();
+        };
+
+        let (position, length) = PlayerWidget::timing(player);
+        let seek = |step: f64| match length > 0.0 {
+            true => Some(PlayerWidgetEvent::SeekSeconds(
+                (position + step).clamp(0.0, length),
+            )),
+            false => None,
+        };
+
+        let event = match action {
+            KeyAction::PlayPause => match player.get_is_playing() {
+                true => PlayerWidgetEvent::Pause,
+                false => PlayerWidgetEvent::Play,
+            },
+            KeyAction::Stop => PlayerWidgetEvent::Stop,
+            KeyAction::Next => PlayerWidgetEvent::Next,
+            KeyAction::Previous => PlayerWidgetEvent::Previous,
+            KeyAction::VolumeUp => {
+                PlayerWidgetEvent::Volume((player.get_volume() + VOLUME_STEP).min(1.0))
+            }
+            KeyAction::VolumeDown => {
+                PlayerWidgetEvent::Volume((player.get_volume() - VOLUME_STEP).max(0.0))
+            }
+            KeyAction::SeekForward => match seek(SEEK_STEP) {
+                Some(event) => event,
+                None => return Task::none(),
+            },
+            KeyAction::SeekBackward => match seek(-SEEK_STEP) {
+                Some(event) => event,
+                None => return Task::none(),
+            },
+        };
+
+        Task::done(event.into())
+    }
+}
+
+/// Reloads the settings whenever the config file is saved.
+pub fn subscription(path: PathBuf) -> Subscription<AppEvent> {
+    Subscription::run_with_id(
+        ("config-watcher", path.clone()),
+        iced::stream::channel(10, move |mut output| async move {
+            let mut sender = output.clone();
+            let watcher = ConfigWatcher::spawn(path, move |result| {
+                let event = match result {
+                    Ok(config) => ConfigEvent::Reloaded(Box::new(config)),
+                    Err(err) => ConfigEvent::Error(Arc::new(err)),
+                };
+                sender.try_send(event.into()).ok();
+            });
+
+            match watcher {
+                Ok(_watcher) => std::future::pending().await,
+                Err(err) => {
+                    output
+                        .send(ConfigEvent::Error(Arc::new(err)).into())
+                        .await
+                        .ok();
+                }
+            }
+        }),
+    )
+}
+
+/// Key presses that no widget, such as a text field, took for itself.
+pub fn keys() -> Subscription<AppEvent> {
+    keyboard::on_key_press(|key, modifiers| {
+        Some(ConfigEvent::KeyPressed(key_binding(key, modifiers)?).into())
+    })
+}
+
+/// Names keys the way the config file spells them.
+fn key_binding(key: Key, modifiers: Modifiers) -> Option<KeyBinding> {
+    let name = match key {
+        Key::Character(c) => c.to_lowercase(),
+        Key::Named(named) => {
+            let name = format!("{named:?}").to_lowercase();
+            name.strip_prefix("arrow").map(String::from).unwrap_or(name)
+        }
+        Key::Unidentified => return None,
+    };
+
+    Some(KeyBinding {
+        shift: modifiers.shift() && KeyBinding::shift_matters(&name),
+        key: name,
+        ctrl: modifiers.control(),
+        alt: modifiers.alt(),
+        logo: modifiers.logo(),
+    })
+}
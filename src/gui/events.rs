@@ -1,6 +1,38 @@
+use std::sync::Arc;
+
+use super::config::ConfigEvent;
+#[cfg(unix)]
+use super::ipc::IpcEvent;
+#[cfg(target_os = "linux")]
+use super::mpris::MprisEvent;
+use super::remote::RemoteEvent;
+use super::session::SessionEvent;
+use super::widgets::library::LibraryWidgetEvent;
 use super::widgets::player::PlayerWidgetEvent;
+use super::widgets::toasts::ToastEvent;
+use crate::library::LibraryError;
+use crate::library::event::LibraryEvent;
+use crate::scrobble::ScrobbleError;
 
 #[derive(Debug, Clone)]
 pub enum AppEvent {
     Player(PlayerWidgetEvent),
+    Library(LibraryEvent),
+    LibraryView(LibraryWidgetEvent),
+    Config(ConfigEvent),
+    Remote(RemoteEvent),
+    Session(SessionEvent),
+    Toast(ToastEvent),
+    /// Try opening the audio output again after it failed at startup.
+    RetryAudio,
+    /// Send listens that are still queued.
+    FlushScrobbles,
+    /// How many queued listens were submitted, or why none could be.
+    ScrobblesSent(Result<usize, Arc<ScrobbleError>>),
+    /// A play was written to the history, or failed to be.
+    PlayRecorded(Result<(), Arc<LibraryError>>),
+    #[cfg(unix)]
+    Ipc(IpcEvent),
+    #[cfg(target_os = "linux")]
+    Mpris(MprisEvent),
 }
@@ -0,0 +1,82 @@
+use iced::futures::SinkExt;
+use iced::{Subscription, Task, window};
+
+use super::CozyApp;
+use super::events::AppEvent;
+use super::widgets::player::PlayerWidgetEvent;
+use crate::ipc::{Ipc, IpcRequest};
+
+#[derive(Debug, Clone)]
+pub enum IpcEvent {
+    Started(Ipc),
+    Request(IpcRequest),
+}
+
+impl From<IpcEvent> for AppEvent {
+    fn from(val: IpcEvent) -> Self {
+        AppEvent::Ipc(val)
+    }
+}
+
+impl CozyApp {
+    pub(super) fn on_ipc(&mut self, event: IpcEvent) -> Task<AppEvent> {
+        let request = match event {
+            IpcEvent::Started(ipc) => {
+                self.ipc = Some(ipc);
+                return Task::none();
+            }
+            IpcEvent::Request(request) => request,
+        };
+
+        let events = match request {
+            IpcRequest::Play => vec![PlayerWidgetEvent::Play],
+            IpcRequest::Pause => vec![PlayerWidgetEvent::Pause],
+            IpcRequest::Toggle => match self.player.as_ref().is_some_and(|p| p.get_is_playing()) {
+                true => vec![PlayerWidgetEvent::Pause],
+                false => vec![PlayerWidgetEvent::Play],
+            },
+            IpcRequest::Stop => vec![PlayerWidgetEvent::Stop],
+            IpcRequest::Next => vec![PlayerWidgetEvent::Next],
+            IpcRequest::Previous => vec![PlayerWidgetEvent::Previous],
+            // Answered by the server from the published status.
+            IpcRequest::Status => Vec::new(),
+            IpcRequest::Enqueue { paths } => {
+                paths.into_iter().map(PlayerWidgetEvent::Enqueue).collect()
+            }
+            IpcRequest::Open { path } => vec![super::open_event(path)],
+            IpcRequest::Raise => {
+                return window::get_latest().and_then(|id| {
+                    Task::batch([window::minimize(id, false), window::gain_focus(id)])
+                });
+            }
+        };
+
+        Task::batch(events.into_iter().map(|event| Task::done(event.into())))
+    }
+}
+
+pub fn subscription() -> Subscription<AppEvent> {
+    Subscription::run_with_id(
+        "ipc",
+        iced::stream::channel(100, |mut output| async move {
+            let sender = output.clone();
+            let ipc = Ipc::bind(move |request| {
+                sender
+                    .clone()
+                    .try_send(IpcEvent::Request(request).into())
+                    .ok();
+            });
+
+            match ipc {
+                Ok((ipc, serve)) => {
+                    output.send(IpcEvent::Started(ipc).into()).await.ok();
+
+                    if let Err(err) = serve.await {
+                        log::warn!("Stopped listening for other instances: {err}");
+                    }
+                }
+                Err(err) => log::warn!("Could not listen for other instances: {err}"),
+            }
+        }),
+    )
+}
@@ -0,0 +1,109 @@
+use iced::futures::SinkExt;
+use iced::{Subscription, Task};
+
+use super::CozyApp;
+use super::events::AppEvent;
+use super::widgets::player::PlayerWidgetEvent;
+use crate::mpris::{Mpris, MprisCommand};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone)]
+pub enum MprisEvent {
+    Started(Mpris),
+    Command(MprisCommand),
+}
+
+impl From<MprisEvent> for AppEvent {
+    fn from(val: MprisEvent) -> Self {
+        AppEvent::Mpris(val)
+    }
+}
+
+impl CozyApp {
+    pub(super) fn on_mpris(&mut self, event: MprisEvent) -> Task<AppEvent> {
+        let command = match event {
+            MprisEvent::Started(mpris) => {
+                self.mpris = Some(mpris);
+                return Task::none();
+            }
+            MprisEvent::Command(command) => command,
+        };
+
+        let is_playing = self.player.as_ref().is_some_and(|p| p.get_is_playing());
+
+        match player_event(command, is_playing) {
+            Some(event) => Task::done(event.into()),
+            None => self.quit(),
+        }
+    }
+
+    /// Lets MPRIS clients know about whatever the last update changed.
+    pub(super) fn sync_mpris(&self, seeked: bool) -> Task<AppEvent> {
+        let (Some(mpris), Some(player)) = (self.mpris.as_ref(), self.player.as_ref()) else {
+            return Task::none();
+        };
+
+        let log_error = |res: zbus::Result<()>| {
+            if let Err(err) = res {
+                log::warn!("Could not notify MPRIS clients: {err}");
+            }
+        };
+
+        let changed = mpris
+            .update(self.player_widget.mpris_state(player))
+            .map(|announce| Task::perform(announce, log_error));
+        let seeked = seeked.then(|| Task::perform(mpris.seeked(), log_error));
+
+        Task::batch(changed.into_iter().chain(seeked)).discard()
+    }
+}
+
+/// What the player does for `command`, `None` for quitting the app.
+fn player_event(command: MprisCommand, is_playing: bool) -> Option<PlayerWidgetEvent> {
+    let event = match command {
+        MprisCommand::Quit => return None,
+        MprisCommand::Play => PlayerWidgetEvent::Play,
+        MprisCommand::Pause => PlayerWidgetEvent::Pause,
+        MprisCommand::PlayPause => match is_playing {
+            true => PlayerWidgetEvent::Pause,
+            false => PlayerWidgetEvent::Play,
+        },
+        MprisCommand::Stop => PlayerWidgetEvent::Stop,
+        MprisCommand::Next => PlayerWidgetEvent::Next,
+        MprisCommand::Previous => PlayerWidgetEvent::Previous,
+        MprisCommand::SeekSeconds(seconds) => PlayerWidgetEvent::SeekSeconds(seconds),
+        MprisCommand::Volume(volume) => PlayerWidgetEvent::Volume(volume),
+        MprisCommand::Rate(rate) => PlayerWidgetEvent::Speed(rate),
+        MprisCommand::LoopMode(mode) => PlayerWidgetEvent::LoopMode(mode),
+        MprisCommand::Shuffle(shuffle) => PlayerWidgetEvent::Shuffle(shuffle),
+        MprisCommand::Open(path) => PlayerWidgetEvent::PlayFile(path),
+    };
+
+    Some(event)
+}
+
+pub fn subscription() -> Subscription<AppEvent> {
+    Subscription::run_with_id(
+        "mpris",
+        iced::stream::channel(100, |mut output| async move {
+            let sender = output.clone();
+            let mpris = Mpris::start(move |command| {
+                sender
+                    .clone()
+                    .try_send(MprisEvent::Command(command).into())
+                    .ok();
+            })
+            .await;
+
+            match mpris {
+                Ok(mpris) => {
+                    output.send(MprisEvent::Started(mpris).into()).await.ok();
+                    std::future::pending().await
+                }
+                Err(err) => log::warn!("Could not register with MPRIS: {err}"),
+            }
+        }),
+    )
+}
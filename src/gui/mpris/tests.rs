@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+
+use super::*;
+use crate::player::LoopMode;
+
+#[test]
+fn quit_is_left_to_the_app() {
+    assert!(player_event(MprisCommand::Quit, true).is_none());
+}
+
+#[test]
+fn play_pause_toggles() {
+    assert!(matches!(
+        player_event(MprisCommand::PlayPause, true),
+        Some(PlayerWidgetEvent::Pause)
+    ));
+    assert!(matches!(
+        player_event(MprisCommand::PlayPause, false),
+        Some(PlayerWidgetEvent::Play)
+    ));
+}
+
+#[test]
+fn play_and_pause_ignore_the_current_state() {
+    assert!(matches!(
+        player_event(MprisCommand::Play, true),
+        Some(PlayerWidgetEvent::Play)
+    ));
+    assert!(matches!(
+        player_event(MprisCommand::Pause, false),
+        Some(PlayerWidgetEvent::Pause)
+    ));
+}
+
+#[test]
+fn values_are_passed_on() {
+    assert!(matches!(
+        player_event(MprisCommand::SeekSeconds(30.0), true),
+        Some(PlayerWidgetEvent::SeekSeconds(30.0))
+    ));
+    assert!(matches!(
+        player_event(MprisCommand::Volume(0.25), true),
+        Some(PlayerWidgetEvent::Volume(0.25))
+    ));
+    assert!(matches!(
+        player_event(MprisCommand::Rate(1.5), true),
+        Some(PlayerWidgetEvent::Speed(1.5))
+    ));
+}
+
+#[test]
+fn loop_mode_and_shuffle_are_passed_on() {
+    assert!(matches!(
+        player_event(MprisCommand::LoopMode(LoopMode::Playlist), false),
+        Some(PlayerWidgetEvent::LoopMode(LoopMode::Playlist))
+    ));
+    assert!(matches!(
+        player_event(MprisCommand::Shuffle(true), false),
+        Some(PlayerWidgetEvent::Shuffle(true))
+    ));
+}
+
+#[test]
+fn opening_plays_the_file() {
+    let path = PathBuf::from("/music/song.flac");
+
+    match player_event(MprisCommand::Open(path.clone()), false) {
+        Some(PlayerWidgetEvent::PlayFile(played)) => assert_eq!(played, path),
+        other => panic!("unexpected event {other:?}"),
+    }
+}
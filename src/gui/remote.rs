@@ -0,0 +1,97 @@
+use iced::futures::SinkExt;
+use iced::{Subscription, Task};
+
+use super::CozyApp;
+use super::events::AppEvent;
+use super::widgets::player::PlayerWidgetEvent;
+use crate::library::Library;
+use crate::remote::{Remote, RemoteCommand, RemoteConfig};
+
+#[derive(Debug, Clone)]
+pub enum RemoteEvent {
+    Started(Remote),
+    Command(RemoteCommand),
+}
+
+impl From<RemoteEvent> for AppEvent {
+    fn from(val: RemoteEvent) -> Self {
+        AppEvent::Remote(val)
+    }
+}
+
+impl CozyApp {
+    pub(super) fn on_remote(&mut self, event: RemoteEvent) -> Task<AppEvent> {
+        let command = match event {
+            RemoteEvent::Started(remote) => {
+                self.remote = Some(remote);
+                return Task::none();
+            }
+            RemoteEvent::Command(command) => command,
+        };
+
+        let event = match command {
+            RemoteCommand::Play => PlayerWidgetEvent::Play,
+            RemoteCommand::Pause => PlayerWidgetEvent::Pause,
+            RemoteCommand::Stop => PlayerWidgetEvent::Stop,
+            RemoteCommand::Next => PlayerWidgetEvent::Next,
+            RemoteCommand::Previous => PlayerWidgetEvent::Previous,
+            RemoteCommand::SeekSeconds(seconds) => PlayerWidgetEvent::SeekSeconds(seconds),
+            RemoteCommand::Volume(volume) => PlayerWidgetEvent::Volume(volume),
+            RemoteCommand::Speed(speed) => PlayerWidgetEvent::Speed(speed),
+            RemoteCommand::LoopMode(mode) => PlayerWidgetEvent::LoopMode(mode),
+            RemoteCommand::Shuffle(shuffle) => PlayerWidgetEvent::Shuffle(shuffle),
+            RemoteCommand::PlayAll(paths) => PlayerWidgetEvent::PlayAll(paths),
+            RemoteCommand::Enqueue(paths) => {
+                return Task::batch(
+                    paths
+                        .into_iter()
+                        .map(|path| Task::done(PlayerWidgetEvent::Enqueue(path).into())),
+                );
+            }
+            RemoteCommand::PlayIndex(idx) => PlayerWidgetEvent::PlayIndex(idx),
+            RemoteCommand::Remove(idx) => PlayerWidgetEvent::RemoveFromQueue(idx),
+            RemoteCommand::ClearQueue => PlayerWidgetEvent::ClearQueue,
+        };
+
+        Task::done(event.into())
+    }
+}
+
+pub fn subscription(config: &RemoteConfig, library: &Library) -> Subscription<AppEvent> {
+    // A library for other folders is a new one, which the server has to be restarted with.
+    let id = (
+        "remote",
+        config.bind,
+        config.token.clone(),
+        library.folders().to_vec(),
+    );
+    let config = config.clone();
+    let library = library.clone();
+
+    Subscription::run_with_id(
+        id,
+        iced::stream::channel(100, move |mut output| async move {
+            let sender = output.clone();
+            // Clients are told to retry when the app falls behind.
+            let remote = Remote::bind(&config, library, move |command| {
+                sender
+                    .clone()
+                    .try_send(RemoteEvent::Command(command).into())
+                    .is_ok()
+            })
+            .await;
+
+            match remote {
+                Ok((remote, serve)) => {
+                    log::info!("Remote control listening on {}", config.bind);
+                    output.send(RemoteEvent::Started(remote).into()).await.ok();
+
+                    if let Err(err) = serve.await {
+                        log::warn!("Remote control stopped: {err}");
+                    }
+                }
+                Err(err) => log::warn!("Could not start the remote control: {err}"),
+            }
+        }),
+    )
+}
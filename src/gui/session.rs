@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use iced::{Subscription, Task, time, window};
+
+use super::CozyApp;
+use super::events::AppEvent;
+use crate::session::Session;
+
+/// Often enough that a crash loses little, rarely enough to not matter for the disk.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone)]
+pub enum SessionEvent {
+    Autosave,
+    CloseRequested,
+}
+
+impl From<SessionEvent> for AppEvent {
+    fn from(val: SessionEvent) -> Self {
+        AppEvent::Session(val)
+    }
+}
+
+impl CozyApp {
+    pub(super) fn on_session(&mut self, event: SessionEvent) -> Task<AppEvent> {
+        match event {
+            SessionEvent::Autosave => self.autosave(),
+            SessionEvent::CloseRequested => self.quit(),
+        }
+    }
+
+    /// Restores the last session. With an `input` to open, the track and position are left
+    /// alone so the file plays instead.
+    pub(super) fn restore_session(&mut self, has_input: bool) -> Task<AppEvent> {
+        let (Some(path), Some(player)) = (self.session_path.as_ref(), self.player.as_ref()) else {
+            return Task::none();
+        };
+
+        let session = match Session::load(path) {
+            Ok(Some(session)) => session,
+            Ok(None) => return Task::none(),
+            Err(err) => {
+                log::warn!("Could not restore the last session: {err}");
+                return Task::none();
+            }
+        };
+
+        self.saved_session = Some(session.clone());
+        self.player_widget
+            .restore(player, session, !has_input)
+            .map(AppEvent::Player)
+    }
+
+    /// Also stores the position in a long track, so a crash doesn't lose it.
+    fn current_session(&mut self) -> Option<Session> {
+        let player = self.player.as_ref()?;
+        self.player_widget.remember_position(player);
+
+        Some(self.player_widget.session(player))
+    }
+
+    /// Saves in the background, and only when something changed since the last save.
+    fn autosave(&mut self) -> Task<AppEvent> {
+        let (Some(path), Some(session)) = (self.session_path.clone(), self.current_session())
+        else {
+            return Task::none();
+        };
+
+        if self.saved_session.as_ref() == Some(&session) {
+            return Task::none();
+        }
+
+        self.saved_session = Some(session.clone());
+
+        Task::perform(async move { session.save(&path) }, |res| {
+            if let Err(err) = res {
+                log::warn!("Could not save the session: {err}");
+            }
+        })
+        .discard()
+    }
+
+    /// Saves the session before exiting, however the app was asked to close.
+    pub(super) fn quit(&mut self) -> Task<AppEvent> {
+        if let (Some(session), Some(path)) = (self.current_session(), self.session_path.as_ref())
+            && let Err(err) = session.save(path)
+        {
+            log::warn!("Could not save the session: {err}");
+        }
+
+        iced::exit()
+    }
+}
+
+pub fn subscription() -> Subscription<AppEvent> {
+    Subscription::batch([
+        time::every(AUTOSAVE_INTERVAL).map(|_| SessionEvent::Autosave.into()),
+        window::close_requests().map(|_| SessionEvent::CloseRequested.into()),
+    ])
+}
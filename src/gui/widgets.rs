@@ -1,4 +1,8 @@
+pub mod library;
 pub mod player;
+pub mod smart_playlist;
+pub mod tag_editor;
+pub mod toasts;
 
 mod utils;
 
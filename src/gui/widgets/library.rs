@@ -0,0 +1,612 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use iced::Alignment::Center;
+use iced::widget::{
+    Column, Row, Text, button, column, container, horizontal_space, image, mouse_area, row,
+    scrollable, text, text_input,
+};
+use iced::{Element, Fill, FillPortion, Task};
+
+use crate::gui::events::AppEvent;
+use crate::gui::widgets::smart_playlist::{SmartPlaylistEvent, SmartPlaylistWidget};
+use crate::gui::widgets::tag_editor::{TagEditor, TagEditorEvent};
+use crate::gui::widgets::{format_duration, format_timestamp, gen_svg_icon};
+use crate::library::{
+    Album, CoverCache, LibraryError, LibraryIndex, SearchError, SearchQuery, SortColumn, Track,
+    TrackFilter, TrackId, export_history,
+};
+
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+const ALBUM_TILE_SIZE: f32 = 160.0;
+const HISTORY_LIMIT: usize = 500;
+
+pub struct LibraryWidget {
+    view: LibraryView,
+    history: Vec<LibraryView>,
+    sort: SortColumn,
+    ascending: bool,
+    last_click: Option<(TrackId, Instant)>,
+    search: String,
+    query: SearchQuery,
+    search_error: Option<SearchError>,
+    playlists: SmartPlaylistWidget,
+    editor: Option<TagEditor>,
+    export_path: String,
+    export_status: Option<String>,
+    cover_cache: Option<CoverCache>,
+    /// Keyed by `Album::cover_track`; `None` while loading or when there is no cover.
+    covers: HashMap<PathBuf, Option<image::Handle>>,
+}
+
+impl Default for LibraryWidget {
+    fn default() -> Self {
+        Self {
+            view: LibraryView::default(),
+            history: Vec::new(),
+            sort: SortColumn::default(),
+            ascending: true,
+            last_click: None,
+            search: String::new(),
+            query: SearchQuery::default(),
+            search_error: None,
+            playlists: SmartPlaylistWidget::default(),
+            editor: None,
+            export_path: String::new(),
+            export_status: None,
+            cover_cache: CoverCache::default_dir().map(CoverCache::new),
+            covers: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum LibraryView {
+    #[default]
+    Artists,
+    Albums(TrackFilter),
+    Tracks(TrackFilter),
+    Genres,
+    Years,
+    Playlists,
+    History,
+    MostPlayed,
+}
+
+#[derive(Debug, Clone)]
+pub enum LibraryWidgetEvent {
+    /// Switches tabs, forgetting the drill-down history.
+    Show(LibraryView),
+    Open(LibraryView),
+    Back,
+    Sort(SortColumn),
+    Search(String),
+    Playlist(SmartPlaylistEvent),
+    Click(TrackId, PathBuf),
+    Play(PathBuf),
+    Enqueue(PathBuf),
+    EditTags(Vec<PathBuf>),
+    /// Edits every track in the table on screen.
+    EditShownTags,
+    ExportPath(String),
+    ExportHistory,
+    /// How many plays were written.
+    HistoryExported(Result<usize, Arc<LibraryError>>),
+    Editor(TagEditorEvent),
+    CoverLoaded(PathBuf, Option<PathBuf>),
+}
+
+impl From<LibraryWidgetEvent> for AppEvent {
+    fn from(val: LibraryWidgetEvent) -> Self {
+        AppEvent::LibraryView(val)
+    }
+}
+
+impl LibraryWidget {
+    const ADD_ICON: &[u8] = include_bytes!("../../assets/icon-add.svg");
+    const EDIT_ICON: &[u8] = include_bytes!("../../assets/icon-edit.svg");
+
+    pub fn update(
+        &mut self,
+        event: LibraryWidgetEvent,
+        library: &LibraryIndex,
+    ) -> Task<LibraryWidgetEvent> {
+        match event {
+            LibraryWidgetEvent::Show(view) => {
+                self.history.clear();
+                self.view = view;
+
+                return self.load_covers(library);
+            }
+            LibraryWidgetEvent::Open(view) => {
+                let previous = std::mem::replace(&mut self.view, view);
+                self.history.push(previous);
+
+                return self.load_covers(library);
+            }
+            LibraryWidgetEvent::Back => {
+                if let Some(view) = self.history.pop() {
+                    self.view = view;
+                }
+
+                return self.load_covers(library);
+            }
+            LibraryWidgetEvent::ExportPath(path) => self.export_path = path,
+            LibraryWidgetEvent::ExportHistory => {
+                let path = PathBuf::from(self.export_path.trim());
+                let history = library.history().to_vec();
+
+                return Task::perform(
+                    async move { export_history(&history, &path).map(|_| history.len()) },
+                    |res| LibraryWidgetEvent::HistoryExported(res.map_err(Arc::new)),
+                );
+            }
+            LibraryWidgetEvent::HistoryExported(res) => {
+                self.export_status = Some(match res {
+                    Ok(count) => format!("Exported {count} plays."),
+                    Err(err) => err.to_string(),
+                });
+            }
+            LibraryWidgetEvent::EditTags(paths) => {
+                self.editor = Some(TagEditor::new(paths, library));
+            }
+            LibraryWidgetEvent::EditShownTags => {
+                let paths = self
+                    .shown_tracks(library)
+                    .into_iter()
+                    .map(|track| track.path.clone())
+                    .collect();
+
+                self.editor = Some(TagEditor::new(paths, library));
+            }
+            LibraryWidgetEvent::Editor(TagEditorEvent::Close) => self.editor = None,
+            LibraryWidgetEvent::Editor(event) => {
+                if let Some(editor) = self.editor.as_mut() {
+                    return editor.update(event).map(LibraryWidgetEvent::Editor);
+                }
+            }
+            LibraryWidgetEvent::CoverLoaded(track, thumbnail) => {
+                self.covers
+                    .insert(track, thumbnail.map(image::Handle::from_path));
+            }
+            LibraryWidgetEvent::Sort(column) => {
+                self.ascending = self.sort != column || !self.ascending;
+                self.sort = column;
+            }
+            LibraryWidgetEvent::Search(search) => {
+                // Keep showing the last valid results while the query is being typed.
+                match search.parse() {
+                    Ok(query) => {
+                        self.query = query;
+                        self.search_error = None;
+                    }
+                    Err(err) => self.search_error = Some(err),
+                }
+
+                self.search = search;
+            }
+            LibraryWidgetEvent::Playlist(event) => {
+                return self
+                    .playlists
+                    .update(event, library)
+                    .map(LibraryWidgetEvent::Playlist);
+            }
+            LibraryWidgetEvent::Click(id, path) => {
+                let now = Instant::now();
+
+                if let Some((last_id, at)) = self.last_click.take()
+                    && last_id == id
+                    && now.duration_since(at) <= DOUBLE_CLICK
+                {
+                    return Task::done(LibraryWidgetEvent::Play(path));
+                }
+
+                self.last_click = Some((id, now));
+            }
+            LibraryWidgetEvent::Play(_) | LibraryWidgetEvent::Enqueue(_) => {}
+        }
+
+        Task::none()
+    }
+
+    /// Re-evaluates anything derived from the index after it changed.
+    pub fn library_changed(&mut self, library: &LibraryIndex) -> Task<LibraryWidgetEvent> {
+        self.playlists.refresh(library);
+
+        if let Some(editor) = self.editor.as_mut() {
+            editor.reload(library);
+        }
+
+        self.load_covers(library)
+    }
+
+    /// Starts loading thumbnails for the albums on screen that don't have one yet.
+    fn load_covers(&mut self, library: &LibraryIndex) -> Task<LibraryWidgetEvent> {
+        let (LibraryView::Albums(filter), Some(cache)) = (&self.view, &self.cover_cache) else {
+            return Task::none();
+        };
+
+        let tasks: Vec<_> = library
+            .albums(filter)
+            .into_iter()
+            .filter(|album| match self.covers.entry(album.cover_track.clone()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(None);
+                    true
+                }
+                Entry::Occupied(_) => false,
+            })
+            .map(|album| {
+                let cache = cache.clone();
+                let track = album.cover_track;
+
+                Task::perform(
+                    async move {
+                        let thumbnail = cache.thumbnail(&track).unwrap_or_else(|err| {
+                            log::warn!("No cover for {}: {err}", track.display());
+                            None
+                        });
+
+                        (track, thumbnail)
+                    },
+                    |(track, thumbnail)| LibraryWidgetEvent::CoverLoaded(track, thumbnail),
+                )
+            })
+            .collect();
+
+        Task::batch(tasks)
+    }
+
+    pub fn view(&self, library: &LibraryIndex) -> Element<'_, LibraryWidgetEvent> {
+        let content = match &self.view {
+            _ if !self.query.is_empty() => self.track_table(self.shown_tracks(library)),
+            LibraryView::Artists => self.list_view(
+                library
+                    .artists()
+                    .into_iter()
+                    .map(|artist| {
+                        let filter = TrackFilter {
+                            artist: Some(artist.clone()),
+                            ..Default::default()
+                        };
+                        (artist, LibraryView::Albums(filter))
+                    })
+                    .collect(),
+            ),
+            LibraryView::Genres => self.list_view(
+                library
+                    .genres()
+                    .into_iter()
+                    .map(|genre| {
+                        let filter = TrackFilter {
+                            genre: Some(genre.clone()),
+                            ..Default::default()
+                        };
+                        (genre, LibraryView::Tracks(filter))
+                    })
+                    .collect(),
+            ),
+            LibraryView::Years => self.list_view(
+                library
+                    .years()
+                    .into_iter()
+                    .map(|year| {
+                        let filter = TrackFilter {
+                            year: Some(year),
+                            ..Default::default()
+                        };
+                        (year.to_string(), LibraryView::Tracks(filter))
+                    })
+                    .collect(),
+            ),
+            LibraryView::Albums(filter) => self.album_grid(library.albums(filter)),
+            LibraryView::Tracks(_) => self.track_table(self.shown_tracks(library)),
+            LibraryView::Playlists => row![
+                container(self.playlists.view().map(LibraryWidgetEvent::Playlist)).width(480),
+                self.track_table(self.shown_tracks(library)),
+            ]
+            .spacing(20)
+            .into(),
+            LibraryView::History => self.history_view(library),
+            LibraryView::MostPlayed => self.most_played_view(library),
+        };
+
+        let content: Element<_> = match &self.editor {
+            Some(editor) => row![
+                content,
+                container(editor.view().map(LibraryWidgetEvent::Editor)).width(320),
+            ]
+            .spacing(20)
+            .into(),
+            None => content,
+        };
+
+        let search = text_input(
+            "Search, e.g. artist:radiohead year:>2000 -live",
+            &self.search,
+        )
+        .on_input(LibraryWidgetEvent::Search)
+        .width(360);
+
+        let mut header = column![row![self.tabs(), horizontal_space(), search].align_y(Center)];
+
+        if let Some(err) = &self.search_error {
+            header = header.push(row![
+                horizontal_space(),
+                text(err.to_string()).size(12).style(text::danger)
+            ]);
+        }
+
+        column![header, content].spacing(12).into()
+    }
+
+    fn tabs(&self) -> Element<'_, LibraryWidgetEvent> {
+        let tabs = [
+            ("Artists", LibraryView::Artists),
+            ("Albums", LibraryView::Albums(TrackFilter::default())),
+            ("Tracks", LibraryView::Tracks(TrackFilter::default())),
+            ("Genres", LibraryView::Genres),
+            ("Years", LibraryView::Years),
+            ("Playlists", LibraryView::Playlists),
+            ("History", LibraryView::History),
+            ("Most played", LibraryView::MostPlayed),
+        ];
+
+        let root = self.history.first().unwrap_or(&self.view);
+        let mut tabs = Row::with_children(tabs.into_iter().map(|(label, view)| {
+            let style = match *root == view {
+                true => button::primary,
+                false => button::text,
+            };
+
+            button(Text::new(label))
+                .style(style)
+                .on_press(LibraryWidgetEvent::Show(view))
+                .into()
+        }))
+        .spacing(4)
+        .align_y(Center);
+
+        if !self.history.is_empty() {
+            tabs = tabs.push(
+                button(Text::new("Back"))
+                    .style(button::secondary)
+                    .on_press(LibraryWidgetEvent::Back),
+            );
+        }
+
+        tabs.into()
+    }
+
+    fn list_view(&self, entries: Vec<(String, LibraryView)>) -> Element<'_, LibraryWidgetEvent> {
+        let entries = entries.into_iter().map(|(label, view)| {
+            button(Text::new(label))
+                .style(button::text)
+                .width(Fill)
+                .on_press(LibraryWidgetEvent::Open(view))
+                .into()
+        });
+
+        scrollable(Column::with_children(entries).spacing(2)).into()
+    }
+
+    fn album_grid(&self, albums: Vec<Album>) -> Element<'_, LibraryWidgetEvent> {
+        let tiles = albums.into_iter().map(|album| {
+            let filter = TrackFilter {
+                artist: Some(album.artist.clone()),
+                album: Some(album.title.clone()),
+                ..Default::default()
+            };
+
+            let cover: Element<_> = match self.covers.get(&album.cover_track) {
+                Some(Some(handle)) => image(handle.clone())
+                    .width(ALBUM_TILE_SIZE)
+                    .height(ALBUM_TILE_SIZE)
+                    .into(),
+                _ => container(Text::new(album_initial(&album.title)).size(48))
+                    .center(ALBUM_TILE_SIZE)
+                    .style(container::rounded_box)
+                    .into(),
+            };
+
+            let year = album.year.map(|y| format!(" · {y}")).unwrap_or_default();
+
+            button(
+                column![
+                    cover,
+                    Text::new(album.title),
+                    Text::new(format!("{}{year}", album.artist)).size(12),
+                ]
+                .width(ALBUM_TILE_SIZE)
+                .spacing(4),
+            )
+            .style(button::text)
+            .on_press(LibraryWidgetEvent::Open(LibraryView::Tracks(filter)))
+            .into()
+        });
+
+        scrollable(Row::with_children(tiles).spacing(8).wrap()).into()
+    }
+
+    fn history_view(&self, library: &LibraryIndex) -> Element<'_, LibraryWidgetEvent> {
+        let rows = library
+            .history()
+            .iter()
+            .rev()
+            .take(HISTORY_LIMIT)
+            .map(|record| {
+                let title = record.title.clone().unwrap_or_else(|| {
+                    record
+                        .path
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                });
+                let artist = record.artist.as_deref().unwrap_or("Unknown artist");
+
+                mouse_area(
+                    row![
+                        Text::new(format_timestamp(record.started_at)).width(150),
+                        Text::new(format!("{artist} - {title}")).width(Fill),
+                        Text::new(format_duration(record.listened)).width(60),
+                        Text::new(record.outcome.label()).width(90),
+                    ]
+                    .spacing(8),
+                )
+                .on_press(LibraryWidgetEvent::Click(record.track, record.path.clone()))
+                .into()
+            });
+
+        let has_path = !self.export_path.trim().is_empty();
+        let mut export = row![
+            text_input("Export to a .csv or .json file", &self.export_path)
+                .on_input(LibraryWidgetEvent::ExportPath)
+                .on_submit(LibraryWidgetEvent::ExportHistory)
+                .width(360),
+            button(Text::new("Export"))
+                .style(button::secondary)
+                .on_press_maybe(has_path.then_some(LibraryWidgetEvent::ExportHistory)),
+        ]
+        .spacing(8)
+        .align_y(Center);
+
+        if let Some(status) = &self.export_status {
+            export = export.push(text(status).size(12));
+        }
+
+        column![export, scrollable(Column::with_children(rows).spacing(2))]
+            .spacing(8)
+            .into()
+    }
+
+    fn most_played_view(&self, library: &LibraryIndex) -> Element<'_, LibraryWidgetEvent> {
+        let rows = library
+            .most_played(HISTORY_LIMIT)
+            .into_iter()
+            .map(|(track, stats)| {
+                let last_played = stats.last_played.map(format_timestamp).unwrap_or_default();
+
+                mouse_area(
+                    row![
+                        Text::new(stats.play_count.to_string()).width(50),
+                        Text::new(format!(
+                            "{} - {}",
+                            track.display_artist(),
+                            track.display_title()
+                        ))
+                        .width(Fill),
+                        Text::new(last_played).width(150),
+                    ]
+                    .spacing(8),
+                )
+                .on_press(LibraryWidgetEvent::Click(track.id, track.path.clone()))
+                .into()
+            });
+
+        scrollable(Column::with_children(rows).spacing(2)).into()
+    }
+
+    /// The tracks listed in a table on the current view, if it has one.
+    fn shown_tracks<'a>(&self, library: &'a LibraryIndex) -> Vec<&'a Track> {
+        match &self.view {
+            _ if !self.query.is_empty() => library.search(&self.query, self.sort, self.ascending),
+            LibraryView::Tracks(filter) => library.filtered(filter, self.sort, self.ascending),
+            LibraryView::Playlists => self.playlists.preview(library),
+            _ => Vec::new(),
+        }
+    }
+
+    fn track_table(&self, tracks: Vec<&Track>) -> Element<'_, LibraryWidgetEvent> {
+        let header = Row::with_children(SortColumn::ALL.into_iter().map(|column| {
+            let arrow = match (self.sort == column, self.ascending) {
+                (true, true) => " ▲",
+                (true, false) => " ▼",
+                (false, _) => "",
+            };
+
+            button(Text::new(format!("{}{arrow}", column.label())))
+                .style(button::text)
+                .width(column_width(column))
+                .on_press(LibraryWidgetEvent::Sort(column))
+                .into()
+        }))
+        .push(container("").width(64));
+
+        let edit_all = button(Text::new(format!("Edit tags of {} tracks", tracks.len())))
+            .style(button::secondary)
+            .on_press_maybe((tracks.len() > 1).then_some(LibraryWidgetEvent::EditShownTags));
+
+        let rows = tracks.into_iter().map(|track| {
+            let cells = SortColumn::ALL
+                .into_iter()
+                .map(|column| {
+                    container(Text::new(cell_text(track, column)))
+                        .width(column_width(column))
+                        .padding([0, 5])
+                        .into()
+                })
+                .collect::<Vec<_>>();
+
+            let enqueue = button(gen_svg_icon(Self::ADD_ICON))
+                .style(button::text)
+                .width(32)
+                .on_press(LibraryWidgetEvent::Enqueue(track.path.clone()));
+
+            let edit = button(gen_svg_icon(Self::EDIT_ICON))
+                .style(button::text)
+                .width(32)
+                .on_press(LibraryWidgetEvent::EditTags(vec![track.path.clone()]));
+
+            row![
+                mouse_area(Row::with_children(cells).align_y(Center))
+                    .on_press(LibraryWidgetEvent::Click(track.id, track.path.clone())),
+                edit,
+                enqueue,
+            ]
+            .align_y(Center)
+            .into()
+        });
+
+        column![
+            row![horizontal_space(), edit_all],
+            header,
+            scrollable(Column::with_children(rows).spacing(2))
+        ]
+        .spacing(4)
+        .into()
+    }
+}
+
+fn column_width(column: SortColumn) -> iced::Length {
+    match column {
+        SortColumn::TrackNumber | SortColumn::Year | SortColumn::Duration => FillPortion(1),
+        SortColumn::Genre => FillPortion(2),
+        SortColumn::Title | SortColumn::Artist | SortColumn::Album => FillPortion(4),
+    }
+}
+
+fn cell_text(track: &Track, column: SortColumn) -> String {
+    match column {
+        SortColumn::TrackNumber => track
+            .track_number
+            .map(|n| n.to_string())
+            .unwrap_or_default(),
+        SortColumn::Title => track.display_title().into_owned(),
+        SortColumn::Artist => track.display_artist().to_string(),
+        SortColumn::Album => track.display_album().to_string(),
+        SortColumn::Genre => track.genre.clone().unwrap_or_default(),
+        SortColumn::Year => track.year.map(|y| y.to_string()).unwrap_or_default(),
+        SortColumn::Duration => format_duration(track.duration),
+    }
+}
+
+fn album_initial(title: &str) -> String {
+    title
+        .chars()
+        .next()
+        .map(|c| c.to_uppercase().collect())
+        .unwrap_or_default()
+}
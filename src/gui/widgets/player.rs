@@ -1,23 +1,98 @@
 use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use std::{sync::Arc, time::Duration};
 
 use iced::Alignment::Center;
-use iced::widget::{Text, button, column, row, slider};
+use iced::futures::SinkExt;
+use iced::widget::{
+    Column, Row, Text, button, column, image, pick_list, row, slider, text, text_input,
+};
 use iced::{Element, Subscription, Task, time};
+use tokio::sync::broadcast::error::RecvError;
 
 use crate::gui::events::AppEvent;
 use crate::gui::widgets::gen_svg_icon;
+use crate::library::{
+    CoverCache, PlayOutcome, PlayRecord, ResumeKey, ResumePositions, Track, TrackId,
+};
+#[cfg(target_os = "linux")]
+use crate::mpris::{MprisState, MprisTrack};
 use crate::player::event::{AtomicEvent, AudioEvent};
-use crate::player::{AudioController, AudioError, SharedAudioBuffer, decode_samples};
+use crate::player::{
+    AudioController, AudioError, DecoderResult, DeviceId, LoopMode, OutputEvent, PlayQueue,
+    PlaybackStatus, PlayerFlags, decode_samples, list_output_devices,
+};
+use crate::playlist::{PlaylistEntry, PlaylistError, PlaylistFile};
+use crate::remote::RemoteStatus;
+use crate::session::Session;
 
 pub struct PlayerWidget {
+    queue: PlayQueue,
+    playlist_path: String,
+    playlist_status: Option<String>,
+    cover_cache: Option<CoverCache>,
+    cover: Option<image::Handle>,
+    cover_path: Option<PathBuf>,
+    /// The track whose cover and tags are shown or on their way, so those of skipped tracks
+    /// are dropped.
+    shown_track: Option<PathBuf>,
+    track: Option<Arc<Track>>,
+    /// Path of the file being decoded, until it replaces the playing one.
+    loading: Option<PathBuf>,
+    /// Where to pick up a restored track once it's decoded, in seconds.
+    resume_at: Option<(PathBuf, f64)>,
+    resume_positions: Option<ResumePositions>,
+    /// Set while a track long enough to resume is loaded.
+    resume_key: Option<ResumeKey>,
+    /// Seconds, see `PlaybackConfig::resume_threshold`.
+    resume_threshold: f64,
+    /// Output devices for the picker, listed again whenever it opens.
+    devices: Vec<DeviceId>,
+    /// What last happened to the output stream.
+    output_status: Option<String>,
+    /// What went wrong decoding the current track, short of failing.
+    decode_status: Option<String>,
+    listening: Option<Listening>,
     song_dur: [u8; 5],
     song_pos: [u8; 5],
 }
 
+/// The track being listened to, turned into a `PlayRecord` once it's over.
+struct Listening {
+    path: PathBuf,
+    started_at: u64,
+    listened: Duration,
+    last_tick: Instant,
+    last_position: f64,
+    /// Whether the app was told the track started playing.
+    announced: bool,
+}
+
 impl Default for PlayerWidget {
     fn default() -> Self {
         Self {
+            queue: PlayQueue::default(),
+            playlist_path: String::new(),
+            playlist_status: None,
+            cover_cache: CoverCache::default_dir().map(CoverCache::new),
+            cover: None,
+            cover_path: None,
+            shown_track: None,
+            track: None,
+            loading: None,
+            resume_at: None,
+            resume_positions: ResumePositions::default_path().and_then(|path| {
+                ResumePositions::load(path)
+                    .map_err(|err| log::warn!("Could not read resume positions: {err}"))
+                    .ok()
+            }),
+            resume_key: None,
+            resume_threshold: f64::INFINITY,
+            devices: Vec::new(),
+            output_status: None,
+            decode_status: None,
+            listening: None,
             song_dur: *b"00:00",
             song_pos: *b"00:00",
         }
@@ -26,15 +101,52 @@ impl Default for PlayerWidget {
 
 #[derive(Debug, Clone)]
 pub enum PlayerWidgetEvent {
-    LoadSong,
-    Loaded(SharedAudioBuffer),
-    Error(Arc<AudioError>),
+    LoadSong(PathBuf),
+    /// A decoded file, dropped unless it's still the one being loaded.
+    Loaded(PathBuf, DecoderResult),
+    /// Decoding the file failed.
+    Error(PathBuf, Arc<AudioError>),
+    /// The thumbnail for a track, if it has a cover.
+    CoverLoaded(PathBuf, Option<PathBuf>),
+    /// The tags of a track, if they could be read.
+    TrackLoaded(PathBuf, Option<Arc<Track>>),
+    /// A track started playing, for the app to announce.
+    NowPlaying(PathBuf),
+    /// A track stopped playing, for the app to record in the history.
+    Played(PlayRecord),
+    PlayFile(PathBuf),
+    PlayAll(Vec<PathBuf>),
+    Enqueue(PathBuf),
+    PlayIndex(usize),
+    RemoveFromQueue(usize),
+    ClearQueue,
+    PlaylistPath(String),
+    OpenPlaylist(PathBuf),
+    PlaylistOpened(Result<Arc<PlaylistFile>, Arc<PlaylistError>>),
+    /// Asks the app to resolve the queue against the library, answered with `SaveQueue`.
+    SaveQueueRequested,
+    SaveQueue(Vec<PlaylistEntry>),
+    PlaylistSaved(Result<usize, Arc<PlaylistError>>),
+    /// Forgets where the current track was left off and moves on.
+    MarkFinished,
+    ListDevices,
+    DevicesListed(Vec<DeviceId>),
+    /// Handled by the app, which also saves the choice.
+    SelectDevice(DeviceId),
+    Output(OutputEvent),
     Play,
     Pause,
     Stop,
+    Next,
+    Previous,
+    LoopMode(LoopMode),
+    Shuffle(bool),
     Volume(f32),
     Speed(f64),
+    /// Fraction of the current track, from the slider.
     Seek(f64),
+    /// Seconds into the current track, which don't depend on the playback speed.
+    SeekSeconds(f64),
     SongTick,
 }
 
@@ -48,6 +160,12 @@ impl PlayerWidget {
     const PLAY_ICON: &[u8] = include_bytes!("../../assets/icon-play.svg");
     const PAUSE_ICON: &[u8] = include_bytes!("../../assets/icon-pause.svg");
     const STOP_ICON: &[u8] = include_bytes!("../../assets/icon-stop.svg");
+    const NEXT_ICON: &[u8] = include_bytes!("../../assets/icon-next.svg");
+    const PREVIOUS_ICON: &[u8] = include_bytes!("../../assets/icon-previous.svg");
+
+    pub fn queue(&self) -> &PlayQueue {
+        &self.queue
+    }
 
     pub fn update(
         &mut self,
@@ -55,53 +173,578 @@ impl PlayerWidget {
         event: PlayerWidgetEvent,
     ) -> Task<PlayerWidgetEvent> {
         match event {
-            PlayerWidgetEvent::LoadSong => {
-                return Task::perform(
+            PlayerWidgetEvent::LoadSong(path) => {
+                let played = self.finish_listening(player, false);
+                self.remember_position(player);
+
+                self.loading = Some(path.clone());
+                self.cover = None;
+                self.cover_path = None;
+                self.shown_track = Some(path.clone());
+                self.track = None;
+                self.decode_status = None;
+
+                let info = {
+                    let path = path.clone();
+
+                    Task::perform(
+                        async move {
+                            let track = Track::read(&path).ok().map(Arc::new);
+                            (path, track)
+                        },
+                        |(path, track)| PlayerWidgetEvent::TrackLoaded(path, track),
+                    )
+                };
+
+                let cover = match self.cover_cache.clone() {
+                    Some(cache) => {
+                        let path = path.clone();
+
+                        Task::perform(
+                            async move {
+                                let thumbnail = cache.thumbnail(&path).unwrap_or_else(|err| {
+                                    log::warn!("No cover for {}: {err}", path.display());
+                                    None
+                                });
+                                (path, thumbnail)
+                            },
+                            |(path, thumbnail)| PlayerWidgetEvent::CoverLoaded(path, thumbnail),
+                        )
+                    }
+                    None => Task::none(),
+                };
+
+                let decode = Task::perform(
                     async move {
-                        decode_samples("/home/eyewave/Music/my-ids/400_eyewave.mp3")
-                            .map(SharedAudioBuffer::from)
+                        match decode_samples(&path) {
+                            Ok(res) => Ok((path, res)),
+                            Err(err) => Err((path, err)),
+                        }
                     },
                     |res| match res {
-                        Ok(res) => PlayerWidgetEvent::Loaded(res),
-                        Err(err) => PlayerWidgetEvent::Error(Arc::new(err.into())),
+                        Ok((path, res)) => PlayerWidgetEvent::Loaded(path, res),
+                        Err((path, err)) => PlayerWidgetEvent::Error(path, Arc::new(err.into())),
                     },
                 );
+
+                return Task::batch([played, decode, cover, info]);
             }
-            PlayerWidgetEvent::Loaded(buf) => {
-                player.shared_audio.swap(Arc::new(buf));
+            PlayerWidgetEvent::TrackLoaded(path, track) => {
+                if self.shown_track.as_ref() == Some(&path) {
+                    self.track = track;
+                }
+            }
+            PlayerWidgetEvent::CoverLoaded(track, thumbnail) => {
+                if self.shown_track.as_ref() == Some(&track) {
+                    self.cover = thumbnail.clone().map(image::Handle::from_path);
+                    self.cover_path = thumbnail;
+                }
+            }
+            PlayerWidgetEvent::Loaded(path, mut res) => {
+                if self.loading.as_ref() != Some(&path) {
+                    return Task::none();
+                }
+                self.loading = None;
+
+                let warnings = std::mem::take(&mut res.warnings);
+                if !warnings.is_empty() {
+                    log::warn!("Decoded {} with problems: {warnings:?}", path.display());
+
+                    let messages: Vec<_> = warnings.iter().map(|w| w.to_string()).collect();
+                    self.decode_status = Some(messages.join(" "));
+                }
+
+                player.shared_audio.swap(Arc::new(res.into()));
+                player.match_track_rate();
+                player.set_position(0.0);
                 self.song_dur = get_song_duration_pretty(player);
+
+                self.resume(player, &path);
+                self.start_listening(player, path);
+
+                return self.announce(player);
+            }
+            PlayerWidgetEvent::PlayFile(path) => {
+                self.queue.play_now(path.clone());
+                player.send_event(AtomicEvent::Play);
+
+                return Task::done(PlayerWidgetEvent::LoadSong(path));
+            }
+            PlayerWidgetEvent::PlayAll(paths) => {
+                self.queue.replace(paths);
+
+                if let Some(path) = self.queue.next() {
+                    player.send_event(AtomicEvent::Play);
+                    return Task::done(PlayerWidgetEvent::LoadSong(path.to_path_buf()));
+                }
+            }
+            PlayerWidgetEvent::Enqueue(path) => self.queue.enqueue(path),
+            PlayerWidgetEvent::PlayIndex(idx) => {
+                if let Some(path) = self.queue.jump(idx) {
+                    player.send_event(AtomicEvent::Play);
+                    return Task::done(PlayerWidgetEvent::LoadSong(path.to_path_buf()));
+                }
+            }
+            PlayerWidgetEvent::RemoveFromQueue(idx) => {
+                self.queue.remove(idx);
+            }
+            PlayerWidgetEvent::ClearQueue => self.queue.clear(),
+            PlayerWidgetEvent::PlaylistPath(path) => self.playlist_path = path,
+            PlayerWidgetEvent::OpenPlaylist(path) => {
+                self.playlist_path = path.to_string_lossy().into_owned();
+
+                return Task::perform(
+                    async move { PlaylistFile::load(&path).map(Arc::new) },
+                    |res| PlayerWidgetEvent::PlaylistOpened(res.map_err(Arc::new)),
+                );
+            }
+            PlayerWidgetEvent::PlaylistOpened(Ok(playlist)) => {
+                for location in &playlist.unresolved {
+                    log::warn!("Playlist entry not found: {location}");
+                }
+
+                if playlist.entries.is_empty() {
+                    self.playlist_status = Some(match playlist.unresolved.len() {
+                        0 => "The playlist is empty.".to_string(),
+                        n => format!("None of the {n} tracks could be found."),
+                    });
+                    return Task::none();
+                }
+
+                self.playlist_status = Some(match playlist.unresolved.len() {
+                    0 => format!("Loaded {} tracks.", playlist.entries.len()),
+                    n => format!(
+                        "Loaded {} tracks, {n} could not be found.",
+                        playlist.entries.len()
+                    ),
+                });
+
+                return Task::done(PlayerWidgetEvent::PlayAll(playlist.paths()));
+            }
+            PlayerWidgetEvent::SaveQueue(entries) => {
+                let path = PathBuf::from(self.playlist_path.trim());
+                let playlist = PlaylistFile {
+                    entries,
+                    unresolved: Vec::new(),
+                };
+
+                return Task::perform(
+                    async move { playlist.save(&path).map(|_| playlist.entries.len()) },
+                    |res| PlayerWidgetEvent::PlaylistSaved(res.map_err(Arc::new)),
+                );
             }
+            PlayerWidgetEvent::PlaylistSaved(Ok(count)) => {
+                self.playlist_status = Some(format!("Saved {count} tracks."));
+            }
+            PlayerWidgetEvent::PlaylistOpened(Err(err))
+            | PlayerWidgetEvent::PlaylistSaved(Err(err)) => {
+                self.playlist_status = Some(err.to_string());
+            }
+            PlayerWidgetEvent::ListDevices => {
+                return Task::perform(
+                    async { list_output_devices().into_iter().map(|d| d.id).collect() },
+                    PlayerWidgetEvent::DevicesListed,
+                );
+            }
+            PlayerWidgetEvent::DevicesListed(devices) => self.devices = devices,
+            PlayerWidgetEvent::Output(event) => {
+                self.output_status = match event {
+                    OutputEvent::Lost(Some(device), err) => Some(format!("Lost {device}: {err}")),
+                    OutputEvent::Lost(None, err) => Some(format!("Audio output failed: {err}")),
+                    OutputEvent::FellBack(device) => {
+                        Some(format!("Output device unavailable, playing on {device}."))
+                    }
+                    OutputEvent::Reconnected(device) => Some(format!("Back on {device}.")),
+                    OutputEvent::Unavailable(err) => {
+                        Some(format!("No output device available, retrying: {err}"))
+                    }
+                };
+            }
+            PlayerWidgetEvent::SaveQueueRequested | PlayerWidgetEvent::SelectDevice(_) => {}
             PlayerWidgetEvent::Play => {
                 player.send_event(AtomicEvent::Play);
 
+                if self.listening.is_none()
+                    && player.get_song_duration() > 0
+                    && let Some(path) = self.queue.current().map(Path::to_path_buf)
+                {
+                    self.start_listening(player, path);
+                }
+
                 if player.get_song_duration() < 1 {
-                    return Task::perform(async {}, |_| PlayerWidgetEvent::LoadSong);
+                    let path = match self.queue.current() {
+                        Some(path) => Some(path.to_path_buf()),
+                        None => self.queue.next().map(Path::to_path_buf),
+                    };
+
+                    if let Some(path) = path {
+                        return Task::done(PlayerWidgetEvent::LoadSong(path));
+                    }
                 }
+
+                return self.announce(player);
             }
             PlayerWidgetEvent::Pause => {
                 player.send_event(AtomicEvent::Pause);
+                self.remember_position(player);
             }
             PlayerWidgetEvent::Stop => {
+                self.remember_position(player);
+                self.resume_key = None;
                 player.send_event(AudioEvent::Stop);
+
+                return self.finish_listening(player, false);
             }
+            PlayerWidgetEvent::MarkFinished => {
+                self.forget_position();
+                self.resume_key = None;
+
+                return Task::done(match self.queue.has_next() {
+                    true => PlayerWidgetEvent::Next,
+                    false => PlayerWidgetEvent::Stop,
+                });
+            }
+            PlayerWidgetEvent::Next => {
+                if let Some(path) = self.queue.next() {
+                    return Task::done(PlayerWidgetEvent::LoadSong(path.to_path_buf()));
+                }
+            }
+            PlayerWidgetEvent::Previous => {
+                if let Some(path) = self.queue.previous() {
+                    return Task::done(PlayerWidgetEvent::LoadSong(path.to_path_buf()));
+                }
+            }
+            PlayerWidgetEvent::LoopMode(mode) => self.queue.set_loop_mode(mode),
+            PlayerWidgetEvent::Shuffle(shuffle) => self.queue.set_shuffle(shuffle),
             PlayerWidgetEvent::Volume(vol) => {
                 player.send_event(AtomicEvent::SetVolume(vol));
             }
             PlayerWidgetEvent::Speed(s) => {
                 player.send_event(AtomicEvent::SetSpeed(s));
             }
-            PlayerWidgetEvent::Seek(pos) => player.set_position(pos),
-            PlayerWidgetEvent::SongTick => self.song_pos = get_song_position_pretty(player),
-            PlayerWidgetEvent::Error(err) => eprintln!("{err:?}"),
+            PlayerWidgetEvent::Seek(pos) => {
+                player.set_position(pos);
+                self.seeked(player);
+            }
+            PlayerWidgetEvent::SeekSeconds(seconds) => {
+                player.set_position_seconds(seconds);
+                self.seeked(player);
+            }
+            PlayerWidgetEvent::SongTick => {
+                self.song_pos = get_song_position_pretty(player);
+
+                if self.track_ended(player) {
+                    let played = self.finish_listening(player, true);
+                    self.forget_position();
+
+                    let next = match self.queue.loop_mode() {
+                        // Playback already wrapped around to the start of the track.
+                        LoopMode::Track => {
+                            if let Some(path) = self.queue.current().map(Path::to_path_buf) {
+                                self.start_listening(player, path);
+                            }
+
+                            Task::none()
+                        }
+                        _ => match self.queue.next() {
+                            Some(path) => {
+                                Task::done(PlayerWidgetEvent::LoadSong(path.to_path_buf()))
+                            }
+                            None => {
+                                player.send_event(AudioEvent::Stop);
+                                Task::none()
+                            }
+                        },
+                    };
+
+                    return Task::batch([played, next]);
+                }
+            }
+            PlayerWidgetEvent::NowPlaying(_) | PlayerWidgetEvent::Played(_) => {}
+            PlayerWidgetEvent::Error(path, _) => {
+                if self.loading.as_ref() == Some(&path) {
+                    self.loading = None;
+                }
+            }
         }
 
         Task::none()
     }
 
+    /// So a jump back isn't taken for the track starting over.
+    fn seeked(&mut self, player: &AudioController) {
+        if let Some(listening) = self.listening.as_mut() {
+            listening.last_position = player.get_song_position();
+        }
+    }
+
+    pub fn set_resume_threshold(&mut self, seconds: f64) {
+        self.resume_threshold = seconds;
+    }
+
+    /// Seeks a freshly loaded track to where the last session, or the last listen of a
+    /// long track, left off.
+    fn resume(&mut self, player: &AudioController, path: &Path) {
+        let (_, length) = Self::timing(player);
+
+        self.resume_key = match length >= self.resume_threshold {
+            true => ResumeKey::for_file(path)
+                .map_err(|err| {
+                    log::warn!("Can't remember the position in {}: {err}", path.display())
+                })
+                .ok(),
+            false => None,
+        };
+
+        let session = self
+            .resume_at
+            .take()
+            .filter(|(resume_path, _)| resume_path == path)
+            .map(|(_, seconds)| seconds);
+        let saved = self
+            .resume_key
+            .as_ref()
+            .zip(self.resume_positions.as_ref())
+            .and_then(|(key, positions)| positions.get(key));
+
+        if let Some(seconds) = session.or(saved) {
+            player.set_position_seconds(seconds);
+        }
+    }
+
+    /// Stores the position in the current track if it's long enough to resume later.
+    pub fn remember_position(&mut self, player: &AudioController) {
+        let (Some(key), Some(positions)) = (&self.resume_key, self.resume_positions.as_mut())
+        else {
+            return;
+        };
+
+        let (position, length) = Self::timing(player);
+        let res = match ResumePositions::position_to_keep(position, length) {
+            Some(position) => positions.set(key, position),
+            None => positions.remove(key),
+        };
+
+        if let Err(err) = res {
+            log::warn!("Could not save the resume position: {err}");
+        }
+    }
+
+    fn forget_position(&mut self) {
+        let (Some(key), Some(positions)) = (&self.resume_key, self.resume_positions.as_mut())
+        else {
+            return;
+        };
+
+        if let Err(err) = positions.remove(key) {
+            log::warn!("Could not save the resume position: {err}");
+        }
+    }
+
+    fn start_listening(&mut self, player: &AudioController, path: PathBuf) {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        self.listening = Some(Listening {
+            path,
+            started_at,
+            listened: Duration::ZERO,
+            last_tick: Instant::now(),
+            last_position: player.get_song_position(),
+            announced: false,
+        });
+    }
+
+    /// Once per listen, when the track is actually playing rather than only loaded.
+    fn announce(&mut self, player: &AudioController) -> Task<PlayerWidgetEvent> {
+        match self.listening.as_mut() {
+            Some(listening) if !listening.announced && player.get_is_playing() => {
+                listening.announced = true;
+                Task::done(PlayerWidgetEvent::NowPlaying(listening.path.clone()))
+            }
+            _ => Task::none(),
+        }
+    }
+
+    /// Counts listening time and reports when playback wrapped around to the start.
+    fn track_ended(&mut self, player: &AudioController) -> bool {
+        let Some(listening) = self.listening.as_mut() else {
+            return false;
+        };
+
+        let now = Instant::now();
+        let position = player.get_song_position();
+        let playing = player.get_is_playing();
+
+        if playing {
+            listening.listened += now - listening.last_tick;
+        }
+
+        let wrapped = playing && position < listening.last_position;
+
+        listening.last_tick = now;
+        listening.last_position = position;
+
+        wrapped
+    }
+
+    fn finish_listening(
+        &mut self,
+        player: &AudioController,
+        reached_end: bool,
+    ) -> Task<PlayerWidgetEvent> {
+        let Some(listening) = self.listening.take() else {
+            return Task::none();
+        };
+
+        let listened = listening.listened.as_secs_f64();
+
+        if listened < 1.0 {
+            return Task::none();
+        }
+
+        let buffer = player.shared_audio.load();
+        let duration = buffer.duration() as f64 / buffer.sample_rate as f64;
+        let track = self.track.as_deref().filter(|t| t.path == listening.path);
+
+        Task::done(PlayerWidgetEvent::Played(PlayRecord {
+            track: TrackId::from_path(&listening.path),
+            artist: track.and_then(|t| t.artist.clone()),
+            title: track.and_then(|t| t.title.clone()),
+            path: listening.path,
+            started_at: listening.started_at,
+            listened,
+            outcome: PlayOutcome::from_listened(listened, duration, reached_end),
+        }))
+    }
+
+    pub fn session(&self, player: &AudioController) -> Session {
+        Session {
+            queue: self.queue.clone(),
+            position: player.get_position_seconds(),
+            volume: player.get_volume(),
+            speed: player.get_speed(),
+            flags: (player.flags() - PlayerFlags::IS_PLAYING).bits(),
+        }
+    }
+
+    /// Puts the player back the way `session` left it, paused. With `load` false only the
+    /// queue and settings come back, for when the app was opened with a file to play.
+    pub fn restore(
+        &mut self,
+        player: &AudioController,
+        session: Session,
+        load: bool,
+    ) -> Task<PlayerWidgetEvent> {
+        player.send_event(AtomicEvent::SetVolume(session.volume));
+        player.send_event(AtomicEvent::SetSpeed(session.speed));
+        player.set_flags(PlayerFlags::from_bits_truncate(session.flags));
+
+        self.queue = session.queue;
+
+        match (load, self.queue.current()) {
+            (true, Some(path)) => {
+                let path = path.to_path_buf();
+                self.resume_at = Some((path.clone(), session.position));
+
+                Task::done(PlayerWidgetEvent::LoadSong(path))
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn status(&self, player: &AudioController) -> PlaybackStatus {
+        match (player.get_is_playing(), self.listening.is_some()) {
+            (true, _) => PlaybackStatus::Playing,
+            (false, true) => PlaybackStatus::Paused,
+            (false, false) => PlaybackStatus::Stopped,
+        }
+    }
+
+    /// Position and length of the loaded track, in seconds.
+    pub fn timing(player: &AudioController) -> (f64, f64) {
+        let buffer = player.shared_audio.load();
+
+        if buffer.duration() == 0 || buffer.sample_rate == 0 {
+            return (0.0, 0.0);
+        }
+
+        let length = buffer.duration() as f64 / buffer.sample_rate as f64;
+
+        (player.get_song_position_percent() * length, length)
+    }
+
+    pub fn remote_status(&self, player: &AudioController) -> RemoteStatus {
+        let (position, duration) = Self::timing(player);
+        let path = self.queue.current().map(Path::to_path_buf);
+        let track = self
+            .track
+            .as_deref()
+            .filter(|t| Some(&t.path) == path.as_ref())
+            .cloned();
+
+        RemoteStatus {
+            status: self.status(player),
+            path,
+            track,
+            position,
+            duration,
+            volume: player.get_volume(),
+            speed: player.get_speed(),
+            loop_mode: self.queue.loop_mode(),
+            shuffle: self.queue.shuffle(),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn mpris_state(&self, player: &AudioController) -> MprisState {
+        let (position, length) = Self::timing(player);
+        let length = (length * 1_000_000.0) as i64;
+
+        let track = self.queue.current().map(|path| {
+            let info = self.track.as_deref().filter(|t| t.path == path);
+            MprisTrack::new(path.to_path_buf(), info, length, self.cover_path.clone())
+        });
+
+        MprisState {
+            status: self.status(player),
+            loop_mode: self.queue.loop_mode(),
+            shuffle: self.queue.shuffle(),
+            volume: player.get_volume() as f64,
+            rate: player.get_speed(),
+            position: (position * 1_000_000.0) as i64,
+            can_go_next: self.queue.has_next(),
+            can_go_previous: self.queue.has_previous(),
+            track,
+        }
+    }
+
     pub fn subscription() -> Subscription<PlayerWidgetEvent> {
         time::every(Duration::from_millis(100)).map(|_| PlayerWidgetEvent::SongTick)
     }
 
+    /// Failures and recoveries of the output stream.
+    pub fn output_subscription(player: &AudioController) -> Subscription<PlayerWidgetEvent> {
+        let mut events = player.output_events();
+
+        Subscription::run_with_id(
+            "audio-output",
+            iced::stream::channel(16, move |mut output| async move {
+                loop {
+                    match events.recv().await {
+                        Ok(event) => {
+                            output.send(PlayerWidgetEvent::Output(event)).await.ok();
+                        }
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            }),
+        )
+    }
+
     fn get_time(&self) -> (Cow<'_, str>, Cow<'_, str>) {
         (
             Cow::Borrowed(std::str::from_utf8(&self.song_dur).unwrap()),
@@ -116,20 +759,56 @@ impl PlayerWidget {
 
         let (duration, position) = self.get_time();
 
+        let cover = self
+            .cover
+            .clone()
+            .map(|handle| image(handle).width(64).height(64));
+
         column![
-            row![
-                match player.get_is_playing() {
+            Row::new()
+                .push_maybe(cover)
+                .push_maybe(self.track.as_deref().map(track_info))
+                .push(
+                    button(gen_svg_icon(Self::PREVIOUS_ICON))
+                        .on_press(PlayerWidgetEvent::Previous)
+                        .width(40)
+                )
+                .push(match player.get_is_playing() {
                     true => button(gen_svg_icon(Self::PAUSE_ICON))
                         .on_press(PlayerWidgetEvent::Pause)
                         .width(40),
                     false => button(gen_svg_icon(Self::PLAY_ICON))
                         .on_press(PlayerWidgetEvent::Play)
                         .width(40),
-                },
-                button(gen_svg_icon(Self::STOP_ICON))
-                    .on_press(PlayerWidgetEvent::Stop)
-                    .width(40),
-                column![
+                })
+                .push(
+                    button(gen_svg_icon(Self::STOP_ICON))
+                        .on_press(PlayerWidgetEvent::Stop)
+                        .width(40)
+                )
+                .push(
+                    button(gen_svg_icon(Self::NEXT_ICON))
+                        .on_press(PlayerWidgetEvent::Next)
+                        .width(40)
+                )
+                .push(
+                    column![
+                        button(text(self.queue.loop_mode().label()).size(12))
+                            .style(match self.queue.loop_mode() {
+                                LoopMode::None => button::secondary,
+                                _ => button::primary,
+                            })
+                            .on_press(PlayerWidgetEvent::LoopMode(self.queue.loop_mode().cycle())),
+                        button(text("Shuffle").size(12))
+                            .style(match self.queue.shuffle() {
+                                true => button::primary,
+                                false => button::secondary,
+                            })
+                            .on_press(PlayerWidgetEvent::Shuffle(!self.queue.shuffle())),
+                    ]
+                    .spacing(4)
+                )
+                .push(column![
                     row![
                         slider(0.0..=100.0, volume, |v| PlayerWidgetEvent::Volume(v * 0.01))
                             .step(1.0)
@@ -142,27 +821,147 @@ impl PlayerWidget {
                             .width(80),
                         Text::new(format!("x{speed:.2}"))
                     ],
-                ]
-            ]
-            .align_y(Center)
-            .spacing(12),
+                ])
+                .align_y(Center)
+                .spacing(12),
             row![
                 Text::new(position),
                 slider(0.0..=100.0, time, |v| PlayerWidgetEvent::Seek(v * 0.01)),
                 Text::new(duration),
             ]
+            .push_maybe(self.resume_key.is_some().then(|| {
+                button(text("Mark as finished").size(12))
+                    .style(button::secondary)
+                    .on_press(PlayerWidgetEvent::MarkFinished)
+            }))
             .spacing(12)
-            .align_y(Center)
+            .align_y(Center),
+            self.playlist_view(player),
         ]
         .align_x(Center)
         .max_width(800)
         .into()
     }
+
+    fn playlist_view(&self, player: &AudioController) -> Element<'_, PlayerWidgetEvent> {
+        let has_path = !self.playlist_path.trim().is_empty();
+        let path = PathBuf::from(self.playlist_path.trim());
+
+        let mut view = Column::new().spacing(4).push(
+            row![
+                text_input(
+                    "Playlist file (.m3u, .m3u8, .pls, .xspf)",
+                    &self.playlist_path
+                )
+                .on_input(PlayerWidgetEvent::PlaylistPath)
+                .on_submit(PlayerWidgetEvent::OpenPlaylist(path.clone())),
+                button(Text::new("Open"))
+                    .style(button::secondary)
+                    .on_press_maybe(has_path.then_some(PlayerWidgetEvent::OpenPlaylist(path))),
+                button(Text::new("Save queue"))
+                    .style(button::secondary)
+                    .on_press_maybe(
+                        (has_path && !self.queue.tracks().is_empty())
+                            .then_some(PlayerWidgetEvent::SaveQueueRequested)
+                    ),
+                pick_list(
+                    self.devices.as_slice(),
+                    player.output_device(),
+                    PlayerWidgetEvent::SelectDevice
+                )
+                .placeholder("Output device")
+                .on_open(PlayerWidgetEvent::ListDevices)
+                .width(200),
+            ]
+            .spacing(8)
+            .align_y(Center),
+        );
+
+        if let Some(status) = &self.playlist_status {
+            view = view.push(text(status).size(12));
+        }
+
+        if let Some(format) = output_format(player) {
+            view = view.push(text(format).size(12));
+        }
+
+        if let Some(status) = &self.output_status {
+            view = view.push(text(status).size(12));
+        }
+
+        if let Some(status) = &self.decode_status {
+            view = view.push(text(status).size(12));
+        }
+
+        view.into()
+    }
+}
+
+fn track_info(track: &Track) -> Element<'static, PlayerWidgetEvent> {
+    let format = &track.format;
+    let details = [
+        format.codec.clone(),
+        format
+            .sample_rate
+            .map(|r| format!("{:.1} kHz", r as f64 / 1000.0)),
+        format.bits_per_sample.map(|b| format!("{b} bit")),
+        format.channels.map(|c| format!("{c} ch")),
+        format.bitrate.map(|b| format!("{b} kbps")),
+    ];
+
+    column![
+        Text::new(track.display_title().into_owned()).size(18),
+        Text::new(format!(
+            "{} — {}",
+            track.display_artist(),
+            track.display_album()
+        )),
+        text(
+            details
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" · ")
+        )
+        .size(12),
+    ]
+    .width(260)
+    .spacing(2)
+    .into()
+}
+
+/// How the loaded track reaches the device.
+fn output_format(player: &AudioController) -> Option<String> {
+    let buffer = player.shared_audio.load();
+    if buffer.duration() == 0 {
+        return None;
+    }
+
+    let khz = |rate: u32| format!("{:.1} kHz", rate as f64 / 1000.0);
+    let output_rate = player.sample_rate();
+
+    let format = match (player.is_bit_perfect(), buffer.sample_rate == output_rate) {
+        (true, _) => format!("Bit-perfect · {}", khz(output_rate)),
+        (false, true) => format!("Native rate · {}", khz(output_rate)),
+        (false, false) => format!(
+            "Resampling {} → {}",
+            khz(buffer.sample_rate),
+            khz(output_rate)
+        ),
+    };
+
+    Some(match player.callback_frames() {
+        0 => format,
+        frames => format!(
+            "{format} · {frames} frames, {:.0} ms latency",
+            player.output_latency() * 1000.0
+        ),
+    })
 }
 
 fn get_song_position_pretty(player: &AudioController) -> [u8; 5] {
-    let pos = player.get_song_position();
-    let (pm, ps) = format_sample_time(pos, player.sample_rate());
+    let pos = player.get_audible_position();
+    let (pm, ps) = format_sample_time(pos, player.shared_audio.load().sample_rate);
 
     let mut buffer = *b"00:00";
 
@@ -0,0 +1,297 @@
+use std::path::PathBuf;
+
+use iced::Alignment::Center;
+use iced::widget::{Column, Text, button, column, pick_list, row, scrollable, text, text_input};
+use iced::{Element, Fill, Task};
+
+use crate::library::{LibraryIndex, Track, TrackId};
+use crate::playlist::{MatchMode, Order, Rule, RuleField, RuleOp, SmartPlaylist};
+
+pub struct SmartPlaylistWidget {
+    dir: Option<PathBuf>,
+    playlists: Vec<(PathBuf, SmartPlaylist)>,
+    selected: Option<usize>,
+    draft: SmartPlaylist,
+    limit: String,
+    error: Option<String>,
+    /// Evaluated once per change so random ordering stays put between redraws.
+    preview: Vec<TrackId>,
+    /// Picked when a playlist is opened so its random order survives library changes.
+    seed: u64,
+}
+
+impl Default for SmartPlaylistWidget {
+    fn default() -> Self {
+        let dir = SmartPlaylist::default_dir();
+        let playlists = dir
+            .as_deref()
+            .map(SmartPlaylist::load_all)
+            .unwrap_or_default();
+
+        Self {
+            dir,
+            playlists,
+            selected: None,
+            draft: SmartPlaylist::default(),
+            limit: String::new(),
+            error: None,
+            preview: Vec::new(),
+            seed: fastrand::u64(..),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SmartPlaylistEvent {
+    Select(usize),
+    New,
+    Name(String),
+    MatchMode(MatchMode),
+    AddRule,
+    RemoveRule(usize),
+    RuleField(usize, RuleField),
+    RuleOp(usize, RuleOp),
+    RuleValue(usize, String),
+    Limit(String),
+    Order(Order),
+    Save,
+    Delete,
+    PlayRequested,
+    Play(Vec<PathBuf>),
+}
+
+impl SmartPlaylistWidget {
+    pub fn update(
+        &mut self,
+        event: SmartPlaylistEvent,
+        library: &LibraryIndex,
+    ) -> Task<SmartPlaylistEvent> {
+        self.error = None;
+
+        match event {
+            SmartPlaylistEvent::Select(idx) => {
+                if let Some((_, playlist)) = self.playlists.get(idx) {
+                    self.draft = playlist.clone();
+                    self.limit = playlist.limit.map(|l| l.to_string()).unwrap_or_default();
+                    self.selected = Some(idx);
+                    self.seed = fastrand::u64(..);
+                }
+            }
+            SmartPlaylistEvent::New => {
+                self.draft = SmartPlaylist::default();
+                self.limit.clear();
+                self.selected = None;
+                self.seed = fastrand::u64(..);
+            }
+            SmartPlaylistEvent::Name(name) => self.draft.name = name,
+            SmartPlaylistEvent::MatchMode(mode) => self.draft.match_mode = mode,
+            SmartPlaylistEvent::AddRule => self.draft.rules.push(Rule::default()),
+            SmartPlaylistEvent::RemoveRule(idx) => {
+                if idx < self.draft.rules.len() {
+                    self.draft.rules.remove(idx);
+                }
+            }
+            SmartPlaylistEvent::RuleField(idx, field) => {
+                if let Some(rule) = self.draft.rules.get_mut(idx) {
+                    rule.field = field;
+
+                    if !field.kind().ops().contains(&rule.op) {
+                        rule.op = field.kind().ops()[0];
+                    }
+                }
+            }
+            SmartPlaylistEvent::RuleOp(idx, op) => {
+                if let Some(rule) = self.draft.rules.get_mut(idx) {
+                    rule.op = op;
+                }
+            }
+            SmartPlaylistEvent::RuleValue(idx, value) => {
+                if let Some(rule) = self.draft.rules.get_mut(idx) {
+                    rule.value = value;
+                }
+            }
+            SmartPlaylistEvent::Limit(limit) => {
+                self.draft.limit = limit.trim().parse().ok();
+                self.limit = limit;
+            }
+            SmartPlaylistEvent::Order(order) => self.draft.order = order,
+            SmartPlaylistEvent::Save => self.save(),
+            SmartPlaylistEvent::Delete => self.delete(),
+            SmartPlaylistEvent::PlayRequested => {
+                let paths = self
+                    .preview(library)
+                    .into_iter()
+                    .map(|t| t.path.clone())
+                    .collect();
+
+                return Task::done(SmartPlaylistEvent::Play(paths));
+            }
+            SmartPlaylistEvent::Play(_) => return Task::none(),
+        }
+
+        self.refresh(library);
+        Task::none()
+    }
+
+    pub fn refresh(&mut self, library: &LibraryIndex) {
+        self.preview = self
+            .draft
+            .evaluate(library, self.seed)
+            .iter()
+            .map(|t| t.id)
+            .collect();
+    }
+
+    pub fn preview<'a>(&self, library: &'a LibraryIndex) -> Vec<&'a Track> {
+        self.preview
+            .iter()
+            .filter_map(|id| library.get(*id))
+            .collect()
+    }
+
+    fn save(&mut self) {
+        let Some(dir) = self.dir.as_deref() else {
+            self.error = Some("No data directory to save playlists in.".to_string());
+            return;
+        };
+
+        let old_path = self
+            .selected
+            .and_then(|idx| self.playlists.get(idx))
+            .map(|(path, _)| path.as_path());
+
+        let path = match self.draft.save_in(dir, old_path) {
+            Ok(path) => path,
+            Err(err) => {
+                self.error = Some(err.to_string());
+                return;
+            }
+        };
+
+        // Renaming a playlist moves it to a new file.
+        if let Some(old_path) = old_path
+            && *old_path != path
+        {
+            std::fs::remove_file(old_path).ok();
+        }
+
+        self.playlists = SmartPlaylist::load_all(dir);
+        self.selected = self.playlists.iter().position(|(p, _)| *p == path);
+    }
+
+    fn delete(&mut self) {
+        let Some(idx) = self.selected.take() else {
+            return;
+        };
+
+        let (path, _) = self.playlists.remove(idx);
+
+        if let Err(err) = std::fs::remove_file(&path) {
+            self.error = Some(err.to_string());
+        }
+
+        self.draft = SmartPlaylist::default();
+        self.limit.clear();
+    }
+
+    pub fn view(&self) -> Element<'_, SmartPlaylistEvent> {
+        let saved = self
+            .playlists
+            .iter()
+            .enumerate()
+            .map(|(idx, (_, playlist))| {
+                let style = match self.selected == Some(idx) {
+                    true => button::primary,
+                    false => button::text,
+                };
+
+                button(Text::new(playlist.name.as_str()))
+                    .style(style)
+                    .width(Fill)
+                    .on_press(SmartPlaylistEvent::Select(idx))
+                    .into()
+            });
+
+        let rules = self.draft.rules.iter().enumerate().map(|(idx, rule)| {
+            row![
+                pick_list(RuleField::ALL, Some(rule.field), move |f| {
+                    SmartPlaylistEvent::RuleField(idx, f)
+                })
+                .width(130),
+                pick_list(rule.field.kind().ops(), Some(rule.op), move |op| {
+                    SmartPlaylistEvent::RuleOp(idx, op)
+                })
+                .width(150),
+                text_input("value", &rule.value)
+                    .on_input(move |v| SmartPlaylistEvent::RuleValue(idx, v))
+                    .width(Fill),
+                button(Text::new("−"))
+                    .style(button::text)
+                    .on_press(SmartPlaylistEvent::RemoveRule(idx)),
+            ]
+            .spacing(4)
+            .align_y(Center)
+            .into()
+        });
+
+        let invalid = self.draft.validate().err().map(|e| e.to_string());
+        let problem = self.error.clone().or(invalid.clone());
+
+        let mut editor = column![
+            row![
+                text_input("Name", &self.draft.name)
+                    .on_input(SmartPlaylistEvent::Name)
+                    .width(Fill),
+                button(Text::new("New"))
+                    .style(button::secondary)
+                    .on_press(SmartPlaylistEvent::New),
+            ]
+            .spacing(4),
+            pick_list(
+                [MatchMode::All, MatchMode::Any],
+                Some(self.draft.match_mode),
+                SmartPlaylistEvent::MatchMode
+            ),
+            Column::with_children(rules).spacing(4),
+            button(Text::new("Add rule"))
+                .style(button::text)
+                .on_press(SmartPlaylistEvent::AddRule),
+            row![
+                Text::new("Limit"),
+                text_input("none", &self.limit)
+                    .on_input(SmartPlaylistEvent::Limit)
+                    .width(60),
+                Text::new("Order"),
+                pick_list(
+                    Order::ALL,
+                    Some(self.draft.order),
+                    SmartPlaylistEvent::Order
+                ),
+            ]
+            .spacing(8)
+            .align_y(Center),
+            row![
+                button(Text::new("Play")).on_press(SmartPlaylistEvent::PlayRequested),
+                button(Text::new("Save"))
+                    .style(button::secondary)
+                    .on_press_maybe(invalid.is_none().then_some(SmartPlaylistEvent::Save)),
+                button(Text::new("Delete"))
+                    .style(button::danger)
+                    .on_press_maybe(self.selected.map(|_| SmartPlaylistEvent::Delete)),
+            ]
+            .spacing(8),
+        ]
+        .spacing(8);
+
+        if let Some(problem) = problem {
+            editor = editor.push(text(problem).size(12).style(text::danger));
+        }
+
+        column![
+            scrollable(Column::with_children(saved).spacing(2)).height(120),
+            editor,
+        ]
+        .spacing(12)
+        .into()
+    }
+}
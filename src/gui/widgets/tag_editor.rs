@@ -0,0 +1,234 @@
+use std::path::PathBuf;
+
+use iced::Alignment::Center;
+use iced::widget::{Column, Text, button, column, row, text, text_input};
+use iced::{Element, Fill, Task};
+
+use crate::library::{LibraryIndex, TagEdit, Track};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagField {
+    Title,
+    Artist,
+    AlbumArtist,
+    Album,
+    Genre,
+    Year,
+    TrackNumber,
+}
+
+/// Edits the tags of one or more files at once.
+pub struct TagEditor {
+    paths: Vec<PathBuf>,
+    /// The value every edited track agrees on, `None` when they differ.
+    shared: [Option<String>; TagField::ALL.len()],
+    values: [String; TagField::ALL.len()],
+    /// Fields to remove from every track. An empty value alone leaves differing fields as
+    /// they are.
+    cleared: [bool; TagField::ALL.len()],
+}
+
+#[derive(Debug, Clone)]
+pub enum TagEditorEvent {
+    Field(TagField, String),
+    /// Toggles removing a field that differs between the tracks.
+    Clear(TagField),
+    Save,
+    Close,
+    Write(Vec<PathBuf>, TagEdit),
+}
+
+impl TagField {
+    pub const ALL: [TagField; 7] = [
+        Self::Title,
+        Self::Artist,
+        Self::AlbumArtist,
+        Self::Album,
+        Self::Genre,
+        Self::Year,
+        Self::TrackNumber,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Title => "Title",
+            Self::Artist => "Artist",
+            Self::AlbumArtist => "Album artist",
+            Self::Album => "Album",
+            Self::Genre => "Genre",
+            Self::Year => "Year",
+            Self::TrackNumber => "Track",
+        }
+    }
+
+    fn value(&self, track: &Track) -> String {
+        let value = match self {
+            Self::Title => track.title.clone(),
+            Self::Artist => track.artist.clone(),
+            Self::AlbumArtist => track.album_artist.clone(),
+            Self::Album => track.album.clone(),
+            Self::Genre => track.genre.clone(),
+            Self::Year => track.year.map(|y| y.to_string()),
+            Self::TrackNumber => track.track_number.map(|n| n.to_string()),
+        };
+
+        value.unwrap_or_default()
+    }
+
+    fn slot<'a>(&self, edit: &'a mut TagEdit) -> &'a mut Option<String> {
+        match self {
+            Self::Title => &mut edit.title,
+            Self::Artist => &mut edit.artist,
+            Self::AlbumArtist => &mut edit.album_artist,
+            Self::Album => &mut edit.album,
+            Self::Genre => &mut edit.genre,
+            Self::Year => &mut edit.year,
+            Self::TrackNumber => &mut edit.track_number,
+        }
+    }
+}
+
+impl TagEditor {
+    pub fn new(paths: Vec<PathBuf>, library: &LibraryIndex) -> Self {
+        let mut editor = Self {
+            paths,
+            shared: Default::default(),
+            values: Default::default(),
+            cleared: Default::default(),
+        };
+
+        editor.reload(library);
+        editor
+    }
+
+    /// Picks up the current tags, unless there are unsaved changes.
+    pub fn reload(&mut self, library: &LibraryIndex) {
+        if !self.edit().is_empty() {
+            return;
+        }
+
+        let tracks: Vec<_> = self
+            .paths
+            .iter()
+            .filter_map(|path| library.get_by_path(path))
+            .collect();
+
+        for (idx, field) in TagField::ALL.iter().enumerate() {
+            let mut values = tracks.iter().map(|track| field.value(track));
+            let first = values.next().unwrap_or_default();
+
+            self.shared[idx] = values.all(|v| v == first).then_some(first);
+            self.values[idx] = self.shared[idx].clone().unwrap_or_default();
+            self.cleared[idx] = false;
+        }
+    }
+
+    /// Only fields that were changed are written, so batch edits keep per-track values.
+    fn edit(&self) -> TagEdit {
+        let mut edit = TagEdit::default();
+
+        for (idx, field) in TagField::ALL.iter().enumerate() {
+            let shared = self.shared[idx].as_deref().unwrap_or_default();
+
+            if self.cleared[idx] {
+                *field.slot(&mut edit) = Some(String::new());
+            } else if self.values[idx] != shared {
+                *field.slot(&mut edit) = Some(self.values[idx].clone());
+            }
+        }
+
+        edit
+    }
+
+    pub fn update(&mut self, event: TagEditorEvent) -> Task<TagEditorEvent> {
+        match event {
+            TagEditorEvent::Field(field, value) => {
+                if let Some(idx) = TagField::ALL.iter().position(|f| *f == field) {
+                    self.values[idx] = value;
+                    self.cleared[idx] = false;
+                }
+            }
+            TagEditorEvent::Clear(field) => {
+                if let Some(idx) = TagField::ALL.iter().position(|f| *f == field) {
+                    self.cleared[idx] = !self.cleared[idx];
+                    self.values[idx].clear();
+                }
+            }
+            TagEditorEvent::Save => {
+                let edit = self.edit();
+
+                // What was written is what the tracks now share.
+                for (idx, value) in self.values.iter().enumerate() {
+                    if self.shared[idx].is_some() || self.cleared[idx] || !value.is_empty() {
+                        self.shared[idx] = Some(value.clone());
+                    }
+                }
+                self.cleared = Default::default();
+
+                return Task::done(TagEditorEvent::Write(self.paths.clone(), edit));
+            }
+            TagEditorEvent::Close | TagEditorEvent::Write(..) => {}
+        }
+
+        Task::none()
+    }
+
+    pub fn view(&self) -> Element<'_, TagEditorEvent> {
+        let title = match self.paths.len() {
+            1 => "Edit tags".to_string(),
+            n => format!("Edit tags of {n} tracks"),
+        };
+
+        let fields = TagField::ALL.iter().enumerate().map(|(idx, field)| {
+            let placeholder = match (self.shared[idx].is_some(), self.cleared[idx]) {
+                (_, true) => "(removed from all)",
+                (true, false) => "",
+                (false, false) => "(multiple values)",
+            };
+
+            let mut input = row![
+                text_input(placeholder, &self.values[idx])
+                    .on_input(|value| TagEditorEvent::Field(*field, value))
+                    .on_submit(TagEditorEvent::Save),
+            ]
+            .spacing(4)
+            .align_y(Center);
+
+            // A shared value is cleared by emptying it, differing ones need asking.
+            if self.shared[idx].is_none() {
+                let label = match self.cleared[idx] {
+                    true => "Keep",
+                    false => "Clear",
+                };
+
+                input = input.push(
+                    button(Text::new(label).size(12))
+                        .style(button::text)
+                        .on_press(TagEditorEvent::Clear(*field)),
+                );
+            }
+
+            column![text(field.label()).size(12), input]
+                .spacing(2)
+                .into()
+        });
+
+        let dirty = !self.edit().is_empty();
+
+        column![
+            Text::new(title).size(18),
+            Column::with_children(fields).spacing(8),
+            row![
+                button(Text::new("Save")).on_press_maybe(dirty.then_some(TagEditorEvent::Save)),
+                button(Text::new("Close"))
+                    .style(button::secondary)
+                    .on_press(TagEditorEvent::Close),
+            ]
+            .spacing(8)
+            .align_y(Center),
+        ]
+        .spacing(12)
+        .width(Fill)
+        .into()
+    }
+}
@@ -0,0 +1,100 @@
+use std::time::{Duration, Instant};
+
+use iced::widget::{Column, button, container, row, text};
+use iced::{Bottom, Center, Element, Fill, Right, Subscription, time};
+
+/// How long a toast stays up unless it's dismissed.
+const TOAST_DURATION: Duration = Duration::from_secs(8);
+/// The oldest toasts go when there are more.
+const MAX_TOASTS: usize = 4;
+
+struct Toast {
+    id: u64,
+    message: String,
+    shown_at: Instant,
+}
+
+/// Errors shown over the app for a while, newest at the bottom.
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    next_id: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum ToastEvent {
+    Dismiss(u64),
+    Tick(Instant),
+}
+
+impl Toasts {
+    pub fn push(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        log::warn!("{message}");
+
+        // The same failure repeating, like every track of a queue failing to decode.
+        if let Some(last) = self.toasts.last_mut()
+            && last.message == message
+        {
+            last.shown_at = Instant::now();
+            return;
+        }
+
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+
+        self.toasts.push(Toast {
+            id: self.next_id,
+            message,
+            shown_at: Instant::now(),
+        });
+        self.next_id += 1;
+    }
+
+    pub fn update(&mut self, event: ToastEvent) {
+        match event {
+            ToastEvent::Dismiss(id) => self.toasts.retain(|toast| toast.id != id),
+            ToastEvent::Tick(now) => self
+                .toasts
+                .retain(|toast| now.duration_since(toast.shown_at) < TOAST_DURATION),
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<ToastEvent> {
+        match self.toasts.is_empty() {
+            true => Subscription::none(),
+            false => time::every(Duration::from_secs(1)).map(ToastEvent::Tick),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, ToastEvent> {
+        let toasts = self.toasts.iter().map(|toast| {
+            container(
+                row![
+                    text(&toast.message)
+                        .size(14)
+                        .style(text::danger)
+                        .width(Fill),
+                    button(text("Dismiss").size(12))
+                        .style(button::text)
+                        .on_press(ToastEvent::Dismiss(toast.id)),
+                ]
+                .spacing(8)
+                .align_y(Center),
+            )
+            .padding(10)
+            .width(360)
+            .style(container::rounded_box)
+            .into()
+        });
+
+        container(Column::with_children(toasts).spacing(8))
+            .padding(20)
+            .width(Fill)
+            .height(Fill)
+            .align_x(Right)
+            .align_y(Bottom)
+            .into()
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs::{self, DirBuilder};
+use std::io::{self, ErrorKind};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+mod client;
+mod error;
+mod server;
+
+pub use client::*;
+pub use error::*;
+pub use server::*;
+
+use crate::remote::RemoteStatus;
+
+/// One line of JSON sent to the running instance, answered by one `IpcResponse` line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum IpcRequest {
+    Play,
+    Pause,
+    Toggle,
+    Stop,
+    Next,
+    Previous,
+    Status,
+    Enqueue {
+        paths: Vec<PathBuf>,
+    },
+    /// Plays an audio file or loads a playlist, like passing it on the command line.
+    Open {
+        path: PathBuf,
+    },
+    /// Brings the window to the front, for when the app is started again.
+    Raise,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum IpcResponse {
+    Ok,
+    Status(Box<RemoteStatus>),
+    Error { message: String },
+}
+
+pub fn socket_path() -> io::Result<PathBuf> {
+    let dir = match dirs::runtime_dir() {
+        Some(dir) => dir,
+        None => private_temp_dir()?,
+    };
+
+    Ok(dir.join("cozy-music.sock"))
+}
+
+/// The temp dir is shared between users, so the socket goes in a folder only its owner can
+/// enter. One left open by someone else is refused rather than used.
+fn private_temp_dir() -> io::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!(
+        "cozy-music-{}",
+        std::env::var("USER").unwrap_or_default()
+    ));
+
+    match DirBuilder::new().mode(0o700).create(&dir) {
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+        res => res?,
+    }
+
+    let metadata = fs::symlink_metadata(&dir)?;
+
+    if !metadata.is_dir() || metadata.permissions().mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is open to other users", dir.display()),
+        ));
+    }
+
+    Ok(dir)
+}
@@ -0,0 +1,49 @@
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+use super::{IpcError, IpcRequest, IpcResponse, socket_path};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sends `request` to the running instance.
+pub fn send(request: &IpcRequest) -> Result<IpcResponse, IpcError> {
+    let mut stream = UnixStream::connect(socket_path()?).map_err(|err| match err.kind() {
+        ErrorKind::NotFound | ErrorKind::ConnectionRefused => IpcError::NotRunning,
+        _ => err.into(),
+    })?;
+
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    writeln!(stream, "{}", serde_json::to_string(request)?)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+
+    match serde_json::from_str(&line)? {
+        IpcResponse::Error { message } => Err(IpcError::Failed(message)),
+        response => Ok(response),
+    }
+}
+
+/// Passes `input` on to an instance that's already running, or brings its window to the front
+/// without any. Returns false when there's none.
+pub fn hand_over(input: Option<&str>) -> Result<bool, IpcError> {
+    let request = match input {
+        // The other instance may have a different working directory.
+        Some(input) => IpcRequest::Open {
+            path: Path::new(input)
+                .canonicalize()
+                .unwrap_or_else(|_| input.into()),
+        },
+        None => IpcRequest::Raise,
+    };
+
+    match send(&request) {
+        Ok(_) => Ok(true),
+        Err(IpcError::NotRunning) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
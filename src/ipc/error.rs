@@ -0,0 +1,14 @@
+#[derive(Debug, thiserror::Error)]
+pub enum IpcError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Cozy music isn't running.")]
+    NotRunning,
+
+    #[error("{0}")]
+    Failed(String),
+}
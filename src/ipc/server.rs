@@ -0,0 +1,113 @@
+use std::future::Future;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::Arc;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::watch;
+
+use super::{IpcError, IpcRequest, IpcResponse, socket_path};
+use crate::remote::RemoteStatus;
+
+type RequestHandler = Arc<dyn Fn(IpcRequest) + Send + Sync>;
+
+/// Handle to the socket other invocations talk to.
+#[derive(Debug, Clone)]
+pub struct Ipc {
+    status: watch::Sender<RemoteStatus>,
+}
+
+impl Ipc {
+    /// Binds the socket. The returned future answers requests until it fails.
+    pub fn bind<F>(
+        on_request: F,
+    ) -> Result<(Self, impl Future<Output = std::io::Result<()>> + use<F>), IpcError>
+    where
+        F: Fn(IpcRequest) + Send + Sync + 'static,
+    {
+        let listener = bind_socket(&socket_path()?)?;
+        let (status, _) = watch::channel(RemoteStatus::default());
+        let on_request: RequestHandler = Arc::new(on_request);
+        let receiver = status.subscribe();
+
+        let serve = async move {
+            loop {
+                let (stream, _) = listener.accept().await?;
+                let on_request = on_request.clone();
+                let status = receiver.clone();
+
+                tokio::spawn(async move {
+                    if let Err(err) = handle(stream, on_request, status).await {
+                        log::warn!("IPC client error: {err}");
+                    }
+                });
+            }
+        };
+
+        Ok((Self { status }, serve))
+    }
+
+    pub fn update_status(&self, status: RemoteStatus) {
+        self.status.send_replace(status);
+    }
+}
+
+/// Takes over a socket left behind by an instance that didn't shut down cleanly.
+fn bind_socket(path: &Path) -> std::io::Result<UnixListener> {
+    match UnixListener::bind(path) {
+        Err(err) if err.kind() == ErrorKind::AddrInUse => {
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                return Err(err);
+            }
+
+            std::fs::remove_file(path)?;
+            UnixListener::bind(path)
+        }
+        res => res,
+    }
+}
+
+async fn handle(
+    stream: UnixStream,
+    on_request: RequestHandler,
+    status: watch::Receiver<RemoteStatus>,
+) -> Result<(), IpcError> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let response = match serde_json::from_str(&line) {
+            Ok(IpcRequest::Status) => IpcResponse::Status(Box::new(status.borrow().clone())),
+            Ok(request) => match validate(&request) {
+                Ok(()) => {
+                    on_request(request);
+                    IpcResponse::Ok
+                }
+                Err(message) => IpcResponse::Error { message },
+            },
+            Err(err) => IpcResponse::Error {
+                message: format!("Invalid request: {err}"),
+            },
+        };
+
+        let mut line = serde_json::to_string(&response)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+fn validate(request: &IpcRequest) -> Result<(), String> {
+    let paths = match request {
+        IpcRequest::Enqueue { paths } => paths.as_slice(),
+        IpcRequest::Open { path } => std::slice::from_ref(path),
+        _ => return Ok(()),
+    };
+
+    match paths.iter().find(|p| !p.is_file()) {
+        Some(missing) => Err(format!("{} is not a file.", missing.display())),
+        None => Ok(()),
+    }
+}
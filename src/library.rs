@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+mod cover;
+mod error;
+mod history;
+mod query;
+mod resume;
+mod scan;
+mod search;
+mod stats;
+mod tags;
+mod track;
+mod watcher;
+
+pub mod event;
+
+pub use cover::*;
+pub use error::*;
+pub use history::*;
+pub use query::*;
+pub use resume::*;
+pub use scan::*;
+pub use search::*;
+pub use stats::*;
+pub use tags::*;
+pub use track::*;
+pub use watcher::*;
+
+#[derive(Debug, Clone, Default)]
+pub struct Library {
+    folders: Arc<Vec<PathBuf>>,
+    index: Arc<RwLock<LibraryIndex>>,
+    history_path: Option<Arc<PathBuf>>,
+}
+
+#[derive(Debug, Default)]
+pub struct LibraryIndex {
+    tracks: HashMap<TrackId, Track>,
+    search_keys: HashMap<TrackId, SearchKeys>,
+    stats: HashMap<TrackId, PlayStats>,
+    history: Vec<PlayRecord>,
+}
+
+impl Library {
+    pub fn new(folders: Vec<PathBuf>) -> Self {
+        Self {
+            folders: Arc::new(folders),
+            index: Arc::default(),
+            history_path: None,
+        }
+    }
+
+    /// Records plays to the file at `path`, see [`Library::record_play`].
+    pub fn with_history(mut self, path: Option<PathBuf>) -> Self {
+        self.history_path = path.map(Arc::new);
+        self
+    }
+
+    pub fn default_folders() -> Vec<PathBuf> {
+        dirs::audio_dir().into_iter().collect()
+    }
+
+    pub fn folders(&self) -> &[PathBuf] {
+        &self.folders
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, LibraryIndex> {
+        self.index.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, LibraryIndex> {
+        self.index.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[allow(unused)]
+impl LibraryIndex {
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    pub fn get(&self, id: TrackId) -> Option<&Track> {
+        self.tracks.get(&id)
+    }
+
+    pub fn get_by_path<P: AsRef<Path> + ?Sized>(&self, path: &P) -> Option<&Track> {
+        self.get(TrackId::from_path(path))
+    }
+
+    pub fn tracks(&self) -> impl Iterator<Item = &Track> {
+        self.tracks.values()
+    }
+
+    /// Returns the previous entry if the track was already indexed.
+    fn insert(&mut self, track: Track) -> Option<Track> {
+        self.search_keys.insert(track.id, SearchKeys::new(&track));
+        self.tracks.insert(track.id, track)
+    }
+
+    fn remove(&mut self, id: TrackId) -> Option<Track> {
+        self.search_keys.remove(&id);
+        self.tracks.remove(&id)
+    }
+
+    /// Swaps in the tracks of a fresh scan.
+    fn replace_tracks(&mut self, tracks: HashMap<TrackId, Track>) {
+        self.search_keys = tracks
+            .values()
+            .map(|track| (track.id, SearchKeys::new(track)))
+            .collect();
+        self.tracks = tracks;
+    }
+
+    fn ids_under<P: AsRef<Path> + ?Sized>(&self, dir: &P) -> Vec<TrackId> {
+        self.tracks
+            .values()
+            .filter(|t| t.path.starts_with(dir))
+            .map(|t| t.id)
+            .collect()
+    }
+}
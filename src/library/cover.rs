@@ -0,0 +1,132 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use image::ImageFormat;
+use symphonia::core::meta::{StandardVisualKey, Visual};
+
+use super::{Fnv1a, LibraryError, probe};
+
+const FOLDER_COVER_NAMES: &[&str] = &["cover", "folder", "front", "album"];
+const FOLDER_COVER_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png"];
+
+/// Scaled down copies of cover art, stored on disk so they survive restarts.
+#[derive(Debug, Clone)]
+pub struct CoverCache {
+    dir: PathBuf,
+}
+
+impl CoverCache {
+    pub const THUMBNAIL_SIZE: u32 = 256;
+
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("cozy-music").join("covers"))
+    }
+
+    /// Thumbnail of the cover for `track`, created on first use. `None` if it has no cover.
+    pub fn thumbnail(&self, track: &Path) -> Result<Option<PathBuf>, LibraryError> {
+        let folder_image = track.parent().and_then(folder_cover);
+        let key = cache_key(track, folder_image.as_deref())?;
+        let cached = self.dir.join(format!("{key:016x}.png"));
+
+        if cached.is_file() {
+            return Ok(Some(cached));
+        }
+
+        let image = match embedded_cover(track)? {
+            Some(data) => image::load_from_memory(&data)?,
+            None => match folder_image {
+                Some(path) => image::open(path)?,
+                None => return Ok(None),
+            },
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        image
+            .thumbnail(Self::THUMBNAIL_SIZE, Self::THUMBNAIL_SIZE)
+            .save_with_format(&cached, ImageFormat::Png)?;
+
+        Ok(Some(cached))
+    }
+}
+
+/// FNV-1a over the paths and modification times of the track and its folder image, so
+/// re-tagging the file or replacing the image gives a fresh thumbnail.
+fn cache_key(track: &Path, folder_image: Option<&Path>) -> Result<u64, LibraryError> {
+    let mut hash = Fnv1a::new();
+
+    for path in [Some(track), folder_image].into_iter().flatten() {
+        let bytes = path.as_os_str().as_encoded_bytes();
+        let modified = fs::metadata(path)?
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+
+        // The length keeps one path's end from reading as the next field.
+        hash.write(&(bytes.len() as u64).to_le_bytes());
+        hash.write(bytes);
+        hash.write(&modified.to_le_bytes());
+    }
+
+    Ok(hash.finish())
+}
+
+/// The picture embedded in the file's tags (ID3 APIC, FLAC PICTURE, MP4 covr), preferring the
+/// front cover when there are several.
+pub fn embedded_cover(path: &Path) -> Result<Option<Box<[u8]>>, LibraryError> {
+    let mut probe = probe(path)?;
+    let mut visuals: Vec<Visual> = Vec::new();
+
+    if let Some(metadata) = probe.metadata.get()
+        && let Some(revision) = metadata.current()
+    {
+        visuals.extend_from_slice(revision.visuals());
+    }
+
+    if let Some(revision) = probe.format.metadata().current() {
+        visuals.extend_from_slice(revision.visuals());
+    }
+
+    let front = visuals
+        .iter()
+        .position(|v| v.usage == Some(StandardVisualKey::FrontCover))
+        .unwrap_or(0);
+
+    Ok((front < visuals.len()).then(|| visuals.swap_remove(front).data))
+}
+
+/// A `cover.jpg`, `folder.png` or similar image next to the audio files.
+pub fn folder_cover(dir: &Path) -> Option<PathBuf> {
+    let mut candidates: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let matches = |value: Option<&std::ffi::OsStr>, names: &[&str]| {
+                value
+                    .and_then(|v| v.to_str())
+                    .is_some_and(|v| names.iter().any(|n| n.eq_ignore_ascii_case(v)))
+            };
+
+            matches(path.file_stem(), FOLDER_COVER_NAMES)
+                && matches(path.extension(), FOLDER_COVER_EXTENSIONS)
+        })
+        .collect();
+
+    // Prefer names in the order listed above, so `cover` wins over `folder`.
+    candidates.sort_by_key(|path| {
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        FOLDER_COVER_NAMES
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(stem))
+    });
+
+    candidates.into_iter().next()
+}
@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LibraryError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Symphonia(#[from] symphonia::core::errors::Error),
+
+    #[error("{0}")]
+    Watch(#[from] notify::Error),
+
+    #[error("{0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("{0}")]
+    TagRead(#[from] lofty::error::FileParseError),
+
+    #[error("{0}")]
+    TagWrite(#[from] lofty::error::FileEncodingError),
+
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Can only export to .csv or .json, not {}.", .0.display())]
+    UnsupportedExport(PathBuf),
+}
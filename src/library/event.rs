@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+use super::{LibraryError, Track, TrackId};
+
+#[allow(unused)]
+#[derive(Debug, Clone)]
+pub enum LibraryEvent {
+    Scanned(usize),
+    Added(Track),
+    Updated(Track),
+    Moved { from: TrackId, track: Track },
+    Removed(TrackId),
+    Error(Arc<LibraryError>),
+}
+
+impl From<LibraryError> for LibraryEvent {
+    fn from(value: LibraryError) -> Self {
+        Self::Error(Arc::new(value))
+    }
+}
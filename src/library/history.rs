@@ -0,0 +1,219 @@
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Library, LibraryError, LibraryIndex, Track, TrackId};
+use crate::scrobble::Listen;
+
+/// One listen of a track, as written to the history file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayRecord {
+    pub track: TrackId,
+    pub path: PathBuf,
+    /// Kept so the history still reads well after files are moved or deleted.
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    /// Unix timestamp in seconds.
+    pub started_at: u64,
+    /// Seconds actually listened, excluding pauses.
+    pub listened: f64,
+    pub outcome: PlayOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayOutcome {
+    Completed,
+    Skipped,
+}
+
+impl PlayOutcome {
+    /// A play counts once it was heard long enough to be scrobbled, or to the end.
+    pub fn from_listened(listened: f64, duration: f64, reached_end: bool) -> Self {
+        match reached_end || Listen::should_scrobble(duration, listened) {
+            true => Self::Completed,
+            false => Self::Skipped,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+impl Library {
+    pub fn default_history_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("cozy-music").join("history.jsonl"))
+    }
+
+    /// Reads the history file into the index and rebuilds play statistics from it.
+    pub fn load_history(&self) -> Result<usize, LibraryError> {
+        let Some(path) = self.history_path.as_deref() else {
+            return Ok(0);
+        };
+
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut records = Vec::new();
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(err) => log::warn!("Skipping history entry: {err}"),
+            }
+        }
+
+        let count = records.len();
+        let mut index = self.write();
+
+        index.history.clear();
+        index.stats.clear();
+
+        for record in records {
+            index.apply_play(record);
+        }
+
+        Ok(count)
+    }
+
+    /// Appends `record` to the history file and updates play statistics.
+    pub fn record_play(&self, record: PlayRecord) -> Result<(), LibraryError> {
+        // Held while writing, so a rewrite of the file can't drop the record.
+        let mut index = self.write();
+
+        if let Some(path) = self.history_path.as_deref() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        }
+
+        index.apply_play(record);
+        Ok(())
+    }
+
+    /// Rewrites the history file from `index`, after plays moved to other tracks.
+    pub(super) fn save_history(&self, index: &LibraryIndex) -> Result<(), LibraryError> {
+        let Some(path) = self.history_path.as_deref() else {
+            return Ok(());
+        };
+
+        let mut content = String::new();
+
+        for record in &index.history {
+            content.push_str(&serde_json::to_string(record)?);
+            content.push('\n');
+        }
+
+        let tmp = path.with_extension("jsonl.tmp");
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+impl LibraryIndex {
+    /// Oldest first.
+    pub fn history(&self) -> &[PlayRecord] {
+        &self.history
+    }
+
+    fn apply_play(&mut self, record: PlayRecord) {
+        if record.outcome == PlayOutcome::Completed {
+            let stats = self.stats.entry(record.track).or_default();
+
+            stats.play_count += 1;
+            stats.last_played = stats.last_played.max(Some(record.started_at));
+        }
+
+        self.history.push(record);
+    }
+
+    /// Carries the plays of a moved track over to its new ID. Returns whether it had any.
+    pub(super) fn move_plays(&mut self, from: TrackId, to: &Track) -> bool {
+        if let Some(stats) = self.stats.remove(&from) {
+            self.stats.insert(to.id, stats);
+        }
+
+        let mut moved = false;
+
+        for record in self
+            .history
+            .iter_mut()
+            .filter(|record| record.track == from)
+        {
+            record.track = to.id;
+            record.path = to.path.clone();
+            moved = true;
+        }
+
+        moved
+    }
+
+    /// Writes the history as CSV or JSON, depending on the extension of `path`.
+    pub fn export_history(&self, path: &Path) -> Result<(), LibraryError> {
+        export_history(&self.history, path)
+    }
+}
+
+/// Writes `records` as CSV or JSON, depending on the extension of `path`.
+pub fn export_history(records: &[PlayRecord], path: &Path) -> Result<(), LibraryError> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+
+    let content = match ext.as_deref() {
+        Some("json") => serde_json::to_string_pretty(records)?,
+        Some("csv") => history_csv(records),
+        _ => return Err(LibraryError::UnsupportedExport(path.to_path_buf())),
+    };
+
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn history_csv(records: &[PlayRecord]) -> String {
+    let mut out = String::from("started_at,track_id,artist,title,path,listened,outcome\n");
+
+    for record in records {
+        let fields = [
+            record.started_at.to_string(),
+            format!("{:016x}", record.track.0),
+            csv_field(record.artist.as_deref().unwrap_or_default()),
+            csv_field(record.title.as_deref().unwrap_or_default()),
+            csv_field(&record.path.to_string_lossy()),
+            format!("{:.1}", record.listened),
+            record.outcome.label().to_string(),
+        ];
+
+        writeln!(out, "{}", fields.join(",")).ok();
+    }
+
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
@@ -0,0 +1,166 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use super::{LibraryIndex, Track};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TrackFilter {
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+}
+
+impl TrackFilter {
+    pub fn matches(&self, track: &Track) -> bool {
+        if let Some(artist) = &self.artist
+            && track.display_artist() != artist
+            && track.display_album_artist() != artist
+        {
+            return false;
+        }
+
+        if let Some(album) = &self.album
+            && track.display_album() != album
+        {
+            return false;
+        }
+
+        if let Some(genre) = &self.genre
+            && track.genre.as_ref() != Some(genre)
+        {
+            return false;
+        }
+
+        if self.year.is_some() && track.year != self.year {
+            return false;
+        }
+
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortColumn {
+    TrackNumber,
+    Title,
+    #[default]
+    Artist,
+    Album,
+    Genre,
+    Year,
+    Duration,
+}
+
+impl SortColumn {
+    pub const ALL: [SortColumn; 7] = [
+        Self::TrackNumber,
+        Self::Title,
+        Self::Artist,
+        Self::Album,
+        Self::Genre,
+        Self::Year,
+        Self::Duration,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::TrackNumber => "#",
+            Self::Title => "Title",
+            Self::Artist => "Artist",
+            Self::Album => "Album",
+            Self::Genre => "Genre",
+            Self::Year => "Year",
+            Self::Duration => "Duration",
+        }
+    }
+
+    /// Falls back to artist, album, track number and title so the order is always stable.
+    pub fn compare(&self, a: &Track, b: &Track) -> Ordering {
+        let primary = match self {
+            Self::TrackNumber => a.track_number.cmp(&b.track_number),
+            Self::Title => a.display_title().cmp(&b.display_title()),
+            Self::Artist => a.display_artist().cmp(b.display_artist()),
+            Self::Album => a.display_album().cmp(b.display_album()),
+            Self::Genre => a.genre.cmp(&b.genre),
+            Self::Year => a.year.cmp(&b.year),
+            Self::Duration => a.duration.total_cmp(&b.duration),
+        };
+
+        primary
+            .then_with(|| a.display_artist().cmp(b.display_artist()))
+            .then_with(|| a.display_album().cmp(b.display_album()))
+            .then_with(|| a.track_number.cmp(&b.track_number))
+            .then_with(|| a.display_title().cmp(&b.display_title()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Album {
+    pub title: String,
+    pub artist: String,
+    pub year: Option<u32>,
+    pub tracks: usize,
+    /// The album's first file by path, used to look up its cover.
+    pub cover_track: PathBuf,
+}
+
+impl LibraryIndex {
+    pub fn artists(&self) -> Vec<String> {
+        let artists: BTreeSet<_> = self.tracks().map(|t| t.display_album_artist()).collect();
+        artists.into_iter().map(str::to_string).collect()
+    }
+
+    pub fn genres(&self) -> Vec<String> {
+        let genres: BTreeSet<_> = self.tracks().filter_map(|t| t.genre.as_deref()).collect();
+        genres.into_iter().map(str::to_string).collect()
+    }
+
+    pub fn years(&self) -> Vec<u32> {
+        let years: BTreeSet<_> = self.tracks().filter_map(|t| t.year).collect();
+        years.into_iter().rev().collect()
+    }
+
+    pub fn albums(&self, filter: &TrackFilter) -> Vec<Album> {
+        let mut albums: BTreeMap<(&str, &str), Album> = BTreeMap::new();
+
+        for track in self.tracks().filter(|t| filter.matches(t)) {
+            let key = (track.display_album_artist(), track.display_album());
+
+            albums
+                .entry(key)
+                .and_modify(|album| {
+                    album.tracks += 1;
+                    album.year = album.year.or(track.year);
+
+                    if track.path < album.cover_track {
+                        album.cover_track = track.path.clone();
+                    }
+                })
+                .or_insert_with(|| Album {
+                    title: key.1.to_string(),
+                    artist: key.0.to_string(),
+                    year: track.year,
+                    tracks: 1,
+                    cover_track: track.path.clone(),
+                });
+        }
+
+        albums.into_values().collect()
+    }
+
+    pub fn filtered(&self, filter: &TrackFilter, sort: SortColumn, ascending: bool) -> Vec<&Track> {
+        let tracks = self.tracks().filter(|t| filter.matches(t)).collect();
+        sort_tracks(tracks, sort, ascending)
+    }
+}
+
+pub fn sort_tracks(mut tracks: Vec<&Track>, sort: SortColumn, ascending: bool) -> Vec<&Track> {
+    tracks.sort_by(|a, b| match ascending {
+        true => sort.compare(a, b),
+        false => sort.compare(b, a),
+    });
+
+    tracks
+}
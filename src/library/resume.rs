@@ -0,0 +1,158 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Fnv1a, LibraryError};
+
+/// Bytes hashed from each end of a file, enough to tell files apart without reading
+/// hours of audio.
+const HASH_CHUNK: u64 = 64 * 1024;
+/// Positions closer than this to the start aren't worth keeping.
+const MIN_POSITION: f64 = 30.0;
+/// With less than this left, the track counts as finished.
+const FINISHED_REMAINING: f64 = 30.0;
+/// The least recently updated positions are dropped past this.
+const MAX_ENTRIES: usize = 500;
+
+/// Identifies a file by path and content, so a different file saved under the same name
+/// starts from the beginning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumeKey {
+    pub path: PathBuf,
+    pub hash: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ResumeEntry {
+    path: PathBuf,
+    hash: u64,
+    /// Seconds.
+    position: f64,
+    updated_at: u64,
+}
+
+/// Where long tracks such as audiobooks and podcasts were left off.
+#[derive(Debug)]
+pub struct ResumePositions {
+    path: PathBuf,
+    entries: HashMap<PathBuf, ResumeEntry>,
+}
+
+impl ResumeKey {
+    /// FNV-1a over the file size and its first and last 64 KiB.
+    pub fn for_file(path: &Path) -> Result<Self, LibraryError> {
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+
+        let mut hash = Fnv1a::new();
+        hash.write(&size.to_le_bytes());
+
+        let mut chunk = Vec::with_capacity(HASH_CHUNK as usize);
+        (&mut file).take(HASH_CHUNK).read_to_end(&mut chunk)?;
+        hash.write(&chunk);
+
+        if size > HASH_CHUNK * 2 {
+            chunk.clear();
+            file.seek(SeekFrom::End(-(HASH_CHUNK as i64)))?;
+            file.read_to_end(&mut chunk)?;
+            hash.write(&chunk);
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            hash: hash.finish(),
+        })
+    }
+}
+
+impl ResumePositions {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("cozy-music").join("resume.json"))
+    }
+
+    pub fn load(path: PathBuf) -> Result<Self, LibraryError> {
+        let entries: Vec<ResumeEntry> = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Self {
+            path,
+            entries: entries.into_iter().map(|e| (e.path.clone(), e)).collect(),
+        })
+    }
+
+    /// The position worth remembering for a track of `duration` seconds, `None` when
+    /// it's barely started or finished.
+    pub fn position_to_keep(position: f64, duration: f64) -> Option<f64> {
+        (position >= MIN_POSITION && duration - position > FINISHED_REMAINING).then_some(position)
+    }
+
+    pub fn get(&self, key: &ResumeKey) -> Option<f64> {
+        self.entries
+            .get(&key.path)
+            .filter(|entry| entry.hash == key.hash)
+            .map(|entry| entry.position)
+    }
+
+    pub fn set(&mut self, key: &ResumeKey, position: f64) -> Result<(), LibraryError> {
+        // Saved on every pause and autosave, skip the write while paused.
+        if self
+            .get(key)
+            .is_some_and(|old| (old - position).abs() < 1.0)
+        {
+            return Ok(());
+        }
+
+        let updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        self.entries.insert(
+            key.path.clone(),
+            ResumeEntry {
+                path: key.path.clone(),
+                hash: key.hash,
+                position,
+                updated_at,
+            },
+        );
+
+        self.save()
+    }
+
+    pub fn remove(&mut self, key: &ResumeKey) -> Result<(), LibraryError> {
+        match self.entries.remove(&key.path) {
+            Some(_) => self.save(),
+            None => Ok(()),
+        }
+    }
+
+    fn save(&mut self) -> Result<(), LibraryError> {
+        let mut entries: Vec<_> = self.entries.values().cloned().collect();
+        entries.sort_by_key(|entry| Reverse(entry.updated_at));
+
+        if entries.len() > MAX_ENTRIES {
+            for entry in entries.drain(MAX_ENTRIES..) {
+                self.entries.remove(&entry.path);
+            }
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&entries)?)?;
+        fs::rename(&tmp, &self.path)?;
+
+        Ok(())
+    }
+}
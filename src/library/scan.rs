@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::{Library, LibraryError, Track};
+
+#[cfg(test)]
+mod tests;
+
+const AUDIO_EXTENSIONS: &[&str] = &[
+    "aac", "aif", "aiff", "caf", "flac", "m4a", "mp2", "mp3", "mp4", "oga", "ogg", "opus", "wav",
+];
+
+pub fn is_audio_file<P: AsRef<Path> + ?Sized>(path: &P) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| {
+            AUDIO_EXTENSIONS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(ext))
+        })
+}
+
+/// Recursively reads every audio file under `dir`. Files that fail to probe are skipped.
+pub fn collect_tracks<P: AsRef<Path> + ?Sized>(
+    dir: &P,
+    tracks: &mut Vec<Track>,
+) -> Result<(), LibraryError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            if let Err(err) = collect_tracks(&path, tracks) {
+                log::warn!("Skipping {}: {err}", path.display());
+            }
+            continue;
+        }
+
+        if !is_audio_file(&path) {
+            continue;
+        }
+
+        match Track::read(&path) {
+            Ok(track) => tracks.push(track),
+            Err(err) => log::warn!("Skipping {}: {err}", path.display()),
+        }
+    }
+
+    Ok(())
+}
+
+impl Library {
+    /// Rebuilds the index from scratch and returns the number of tracks found.
+    pub fn scan(&self) -> Result<usize, LibraryError> {
+        let mut tracks = Vec::new();
+
+        for folder in self.folders() {
+            // Such as the default music folder on a system that has none.
+            if !folder.exists() {
+                log::warn!("Skipping {}, the folder does not exist", folder.display());
+                continue;
+            }
+
+            collect_tracks(folder, &mut tracks)?;
+        }
+
+        let tracks: HashMap<_, _> = tracks.into_iter().map(|t| (t.id, t)).collect();
+        let count = tracks.len();
+
+        self.write().replace_tracks(tracks);
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,11 @@
+use super::*;
+use crate::test_util::TempDir;
+
+#[test]
+fn missing_folders_are_skipped() {
+    let dir = TempDir::new("scan-missing");
+    dir.touch("notes.txt");
+    let library = Library::new(vec![dir.join("gone"), dir.path().to_path_buf()]);
+
+    assert_eq!(library.scan().unwrap(), 0);
+}
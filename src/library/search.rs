@@ -0,0 +1,273 @@
+use std::str::FromStr;
+
+use super::{LibraryIndex, SortColumn, Track, sort_tracks};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SearchError {
+    #[error("Expected a number after `{0}:`, got `{1}`.")]
+    InvalidNumber(String, String),
+
+    #[error("Unterminated quote in search query.")]
+    UnterminatedQuote,
+}
+
+/// A parsed search such as `artist:radiohead year:>2000 genre:rock -live`.
+///
+/// Bare words match title, artist and album; `field:value` narrows the match to one
+/// field and a leading `-` negates a term. All terms must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SearchQuery {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Term {
+    field: Option<Field>,
+    matcher: Matcher,
+    negated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Field {
+    Title,
+    Artist,
+    AlbumArtist,
+    Album,
+    Genre,
+    Year,
+    TrackNumber,
+    Path,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Matcher {
+    /// Lowercased needle for case-insensitive substring matching.
+    Contains(String),
+    Number(Comparison, u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Lowercased copies of the fields searched as text, kept in the index so a query doesn't
+/// lowercase the whole library on every keystroke.
+#[derive(Debug, Clone, Default)]
+pub(super) struct SearchKeys {
+    title: String,
+    artist: Option<String>,
+    album_artist: Option<String>,
+    album: Option<String>,
+    genre: Option<String>,
+    path: String,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "title" => Some(Self::Title),
+            "artist" => Some(Self::Artist),
+            "albumartist" => Some(Self::AlbumArtist),
+            "album" => Some(Self::Album),
+            "genre" => Some(Self::Genre),
+            "year" => Some(Self::Year),
+            "track" => Some(Self::TrackNumber),
+            "path" => Some(Self::Path),
+            _ => None,
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Self::Year | Self::TrackNumber)
+    }
+}
+
+impl Comparison {
+    fn split(value: &str) -> (Self, &str) {
+        for (prefix, cmp) in [
+            (">=", Self::Ge),
+            ("<=", Self::Le),
+            (">", Self::Gt),
+            ("<", Self::Lt),
+            ("=", Self::Eq),
+        ] {
+            if let Some(rest) = value.strip_prefix(prefix) {
+                return (cmp, rest);
+            }
+        }
+
+        (Self::Eq, value)
+    }
+
+    fn test(&self, lhs: u32, rhs: u32) -> bool {
+        match self {
+            Self::Eq => lhs == rhs,
+            Self::Lt => lhs < rhs,
+            Self::Le => lhs <= rhs,
+            Self::Gt => lhs > rhs,
+            Self::Ge => lhs >= rhs,
+        }
+    }
+}
+
+impl FromStr for SearchQuery {
+    type Err = SearchError;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let mut terms = Vec::new();
+
+        for token in tokenize(query)? {
+            let (negated, token) = match token.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest),
+                _ => (false, token.as_str()),
+            };
+
+            let (name, value) = token.split_once(':').unwrap_or_default();
+            let (field, value) = match Field::parse(name) {
+                Some(field) => (Some(field), value),
+                None => (None, token),
+            };
+
+            if value.is_empty() {
+                continue;
+            }
+
+            let matcher = match field {
+                Some(field) if field.is_numeric() => {
+                    let (cmp, number) = Comparison::split(value);
+                    let number = number.parse().map_err(|_| {
+                        SearchError::InvalidNumber(name.to_string(), value.to_string())
+                    })?;
+
+                    Matcher::Number(cmp, number)
+                }
+                _ => Matcher::Contains(value.to_lowercase()),
+            };
+
+            terms.push(Term {
+                field,
+                matcher,
+                negated,
+            });
+        }
+
+        Ok(Self { terms })
+    }
+}
+
+/// Splits on whitespace, keeping `"quoted phrases"` together and dropping the quotes.
+fn tokenize(query: &str) -> Result<Vec<String>, SearchError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(SearchError::UnterminatedQuote);
+    }
+
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+impl SearchQuery {
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    fn matches(&self, track: &Track, keys: &SearchKeys) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.matches(track, keys) != term.negated)
+    }
+}
+
+impl Term {
+    fn matches(&self, track: &Track, keys: &SearchKeys) -> bool {
+        match (&self.matcher, self.field) {
+            (Matcher::Contains(needle), None) => [
+                Some(keys.title.as_str()),
+                keys.artist.as_deref(),
+                keys.album_artist.as_deref(),
+                keys.album.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .any(|value| value.contains(needle.as_str())),
+            (Matcher::Contains(needle), Some(field)) => {
+                let value = match field {
+                    Field::Title => Some(keys.title.as_str()),
+                    Field::Artist => keys.artist.as_deref(),
+                    Field::AlbumArtist => keys.album_artist.as_deref(),
+                    Field::Album => keys.album.as_deref(),
+                    Field::Genre => keys.genre.as_deref(),
+                    Field::Path => Some(keys.path.as_str()),
+                    Field::Year | Field::TrackNumber => None,
+                };
+
+                value.is_some_and(|value| value.contains(needle.as_str()))
+            }
+            (Matcher::Number(cmp, rhs), field) => {
+                let lhs = match field {
+                    Some(Field::Year) => track.year,
+                    Some(Field::TrackNumber) => track.track_number,
+                    _ => None,
+                };
+
+                lhs.is_some_and(|lhs| cmp.test(lhs, *rhs))
+            }
+        }
+    }
+}
+
+impl SearchKeys {
+    pub(super) fn new(track: &Track) -> Self {
+        let lower = |value: &Option<String>| value.as_deref().map(str::to_lowercase);
+
+        Self {
+            title: track.display_title().to_lowercase(),
+            artist: lower(&track.artist),
+            album_artist: lower(&track.album_artist),
+            album: lower(&track.album),
+            genre: lower(&track.genre),
+            path: track.path.to_string_lossy().to_lowercase(),
+        }
+    }
+}
+
+impl LibraryIndex {
+    pub fn search(&self, query: &SearchQuery, sort: SortColumn, ascending: bool) -> Vec<&Track> {
+        let tracks = self
+            .tracks()
+            .filter(|t| {
+                self.search_keys
+                    .get(&t.id)
+                    .is_some_and(|keys| query.matches(t, keys))
+            })
+            .collect();
+
+        sort_tracks(tracks, sort, ascending)
+    }
+}
@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+
+use super::*;
+
+fn parse(query: &str) -> Vec<Term> {
+    query.parse::<SearchQuery>().unwrap().terms
+}
+
+fn contains(field: Option<Field>, needle: &str) -> Term {
+    Term {
+        field,
+        matcher: Matcher::Contains(needle.to_string()),
+        negated: false,
+    }
+}
+
+fn year(cmp: Comparison, year: u32) -> Term {
+    Term {
+        field: Some(Field::Year),
+        matcher: Matcher::Number(cmp, year),
+        negated: false,
+    }
+}
+
+fn track() -> Track {
+    Track {
+        path: PathBuf::from("/Music/Pink Floyd/Time.flac"),
+        title: Some("Time".to_string()),
+        artist: Some("Pink Floyd".to_string()),
+        album: Some("The Dark Side of the Moon".to_string()),
+        genre: Some("Progressive Rock".to_string()),
+        year: Some(1973),
+        track_number: Some(4),
+        ..Track::default()
+    }
+}
+
+fn matches(query: &str) -> bool {
+    let track = track();
+    let query: SearchQuery = query.parse().unwrap();
+
+    query.matches(&track, &SearchKeys::new(&track))
+}
+
+#[test]
+fn bare_words_are_lowercased() {
+    assert_eq!(
+        parse("Dark  MOON"),
+        [contains(None, "dark"), contains(None, "moon")]
+    );
+}
+
+#[test]
+fn fields_narrow_the_match() {
+    assert_eq!(
+        parse("Artist:Floyd genre:rock"),
+        [
+            contains(Some(Field::Artist), "floyd"),
+            contains(Some(Field::Genre), "rock"),
+        ]
+    );
+}
+
+#[test]
+fn quotes_keep_phrases_together() {
+    assert_eq!(
+        parse(r#"artist:"Pink Floyd" "dark side""#),
+        [
+            contains(Some(Field::Artist), "pink floyd"),
+            contains(None, "dark side"),
+        ]
+    );
+}
+
+#[test]
+fn unterminated_quotes_are_an_error() {
+    assert_eq!(
+        r#"artist:"pink"#.parse::<SearchQuery>(),
+        Err(SearchError::UnterminatedQuote)
+    );
+}
+
+#[test]
+fn unknown_fields_are_searched_as_words() {
+    assert_eq!(parse("mood:calm"), [contains(None, "mood:calm")]);
+}
+
+#[test]
+fn empty_values_are_ignored() {
+    assert!(parse("artist: year:").is_empty());
+}
+
+#[test]
+fn a_leading_dash_negates() {
+    let terms = parse("-live -");
+
+    assert_eq!(
+        terms,
+        [
+            Term {
+                negated: true,
+                ..contains(None, "live")
+            },
+            contains(None, "-"),
+        ]
+    );
+}
+
+#[test]
+fn years_take_comparisons() {
+    assert_eq!(
+        parse("year:1973 year:>1970 year:>=1970 year:<1980 year:<=1980 year:=1973"),
+        [
+            year(Comparison::Eq, 1973),
+            year(Comparison::Gt, 1970),
+            year(Comparison::Ge, 1970),
+            year(Comparison::Lt, 1980),
+            year(Comparison::Le, 1980),
+            year(Comparison::Eq, 1973),
+        ]
+    );
+}
+
+#[test]
+fn years_must_be_numbers() {
+    assert_eq!(
+        "year:>seventies".parse::<SearchQuery>(),
+        Err(SearchError::InvalidNumber(
+            "year".to_string(),
+            ">seventies".to_string()
+        ))
+    );
+}
+
+#[test]
+fn year_ranges_match_inside() {
+    assert!(matches("year:>=1970 year:<1980"));
+    assert!(!matches("year:>=1980 year:<1990"));
+    assert!(matches("year:1973 track:4"));
+}
+
+#[test]
+fn matching_ignores_case() {
+    assert!(matches("FLOYD moon"));
+    assert!(matches(r#"album:"dark side""#));
+    assert!(matches("path:/music/pink"));
+    assert!(!matches("artist:moon"));
+    assert!(!matches("-floyd"));
+}
+
+#[test]
+fn missing_fields_do_not_match() {
+    assert!(!matches("albumartist:floyd"));
+    assert!(matches("-albumartist:floyd"));
+}
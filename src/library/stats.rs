@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+use super::{LibraryIndex, Track, TrackId};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PlayStats {
+    pub play_count: u32,
+    /// Unix timestamp in seconds.
+    pub last_played: Option<u64>,
+}
+
+impl LibraryIndex {
+    pub fn stats(&self, id: TrackId) -> PlayStats {
+        self.stats.get(&id).copied().unwrap_or_default()
+    }
+
+    /// Tracks that were played at least once, most played first.
+    pub fn most_played(&self, limit: usize) -> Vec<(&Track, PlayStats)> {
+        let mut played: Vec<_> = self
+            .stats
+            .iter()
+            .filter(|(_, stats)| stats.play_count > 0)
+            .filter_map(|(id, stats)| Some((self.get(*id)?, *stats)))
+            .collect();
+
+        played.sort_by(|(a, sa), (b, sb)| {
+            sb.play_count
+                .cmp(&sa.play_count)
+                .then(sb.last_played.cmp(&sa.last_played))
+                .then(a.path.cmp(&b.path))
+        });
+        played.truncate(limit);
+
+        played
+    }
+}
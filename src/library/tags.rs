@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+
+use lofty::config::WriteOptions;
+use lofty::prelude::*;
+use lofty::tag::Tag;
+
+use super::event::LibraryEvent;
+use super::{Library, LibraryError, Track};
+
+/// Changes to a file's tags. `None` leaves a field untouched, an empty string removes it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagEdit {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album_artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<String>,
+    pub track_number: Option<String>,
+}
+
+impl TagEdit {
+    pub fn is_empty(&self) -> bool {
+        self.fields().next().is_none()
+    }
+
+    fn fields(&self) -> impl Iterator<Item = (ItemKey, &str)> {
+        [
+            (ItemKey::TrackTitle, &self.title),
+            (ItemKey::TrackArtist, &self.artist),
+            (ItemKey::AlbumArtist, &self.album_artist),
+            (ItemKey::AlbumTitle, &self.album),
+            (ItemKey::Genre, &self.genre),
+            (ItemKey::RecordingDate, &self.year),
+            (ItemKey::TrackNumber, &self.track_number),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value.as_deref()?.trim())))
+    }
+
+    /// Writes the changes to the file's native tag: ID3v2 for MP3, Vorbis comments for FLAC and
+    /// Ogg, atoms for MP4.
+    pub fn write(&self, path: &Path) -> Result<(), LibraryError> {
+        let mut file = lofty::read_from_path(path)?;
+
+        if file.primary_tag().is_none() {
+            file.insert_tag(Tag::new(file.primary_tag_type()));
+        }
+
+        let Some(tag) = file.primary_tag_mut() else {
+            return Ok(());
+        };
+
+        for (key, value) in self.fields() {
+            match value.is_empty() {
+                true => tag.remove_key(key),
+                false => {
+                    tag.insert_text(key, value.to_string());
+                }
+            }
+        }
+
+        tag.save_to_path(path, WriteOptions::default())?;
+        Ok(())
+    }
+}
+
+impl Library {
+    /// Applies `edit` to every file in `paths` and re-reads them into the index.
+    pub fn edit_tags(&self, paths: &[PathBuf], edit: &TagEdit) -> Vec<LibraryEvent> {
+        paths
+            .iter()
+            .map(|path| {
+                edit.write(path)?;
+                let track = Track::read(path)?;
+
+                self.write().insert(track.clone());
+                Ok(LibraryEvent::Updated(track))
+            })
+            .map(|res: Result<_, LibraryError>| res.unwrap_or_else(LibraryEvent::from))
+            .collect()
+    }
+}
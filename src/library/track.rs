@@ -0,0 +1,216 @@
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::{MediaSourceStream, MediaSourceStreamOptions};
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, Tag};
+use symphonia::core::probe::{Hint, ProbeResult};
+use symphonia::default::{get_codecs, get_probe};
+
+use super::LibraryError;
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct TrackId(pub u64);
+
+impl TrackId {
+    /// FNV-1a over the raw path bytes, so ids stay stable between runs.
+    pub fn from_path<P: AsRef<Path> + ?Sized>(path: &P) -> Self {
+        let mut hash = Fnv1a::new();
+        hash.write(path.as_ref().as_os_str().as_encoded_bytes());
+
+        Self(hash.finish())
+    }
+}
+
+/// 64-bit FNV-1a, which unlike the std hasher gives the same result on every run.
+pub(super) struct Fnv1a(u64);
+
+impl Fnv1a {
+    pub fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Track {
+    pub id: TrackId,
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album_artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub track_number: Option<u32>,
+    /// Zero to five stars.
+    pub rating: Option<u8>,
+    pub duration: f64,
+    #[serde(default)]
+    pub format: AudioFormat,
+}
+
+/// Technical details of the audio stream.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AudioFormat {
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub bits_per_sample: Option<u32>,
+    pub channels: Option<usize>,
+    /// Average over the whole file, in kbit/s.
+    pub bitrate: Option<u32>,
+}
+
+impl Track {
+    pub fn read<P: AsRef<Path> + ?Sized>(path: &P) -> Result<Self, LibraryError> {
+        let path = path.as_ref();
+        let mut probe = probe(path)?;
+
+        let mut track = Self {
+            id: TrackId::from_path(path),
+            path: path.to_path_buf(),
+            ..Default::default()
+        };
+
+        if let Some(params) = probe.format.default_track().map(|t| &t.codec_params) {
+            if let (Some(n_frames), Some(sample_rate)) = (params.n_frames, params.sample_rate) {
+                track.duration = n_frames as f64 / sample_rate as f64;
+            }
+
+            let size = fs::metadata(path).map(|m| m.len()).unwrap_or_default();
+
+            track.format = AudioFormat {
+                codec: get_codecs()
+                    .get_codec(params.codec)
+                    .map(|codec| codec.short_name.to_uppercase()),
+                sample_rate: params.sample_rate,
+                bits_per_sample: params.bits_per_sample,
+                channels: params.channels.map(|c| c.count()),
+                bitrate: (track.duration > 0.0)
+                    .then(|| (size as f64 * 8.0 / track.duration / 1000.0).round() as u32),
+            };
+        }
+
+        // Tags found outside the container (e.g. ID3) come first, container tags win.
+        if let Some(metadata) = probe.metadata.get()
+            && let Some(revision) = metadata.current()
+        {
+            track.apply_tags(revision.tags());
+        }
+
+        if let Some(revision) = probe.format.metadata().current() {
+            track.apply_tags(revision.tags());
+        }
+
+        Ok(track)
+    }
+
+    fn apply_tags(&mut self, tags: &[Tag]) {
+        for tag in tags {
+            let value = tag.value.to_string();
+            let value = value.trim();
+
+            if value.is_empty() {
+                continue;
+            }
+
+            match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => self.title = Some(value.to_string()),
+                Some(StandardTagKey::Artist) => self.artist = Some(value.to_string()),
+                Some(StandardTagKey::AlbumArtist) => self.album_artist = Some(value.to_string()),
+                Some(StandardTagKey::Album) => self.album = Some(value.to_string()),
+                Some(StandardTagKey::Genre) => self.genre = Some(value.to_string()),
+                Some(StandardTagKey::Date) => self.year = parse_leading_number(value),
+                Some(StandardTagKey::TrackNumber) => {
+                    self.track_number = parse_leading_number(value)
+                }
+                Some(StandardTagKey::Rating) => self.rating = parse_rating(value),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn display_title(&self) -> Cow<'_, str> {
+        match &self.title {
+            Some(title) => Cow::Borrowed(title),
+            None => self
+                .path
+                .file_stem()
+                .map(|s| s.to_string_lossy())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn display_artist(&self) -> &str {
+        self.artist
+            .as_deref()
+            .or(self.album_artist.as_deref())
+            .unwrap_or("Unknown artist")
+    }
+
+    /// Albums are grouped by album artist so compilations stay together.
+    pub fn display_album_artist(&self) -> &str {
+        self.album_artist
+            .as_deref()
+            .or(self.artist.as_deref())
+            .unwrap_or("Unknown artist")
+    }
+
+    pub fn display_album(&self) -> &str {
+        self.album.as_deref().unwrap_or("Unknown album")
+    }
+}
+
+/// Parses values like `2001-05-14` or `3/12`.
+fn parse_leading_number(value: &str) -> Option<u32> {
+    let end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+
+    value[..end].parse().ok()
+}
+
+/// Ratings are stored as 1-5, 0-100 or 0-255 (POPM) depending on the tagger.
+fn parse_rating(value: &str) -> Option<u8> {
+    let rating = parse_leading_number(value)?;
+
+    let stars = match rating {
+        0..=5 => rating,
+        6..=100 => (rating + 10) / 20,
+        _ => (rating.min(255) * 5 + 127) / 255,
+    };
+
+    Some(stars as u8)
+}
+
+/// Opens `path` far enough to read its tracks and metadata.
+pub(super) fn probe(path: &Path) -> Result<ProbeResult, LibraryError> {
+    let file = File::open(path)?;
+    let media_source = MediaSourceStream::new(Box::new(file), MediaSourceStreamOptions::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    Ok(get_probe().format(
+        &hint,
+        media_source,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?)
+}
@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{DebounceEventResult, Debouncer, RecommendedCache, new_debouncer};
+
+use super::event::LibraryEvent;
+use super::{Library, LibraryError, Track, TrackId, collect_tracks, is_audio_file};
+
+#[cfg(test)]
+mod tests;
+
+/// Taggers tend to rewrite a file in several steps, wait for them to settle.
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(750);
+
+/// Keeps the library in sync with the filesystem for as long as it is alive.
+pub struct LibraryWatcher {
+    _debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+}
+
+impl LibraryWatcher {
+    pub fn spawn<F>(library: Library, mut on_event: F) -> Result<Self, LibraryError>
+    where
+        F: FnMut(LibraryEvent) + Send + 'static,
+    {
+        let folders = library.folders().to_vec();
+
+        let mut debouncer = new_debouncer(
+            DEBOUNCE_TIMEOUT,
+            None,
+            move |result: DebounceEventResult| match result {
+                Ok(events) => {
+                    for event in events {
+                        for event in library.apply_fs_event(&event.kind, &event.paths) {
+                            on_event(event);
+                        }
+                    }
+                }
+                Err(errors) => {
+                    for err in errors {
+                        on_event(LibraryError::from(err).into());
+                    }
+                }
+            },
+        )?;
+
+        // Missing folders were skipped by the scan as well.
+        for folder in folders.iter().filter(|folder| folder.exists()) {
+            debouncer.watch(folder, RecursiveMode::Recursive)?;
+        }
+
+        Ok(Self {
+            _debouncer: debouncer,
+        })
+    }
+}
+
+impl Library {
+    fn apply_fs_event(&self, kind: &EventKind, paths: &[PathBuf]) -> Vec<LibraryEvent> {
+        match kind {
+            EventKind::Create(_) => paths.iter().flat_map(|p| self.add_path(p)).collect(),
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => match paths {
+                [from, to] => self.move_path(from, to),
+                _ => Vec::new(),
+            },
+            EventKind::Modify(ModifyKind::Name(_)) => paths
+                .iter()
+                .flat_map(|p| match p.exists() {
+                    true => self.add_path(p),
+                    false => self.remove_path(p),
+                })
+                .collect(),
+            EventKind::Modify(_) => paths
+                .iter()
+                .filter(|p| p.is_file())
+                .flat_map(|p| self.add_path(p))
+                .collect(),
+            EventKind::Remove(_) => paths.iter().flat_map(|p| self.remove_path(p)).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn add_path(&self, path: &Path) -> Vec<LibraryEvent> {
+        let mut tracks = Vec::new();
+
+        if path.is_dir() {
+            if let Err(err) = collect_tracks(path, &mut tracks) {
+                return vec![err.into()];
+            }
+        } else if is_audio_file(path) {
+            match Track::read(path) {
+                Ok(track) => tracks.push(track),
+                Err(err) => return vec![err.into()],
+            }
+        }
+
+        let mut index = self.write();
+
+        tracks
+            .into_iter()
+            .map(|track| match index.insert(track.clone()) {
+                Some(_) => LibraryEvent::Updated(track),
+                None => LibraryEvent::Added(track),
+            })
+            .collect()
+    }
+
+    fn remove_path(&self, path: &Path) -> Vec<LibraryEvent> {
+        let mut index = self.write();
+
+        index
+            .ids_under(path)
+            .into_iter()
+            .filter_map(|id| index.remove(id))
+            .map(|track| LibraryEvent::Removed(track.id))
+            .collect()
+    }
+
+    fn move_path(&self, from: &Path, to: &Path) -> Vec<LibraryEvent> {
+        let mut index = self.write();
+        let ids = index.ids_under(from);
+
+        if ids.is_empty() {
+            drop(index);
+            return self.add_path(to);
+        }
+
+        let mut events = Vec::with_capacity(ids.len());
+        let mut plays_moved = false;
+
+        for id in ids {
+            let Some(mut track) = index.remove(id) else {
+                continue;
+            };
+
+            let relative = track.path.strip_prefix(from).unwrap_or(Path::new(""));
+            let new_path = match relative.as_os_str().is_empty() {
+                true => to.to_path_buf(),
+                false => to.join(relative),
+            };
+
+            if !is_audio_file(&new_path) {
+                events.push(LibraryEvent::Removed(id));
+                continue;
+            }
+
+            track.id = TrackId::from_path(&new_path);
+            track.path = new_path;
+            index.insert(track.clone());
+            plays_moved |= index.move_plays(id, &track);
+
+            events.push(LibraryEvent::Moved { from: id, track });
+        }
+
+        // Otherwise the play counts would go back to the old paths on the next start.
+        if plays_moved && let Err(err) = self.save_history(&index) {
+            events.push(err.into());
+        }
+
+        events
+    }
+}
@@ -0,0 +1,48 @@
+use super::*;
+use crate::library::{PlayOutcome, PlayRecord};
+use crate::test_util::TempDir;
+
+fn completed(track: &Track) -> PlayRecord {
+    PlayRecord {
+        track: track.id,
+        path: track.path.clone(),
+        artist: None,
+        title: None,
+        started_at: 1_700_000_000,
+        listened: 200.0,
+        outcome: PlayOutcome::Completed,
+    }
+}
+
+#[test]
+fn moved_tracks_keep_their_plays() {
+    let dir = TempDir::new("watcher-move");
+    let history = dir.join("history.jsonl");
+    let library = Library::new(vec![dir.path().to_path_buf()]).with_history(Some(history.clone()));
+
+    let from = dir.join("old.flac");
+    let to = dir.join("new.flac");
+    let track = Track {
+        id: TrackId::from_path(&from),
+        path: from.clone(),
+        ..Track::default()
+    };
+    library.write().insert(track.clone());
+    library.record_play(completed(&track)).unwrap();
+
+    let events = library.move_path(&from, &to);
+    assert!(
+        matches!(&events[..], [LibraryEvent::Moved { from, track: moved }]
+            if *from == track.id && moved.path == to),
+        "{events:?}"
+    );
+
+    let moved = TrackId::from_path(&to);
+    assert_eq!(library.read().stats(moved).play_count, 1);
+    assert_eq!(library.read().stats(track.id).play_count, 0);
+
+    // The history file follows, so the count survives a restart.
+    let restarted = Library::new(Vec::new()).with_history(Some(history));
+    restarted.load_history().unwrap();
+    assert_eq!(restarted.read().stats(moved).play_count, 1);
+}
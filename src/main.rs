@@ -1,14 +1,55 @@
 mod cli;
+mod config;
 mod gui;
+#[cfg(unix)]
+mod ipc;
+mod library;
+#[cfg(target_os = "linux")]
+mod mpris;
 mod player;
+mod playlist;
+mod remote;
+mod scrobble;
+mod session;
+#[cfg(test)]
+mod test_util;
 
 use cli::CliOptions;
+use config::{AudioOverrides, BUFFER_SIZES};
 
 pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: CliOptions = argh::from_env();
 
+    if let Some(command) = args.command {
+        return command.run();
+    }
+
+    if args.list_devices {
+        cli::list_devices();
+        return Ok(());
+    }
+
     if !args.no_gui {
-        return Ok(gui::run()?);
+        #[cfg(unix)]
+        match ipc::hand_over(args.input.as_deref()) {
+            Ok(true) => return Ok(()),
+            Ok(false) => {}
+            // Better a second instance than none at all.
+            Err(err) => eprintln!("Could not reach a running instance, starting anyway: {err}"),
+        }
+
+        if let Some(frames) = args.buffer_size
+            && !BUFFER_SIZES.contains(&frames)
+        {
+            return Err("--buffer-size must be between 16 and 16384 frames".into());
+        }
+
+        let overrides = AudioOverrides {
+            device: args.device,
+            buffer_size: args.buffer_size,
+        };
+
+        return Ok(gui::run(args.input, overrides)?);
     }
 
     if args.input.is_none() {
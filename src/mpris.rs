@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use zbus::connection::Builder;
+use zbus::fdo::Properties;
+use zbus::names::InterfaceName;
+use zbus::object_server::SignalEmitter;
+use zbus::{Connection, zvariant::Value};
+
+mod interface;
+mod state;
+
+#[cfg(test)]
+mod tests;
+
+pub use state::*;
+
+use interface::{PlayerInterface, RootInterface};
+
+use crate::player::LoopMode;
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.cozy_music";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Requests from other programs, for the app to carry out.
+#[derive(Debug, Clone)]
+pub enum MprisCommand {
+    Quit,
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Next,
+    Previous,
+    /// Seconds into the current track.
+    SeekSeconds(f64),
+    Volume(f32),
+    Rate(f64),
+    LoopMode(LoopMode),
+    Shuffle(bool),
+    Open(PathBuf),
+}
+
+type CommandHandler = Arc<dyn Fn(MprisCommand) + Send + Sync>;
+
+/// The player as seen on the session bus through `org.mpris.MediaPlayer2`.
+#[derive(Debug, Clone)]
+pub struct Mpris {
+    connection: Connection,
+    state: Arc<Mutex<MprisState>>,
+}
+
+impl Mpris {
+    pub async fn start<F>(on_command: F) -> zbus::Result<Self>
+    where
+        F: Fn(MprisCommand) + Send + Sync + 'static,
+    {
+        Self::start_on(Builder::session, on_command).await
+    }
+
+    /// Like `start`, on whichever bus `bus` connects to.
+    async fn start_on<F>(
+        bus: impl Fn() -> zbus::Result<Builder<'static>>,
+        on_command: F,
+    ) -> zbus::Result<Self>
+    where
+        F: Fn(MprisCommand) + Send + Sync + 'static,
+    {
+        let on_command: CommandHandler = Arc::new(on_command);
+        let state = Arc::new(Mutex::new(MprisState::default()));
+
+        // Other instances get their own name, as the spec asks.
+        let connection = match connect(bus()?, BUS_NAME.to_string(), &on_command, &state).await {
+            Err(zbus::Error::NameTaken) => {
+                let name = format!("{BUS_NAME}.instance{}", std::process::id());
+                connect(bus()?, name, &on_command, &state).await?
+            }
+            res => res?,
+        };
+
+        Ok(Self { connection, state })
+    }
+
+    /// Stores the latest state. Returns a future announcing the properties that changed, if any.
+    pub fn update(
+        &self,
+        state: MprisState,
+    ) -> Option<impl Future<Output = zbus::Result<()>> + use<>> {
+        let changed = {
+            let mut current = self.lock_state();
+            let changed = state.changed_properties(&current);
+
+            *current = state;
+            changed
+        };
+
+        if changed.is_empty() {
+            return None;
+        }
+
+        let connection = self.connection.clone();
+
+        Some(async move {
+            let emitter = SignalEmitter::new(&connection, OBJECT_PATH)?;
+            let changed: HashMap<&str, Value<'_>> = changed.into_iter().collect();
+
+            Properties::properties_changed(
+                &emitter,
+                InterfaceName::from_static_str_unchecked(PLAYER_INTERFACE),
+                changed,
+                (&[] as &[&str]).into(),
+            )
+            .await
+        })
+    }
+
+    /// Tells clients the position jumped, so they don't have to poll it.
+    pub fn seeked(&self) -> impl Future<Output = zbus::Result<()>> + use<> {
+        let connection = self.connection.clone();
+        let position = self.lock_state().position;
+
+        async move {
+            let emitter = SignalEmitter::new(&connection, OBJECT_PATH)?;
+            PlayerInterface::seeked(&emitter, position).await
+        }
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, MprisState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+async fn connect(
+    bus: Builder<'_>,
+    name: String,
+    on_command: &CommandHandler,
+    state: &Arc<Mutex<MprisState>>,
+) -> zbus::Result<Connection> {
+    bus.name(name)?
+        .serve_at(OBJECT_PATH, RootInterface::new(on_command.clone()))?
+        .serve_at(
+            OBJECT_PATH,
+            PlayerInterface::new(on_command.clone(), state.clone()),
+        )?
+        .build()
+        .await
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use zbus::fdo;
+use zbus::interface;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, Value};
+
+use super::{CommandHandler, MprisCommand, MprisState};
+
+#[cfg(test)]
+mod tests;
+
+const MIN_RATE: f64 = 0.5;
+const MAX_RATE: f64 = 2.0;
+
+pub(super) struct RootInterface {
+    on_command: CommandHandler,
+}
+
+pub(super) struct PlayerInterface {
+    on_command: CommandHandler,
+    state: Arc<Mutex<MprisState>>,
+}
+
+impl RootInterface {
+    pub fn new(on_command: CommandHandler) -> Self {
+        Self { on_command }
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl RootInterface {
+    fn raise(&self) {}
+
+    fn quit(&self) {
+        (self.on_command)(MprisCommand::Quit);
+    }
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> &str {
+        "Cozy music"
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> &str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<&str> {
+        vec!["file"]
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<&str> {
+        vec![
+            "audio/mpeg",
+            "audio/mp4",
+            "audio/aac",
+            "audio/flac",
+            "audio/wav",
+        ]
+    }
+}
+
+impl PlayerInterface {
+    pub fn new(on_command: CommandHandler, state: Arc<Mutex<MprisState>>) -> Self {
+        Self { on_command, state }
+    }
+
+    fn state(&self) -> MutexGuard<'_, MprisState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn send(&self, command: MprisCommand) {
+        (self.on_command)(command);
+    }
+
+    /// Moves to `position` microseconds into the current track, or skips it when past the end.
+    fn seek_to(&self, position: i64) {
+        let Some(length) = self.state().track.as_ref().map(|t| t.length) else {
+            return;
+        };
+
+        match position > length {
+            true => self.send(MprisCommand::Next),
+            false if length > 0 => {
+                self.send(MprisCommand::SeekSeconds(
+                    position.max(0) as f64 / 1_000_000.0,
+                ));
+            }
+            false => {}
+        }
+    }
+}
+
+/// Property setters store the new value right away, since zbus announces it as soon as they
+/// return. The app's next update corrects it if the change didn't take.
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl PlayerInterface {
+    fn next(&self) {
+        self.send(MprisCommand::Next);
+    }
+
+    fn previous(&self) {
+        self.send(MprisCommand::Previous);
+    }
+
+    fn pause(&self) {
+        self.send(MprisCommand::Pause);
+    }
+
+    fn play_pause(&self) {
+        self.send(MprisCommand::PlayPause);
+    }
+
+    fn stop(&self) {
+        self.send(MprisCommand::Stop);
+    }
+
+    fn play(&self) {
+        self.send(MprisCommand::Play);
+    }
+
+    /// `offset` is in microseconds and may be negative.
+    fn seek(&self, offset: i64) {
+        let position = self.state().position;
+        self.seek_to(position.saturating_add(offset));
+    }
+
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+        let current = self.state().track.as_ref().map(|t| t.object_path());
+
+        // Stale requests for a previous track are ignored, as the spec asks.
+        if current.as_ref() == Some(&track_id) && position >= 0 {
+            self.seek_to(position);
+        }
+    }
+
+    fn open_uri(&self, uri: &str) -> fdo::Result<()> {
+        let path = url::Url::parse(uri)
+            .ok()
+            .filter(|url| url.scheme() == "file")
+            .and_then(|url| url.to_file_path().ok())
+            .ok_or_else(|| {
+                fdo::Error::InvalidArgs(format!("Can only open file URIs, not {uri}"))
+            })?;
+
+        self.send(MprisCommand::Open(path));
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    pub async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        self.state().playback_status()
+    }
+
+    #[zbus(property)]
+    fn loop_status(&self) -> &str {
+        self.state().loop_status()
+    }
+
+    #[zbus(property)]
+    fn set_loop_status(&mut self, status: &str) -> fdo::Result<()> {
+        let mode = MprisState::parse_loop_status(status)
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("Unknown loop status {status}")))?;
+
+        self.state().loop_mode = mode;
+        self.send(MprisCommand::LoopMode(mode));
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        self.state().rate
+    }
+
+    /// A rate of zero pauses, as the spec asks.
+    #[zbus(property)]
+    fn set_rate(&mut self, rate: f64) {
+        if rate <= 0.0 {
+            self.send(MprisCommand::Pause);
+            return;
+        }
+
+        let rate = rate.clamp(MIN_RATE, MAX_RATE);
+
+        self.state().rate = rate;
+        self.send(MprisCommand::Rate(rate));
+    }
+
+    #[zbus(property)]
+    fn shuffle(&self) -> bool {
+        self.state().shuffle
+    }
+
+    #[zbus(property)]
+    fn set_shuffle(&mut self, shuffle: bool) {
+        self.state().shuffle = shuffle;
+        self.send(MprisCommand::Shuffle(shuffle));
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, Value<'static>> {
+        self.state().metadata()
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.state().volume
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, volume: f64) {
+        let volume = volume.clamp(0.0, 1.0);
+
+        self.state().volume = volume;
+        self.send(MprisCommand::Volume(volume as f32));
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        self.state().position
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        MIN_RATE
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        MAX_RATE
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.state().can_go_next
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.state().can_go_previous
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.state().track.is_some()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
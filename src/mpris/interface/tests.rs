@@ -0,0 +1,92 @@
+use std::sync::{Arc, Mutex};
+
+use super::*;
+use crate::mpris::tests::playing;
+use crate::player::LoopMode;
+
+fn collecting_interface(state: MprisState) -> (PlayerInterface, Arc<Mutex<Vec<MprisCommand>>>) {
+    let commands = Arc::new(Mutex::new(Vec::new()));
+    let sent = commands.clone();
+    let on_command: CommandHandler = Arc::new(move |command| sent.lock().unwrap().push(command));
+
+    let interface = PlayerInterface::new(on_command, Arc::new(Mutex::new(state)));
+    (interface, commands)
+}
+
+#[test]
+fn setting_loop_status_sends_the_mode() {
+    let (mut interface, commands) = collecting_interface(MprisState::default());
+
+    interface.set_loop_status("Track").unwrap();
+    assert!(interface.set_loop_status("Album").is_err());
+
+    let commands = commands.lock().unwrap();
+    assert!(matches!(
+        commands.as_slice(),
+        [MprisCommand::LoopMode(LoopMode::Track)]
+    ));
+    assert_eq!(interface.loop_status(), "Track");
+}
+
+#[test]
+fn setting_shuffle_sends_it() {
+    let (mut interface, commands) = collecting_interface(MprisState::default());
+
+    interface.set_shuffle(true);
+
+    assert!(matches!(
+        commands.lock().unwrap().as_slice(),
+        [MprisCommand::Shuffle(true)]
+    ));
+    assert!(interface.shuffle());
+}
+
+#[test]
+fn zero_rate_pauses() {
+    let (mut interface, commands) = collecting_interface(MprisState::default());
+
+    interface.set_rate(0.0);
+    interface.set_rate(4.0);
+
+    assert!(matches!(
+        commands.lock().unwrap().as_slice(),
+        [MprisCommand::Pause, MprisCommand::Rate(2.0)]
+    ));
+}
+
+#[test]
+fn seeking_past_the_end_skips_the_track() {
+    let (interface, commands) = collecting_interface(playing(10_000_000));
+
+    interface.seek(2_500_000);
+    interface.seek(20_000_000);
+
+    assert!(matches!(
+        commands.lock().unwrap().as_slice(),
+        [MprisCommand::SeekSeconds(2.5), MprisCommand::Next]
+    ));
+}
+
+#[test]
+fn setting_the_position_seeks_in_seconds() {
+    let state = playing(60_000_000);
+    let track_id = state.track.as_ref().unwrap().object_path();
+    let (interface, commands) = collecting_interface(state);
+
+    interface.set_position(track_id, 30_000_000);
+    interface.set_position(ObjectPath::from_static_str_unchecked("/stale"), 10_000_000);
+
+    assert!(matches!(
+        commands.lock().unwrap().as_slice(),
+        [MprisCommand::SeekSeconds(30.0)]
+    ));
+}
+
+#[test]
+fn seeking_without_a_track_does_nothing() {
+    let (interface, commands) = collecting_interface(MprisState::default());
+
+    interface.seek(250_000);
+
+    assert!(commands.lock().unwrap().is_empty());
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use zbus::zvariant::{ObjectPath, Value};
+
+use crate::library::{Track, TrackId};
+use crate::player::{LoopMode, PlaybackStatus};
+
+/// Everything clients can read about the player, refreshed by the app as it changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MprisState {
+    pub status: PlaybackStatus,
+    pub loop_mode: LoopMode,
+    pub shuffle: bool,
+    pub volume: f64,
+    pub rate: f64,
+    /// Microseconds. Clients poll it, so changing it alone isn't announced.
+    pub position: i64,
+    pub can_go_next: bool,
+    pub can_go_previous: bool,
+    pub track: Option<MprisTrack>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MprisTrack {
+    pub id: TrackId,
+    pub path: PathBuf,
+    pub title: String,
+    pub artist: Option<String>,
+    pub album_artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub track_number: Option<u32>,
+    /// Microseconds.
+    pub length: i64,
+    pub art: Option<PathBuf>,
+}
+
+impl Default for MprisState {
+    fn default() -> Self {
+        Self {
+            status: PlaybackStatus::Stopped,
+            loop_mode: LoopMode::None,
+            shuffle: false,
+            volume: 1.0,
+            rate: 1.0,
+            position: 0,
+            can_go_next: false,
+            can_go_previous: false,
+            track: None,
+        }
+    }
+}
+
+impl MprisState {
+    pub(super) const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+    pub(super) fn playback_status(&self) -> &'static str {
+        match self.status {
+            PlaybackStatus::Playing => "Playing",
+            PlaybackStatus::Paused => "Paused",
+            PlaybackStatus::Stopped => "Stopped",
+        }
+    }
+
+    pub(super) fn loop_status(&self) -> &'static str {
+        match self.loop_mode {
+            LoopMode::None => "None",
+            LoopMode::Track => "Track",
+            LoopMode::Playlist => "Playlist",
+        }
+    }
+
+    /// The mode a client asks for by setting `LoopStatus`.
+    pub(super) fn parse_loop_status(status: &str) -> Option<LoopMode> {
+        match status {
+            "None" => Some(LoopMode::None),
+            "Track" => Some(LoopMode::Track),
+            "Playlist" => Some(LoopMode::Playlist),
+            _ => None,
+        }
+    }
+
+    pub(super) fn metadata(&self) -> HashMap<String, Value<'static>> {
+        let mut metadata = HashMap::new();
+
+        let Some(track) = &self.track else {
+            metadata.insert(
+                "mpris:trackid".to_string(),
+                ObjectPath::from_static_str_unchecked(Self::NO_TRACK).into(),
+            );
+            return metadata;
+        };
+
+        let mut insert = |key: &str, value: Value<'static>| {
+            metadata.insert(key.to_string(), value);
+        };
+
+        insert("mpris:trackid", track.object_path().into());
+        insert("mpris:length", track.length.into());
+        insert("xesam:title", track.title.clone().into());
+
+        if let Some(url) = file_url(&track.path) {
+            insert("xesam:url", url.into());
+        }
+
+        if let Some(url) = track.art.as_deref().and_then(file_url) {
+            insert("mpris:artUrl", url.into());
+        }
+
+        if let Some(artist) = &track.artist {
+            insert("xesam:artist", vec![artist.clone()].into());
+        }
+
+        if let Some(album_artist) = &track.album_artist {
+            insert("xesam:albumArtist", vec![album_artist.clone()].into());
+        }
+
+        if let Some(album) = &track.album {
+            insert("xesam:album", album.clone().into());
+        }
+
+        if let Some(genre) = &track.genre {
+            insert("xesam:genre", vec![genre.clone()].into());
+        }
+
+        if let Some(number) = track.track_number {
+            insert("xesam:trackNumber", (number as i32).into());
+        }
+
+        metadata
+    }
+
+    /// Properties of the `Player` interface that differ from `old`, with their new values.
+    pub(super) fn changed_properties(
+        &self,
+        old: &MprisState,
+    ) -> Vec<(&'static str, Value<'static>)> {
+        let mut changed = Vec::new();
+
+        if self.status != old.status {
+            changed.push(("PlaybackStatus", self.playback_status().into()));
+        }
+
+        if self.loop_mode != old.loop_mode {
+            changed.push(("LoopStatus", self.loop_status().into()));
+        }
+
+        if self.shuffle != old.shuffle {
+            changed.push(("Shuffle", self.shuffle.into()));
+        }
+
+        if self.volume != old.volume {
+            changed.push(("Volume", self.volume.into()));
+        }
+
+        if self.rate != old.rate {
+            changed.push(("Rate", self.rate.into()));
+        }
+
+        if self.can_go_next != old.can_go_next {
+            changed.push(("CanGoNext", self.can_go_next.into()));
+        }
+
+        if self.can_go_previous != old.can_go_previous {
+            changed.push(("CanGoPrevious", self.can_go_previous.into()));
+        }
+
+        if self.track != old.track {
+            changed.push(("Metadata", self.metadata().into()));
+        }
+
+        if self.track.is_some() != old.track.is_some() {
+            changed.push(("CanSeek", self.track.is_some().into()));
+        }
+
+        changed
+    }
+}
+
+impl MprisTrack {
+    pub fn new(path: PathBuf, track: Option<&Track>, length: i64, art: Option<PathBuf>) -> Self {
+        let title = match track {
+            Some(track) => track.display_title().into_owned(),
+            None => path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+
+        Self {
+            id: TrackId::from_path(&path),
+            title,
+            artist: track.and_then(|t| t.artist.clone()),
+            album_artist: track.and_then(|t| t.album_artist.clone()),
+            album: track.and_then(|t| t.album.clone()),
+            genre: track.and_then(|t| t.genre.clone()),
+            track_number: track.and_then(|t| t.track_number),
+            length,
+            art,
+            path,
+        }
+    }
+
+    pub(super) fn object_path(&self) -> ObjectPath<'static> {
+        ObjectPath::try_from(format!("/org/cozy_music/track/{:016x}", self.id.0))
+            .unwrap_or_else(|_| ObjectPath::from_static_str_unchecked(MprisState::NO_TRACK))
+    }
+}
+
+fn file_url(path: &Path) -> Option<String> {
+    url::Url::from_file_path(path).ok().map(String::from)
+}
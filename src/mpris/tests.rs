@@ -0,0 +1,201 @@
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use iced::futures::StreamExt;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use zbus::fdo::PropertiesProxy;
+use zbus::zvariant::ObjectPath;
+
+use super::*;
+use crate::player::PlaybackStatus;
+use crate::test_util::TempDir;
+
+/// Playing a track `length` microseconds long.
+pub(super) fn playing(length: i64) -> MprisState {
+    MprisState {
+        track: Some(MprisTrack::new(
+            PathBuf::from("/music/song.flac"),
+            None,
+            length,
+            None,
+        )),
+        ..MprisState::default()
+    }
+}
+
+#[test]
+fn loop_status_round_trips() {
+    for mode in [LoopMode::None, LoopMode::Track, LoopMode::Playlist] {
+        let state = MprisState {
+            loop_mode: mode,
+            ..MprisState::default()
+        };
+
+        assert_eq!(
+            MprisState::parse_loop_status(state.loop_status()),
+            Some(mode)
+        );
+    }
+}
+
+#[test]
+fn unknown_loop_status_is_rejected() {
+    assert_eq!(MprisState::parse_loop_status("Album"), None);
+    assert_eq!(MprisState::parse_loop_status("none"), None);
+}
+
+#[test]
+fn loop_and_shuffle_changes_are_announced() {
+    let old = MprisState::default();
+    let new = MprisState {
+        loop_mode: LoopMode::Playlist,
+        shuffle: true,
+        ..MprisState::default()
+    };
+
+    let changed = new.changed_properties(&old);
+    let names: Vec<_> = changed.iter().map(|(name, _)| *name).collect();
+
+    assert_eq!(names, ["LoopStatus", "Shuffle"]);
+    assert_eq!(changed[0].1, "Playlist".into());
+    assert_eq!(changed[1].1, true.into());
+}
+
+#[test]
+fn unchanged_state_announces_nothing() {
+    let state = playing(1_000_000);
+    assert!(state.changed_properties(&state.clone()).is_empty());
+}
+
+/// A private session bus, shut down when the test ends.
+struct TestBus {
+    daemon: Child,
+    address: String,
+    _dir: TempDir,
+}
+
+impl TestBus {
+    /// `None` when dbus-daemon isn't installed.
+    fn start() -> Option<Self> {
+        let dir = TempDir::new("mpris-bus");
+        let socket = dir.join("bus");
+        let address = format!("unix:path={}", socket.display());
+
+        let daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", &format!("--address={address}")])
+            .stderr(Stdio::null())
+            .spawn();
+        let daemon = match daemon {
+            Ok(daemon) => daemon,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                eprintln!("Skipped, dbus-daemon isn't installed");
+                return None;
+            }
+            Err(err) => panic!("Could not start dbus-daemon: {err}"),
+        };
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !socket.exists() {
+            assert!(Instant::now() < deadline, "dbus-daemon did not start");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        Some(Self {
+            daemon,
+            address,
+            _dir: dir,
+        })
+    }
+
+    fn connect(&self) -> zbus::Result<Builder<'static>> {
+        Builder::address(self.address.as_str())
+    }
+}
+
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        self.daemon.kill().ok();
+        self.daemon.wait().ok();
+    }
+}
+
+#[tokio::test]
+async fn the_player_is_reachable_over_the_bus() {
+    let Some(bus) = TestBus::start() else {
+        return;
+    };
+    let wait = Duration::from_secs(10);
+
+    let (sender, mut commands) = mpsc::unbounded_channel();
+    let mpris = Mpris::start_on(
+        || bus.connect(),
+        move |command| {
+            sender.send(command).ok();
+        },
+    )
+    .await
+    .unwrap();
+
+    let client = bus.connect().unwrap().build().await.unwrap();
+    client
+        .call_method(
+            Some(BUS_NAME),
+            OBJECT_PATH,
+            Some(PLAYER_INTERFACE),
+            "SetPosition",
+            &(
+                ObjectPath::from_static_str_unchecked(MprisState::NO_TRACK),
+                0i64,
+            ),
+        )
+        .await
+        .unwrap();
+    client
+        .call_method(
+            Some(BUS_NAME),
+            OBJECT_PATH,
+            Some(PLAYER_INTERFACE),
+            "PlayPause",
+            &(),
+        )
+        .await
+        .unwrap();
+
+    // The stale `SetPosition` is dropped, only the second call arrives.
+    let command = timeout(wait, commands.recv()).await.unwrap();
+    assert!(
+        matches!(command, Some(MprisCommand::PlayPause)),
+        "{command:?}"
+    );
+
+    let properties = PropertiesProxy::builder(&client)
+        .destination(BUS_NAME)
+        .unwrap()
+        .path(OBJECT_PATH)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let mut changes = properties.receive_properties_changed().await.unwrap();
+
+    let state = MprisState {
+        status: PlaybackStatus::Playing,
+        ..playing(60_000_000)
+    };
+    mpris.update(state).unwrap().await.unwrap();
+
+    let signal = timeout(wait, changes.next()).await.unwrap().unwrap();
+    let args = signal.args().unwrap();
+    assert_eq!(args.interface_name().as_str(), PLAYER_INTERFACE);
+    assert!(args.changed_properties().contains_key("Metadata"));
+    assert_eq!(
+        args.changed_properties()["PlaybackStatus"],
+        Value::from("Playing")
+    );
+
+    let interface = InterfaceName::from_static_str_unchecked(PLAYER_INTERFACE);
+    let status = properties.get(interface, "PlaybackStatus").await.unwrap();
+    assert_eq!(Value::from(status), Value::from("Playing"));
+}
@@ -1,44 +1,79 @@
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::atomic::{AtomicIsize, AtomicU8, AtomicU32, Ordering};
 
 use arc_swap::ArcSwap;
 use atomic_float::{AtomicF32, AtomicF64};
 use bitflags::bitflags;
 use crossbeam_channel::Sender;
+use serde::{Deserialize, Serialize};
 
 mod audio_loop;
+mod backend;
 mod bus;
+mod channels;
 mod decoder;
 mod device;
+mod dither;
 mod effects;
 mod error;
+mod output;
+mod queue;
 mod resample;
 
 pub mod event;
 
+#[cfg(test)]
+mod tests;
+
+pub use channels::Speaker;
 pub use decoder::*;
+pub use device::{DeviceId, list_output_devices};
+pub use dither::DitherMode;
 pub use error::*;
+pub use output::OutputEvent;
+pub use queue::*;
+pub use resample::ResamplerQuality;
 
 use bus::Bus;
+use channels::ChannelMap;
 use device::SAMPLE_RATE;
 use event::{AtomicEvent, AudioEvent};
+use output::Output;
 
 bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct PlayerFlags: u8 {
         const IS_PLAYING = 1 << 0;
-        const LOOP       = 1 << 1;
-        const SHUFFLE    = 1 << 2;
+        // Bits 1 and 2 were loop and shuffle, which the queue keeps track of.
         const MUTED      = 1 << 3;
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    #[default]
+    Stopped,
+}
+
 #[derive(Debug)]
 pub struct PlayerProps {
-    pub sample_rate: u32,
+    /// Of the output device, changes when switching devices.
+    pub sample_rate: AtomicU32,
+    /// Bits the output format holds exactly, see `dither::exact_bits`.
+    pub output_bits: AtomicU32,
+    pub output_channels: AtomicU32,
+    /// Frames the device asked for in the last callback.
+    pub callback_frames: AtomicU32,
+    /// Seconds from a callback until its first frame is heard, 0 when the host can't tell.
+    pub device_latency: AtomicF64,
     pub flags: AtomicU8,
     pub position: AtomicF64,
     pub volume: AtomicF32,
     pub playback_speed: AtomicF64,
+    pub resampler_window: AtomicIsize,
 }
 
 impl PlayerProps {
@@ -59,11 +94,16 @@ impl PlayerProps {
 impl Default for PlayerProps {
     fn default() -> Self {
         Self {
-            sample_rate: 44100,
+            sample_rate: AtomicU32::new(SAMPLE_RATE),
+            output_bits: AtomicU32::new(24),
+            output_channels: AtomicU32::new(2),
+            callback_frames: AtomicU32::new(0),
+            device_latency: AtomicF64::new(0.0),
             flags: AtomicU8::new(0),
             position: AtomicF64::new(0.0),
             volume: AtomicF32::new(0.4),
             playback_speed: AtomicF64::new(0.97),
+            resampler_window: AtomicIsize::new(ResamplerQuality::default().window_size()),
         }
     }
 }
@@ -71,8 +111,27 @@ impl Default for PlayerProps {
 impl PlayerProps {
     pub fn get_playback_rate(&self, sample_rate: u32) -> f64 {
         let speed = self.playback_speed.load(Ordering::Relaxed);
+        let output_rate = self.sample_rate.load(Ordering::Relaxed);
+
+        (sample_rate as f64 / output_rate as f64) * speed
+    }
+
+    pub fn channel_map(&self, buffer: &SharedAudioBuffer) -> ChannelMap {
+        let output_channels = self.output_channels.load(Ordering::Relaxed) as usize;
 
-        (sample_rate as f64 / self.sample_rate as f64) * speed
+        ChannelMap::new(&buffer.layout, buffer.channels.len(), output_channels)
+    }
+
+    /// The samples reach the device unchanged: no resampling or mixing, full volume and an
+    /// output format at least as wide as the source.
+    pub fn is_bit_exact(&self, buffer: &SharedAudioBuffer) -> bool {
+        buffer.sample_rate == self.sample_rate.load(Ordering::Relaxed)
+            && self.channel_map(buffer).is_identity()
+            && self.playback_speed.load(Ordering::Relaxed) == 1.0
+            && self.volume.load(Ordering::Relaxed) == 1.0
+            && buffer
+                .bits_per_sample
+                .is_some_and(|bits| bits <= self.output_bits.load(Ordering::Relaxed))
     }
 }
 
@@ -82,12 +141,17 @@ pub struct AudioController {
     pub shared_audio: Arc<ArcSwap<SharedAudioBuffer>>,
     event_sender: Sender<AudioEvent>,
     props: Arc<PlayerProps>,
+    output: Output,
 }
 
 #[derive(Debug, Clone)]
 pub struct SharedAudioBuffer {
     pub sample_rate: u32,
     pub channels: Arc<Vec<Vec<f32>>>,
+    /// Speaker of each channel, the usual one for the channel count when empty.
+    pub layout: Vec<Speaker>,
+    /// Of the source, `None` for lossy formats.
+    pub bits_per_sample: Option<u32>,
 }
 
 impl SharedAudioBuffer {
@@ -101,6 +165,8 @@ impl Default for SharedAudioBuffer {
         Self {
             sample_rate: SAMPLE_RATE,
             channels: Arc::new(Vec::new()),
+            layout: Vec::new(),
+            bits_per_sample: None,
         }
     }
 }
@@ -132,8 +198,31 @@ impl AudioController {
         };
     }
 
+    pub fn set_resampler_quality(&self, quality: ResamplerQuality) {
+        self.props
+            .resampler_window
+            .store(quality.window_size(), Ordering::Relaxed);
+    }
+
     pub fn sample_rate(&self) -> u32 {
-        self.props.sample_rate
+        self.props.sample_rate.load(Ordering::Relaxed)
+    }
+
+    /// The loaded track reaches the device unchanged.
+    pub fn is_bit_perfect(&self) -> bool {
+        self.props.is_bit_exact(&self.shared_audio.load())
+    }
+
+    pub fn callback_frames(&self) -> u32 {
+        self.props.callback_frames.load(Ordering::Relaxed)
+    }
+
+    /// Seconds between a sample being written and it being heard: one callback's worth of
+    /// frames plus what the host reports for the device.
+    pub fn output_latency(&self) -> f64 {
+        let buffered = self.callback_frames() as f64 / self.sample_rate().max(1) as f64;
+
+        buffered + self.props.device_latency.load(Ordering::Relaxed)
     }
 
     pub fn get_volume(&self) -> f32 {
@@ -156,14 +245,55 @@ impl AudioController {
         self.props.position.load(Ordering::Relaxed)
     }
 
+    /// The position minus what's still on its way to the speakers, for showing to the user.
+    pub fn get_audible_position(&self) -> f64 {
+        let position = self.get_song_position();
+        if !self.get_is_playing() {
+            return position;
+        }
+
+        let behind = self.output_latency() * self.sample_rate() as f64 * self.get_playback_rate();
+
+        (position - behind).max(0.0)
+    }
+
     pub fn get_song_position_percent(&self) -> f64 {
         let buffer = self.shared_audio.load();
         let duration_samples = buffer.duration() as f64;
-        let pos_samples = self.get_song_position();
+        let pos_samples = self.get_audible_position();
 
         pos_samples / duration_samples
     }
 
+    /// Seconds into the loaded track.
+    pub fn get_position_seconds(&self) -> f64 {
+        let buffer = self.shared_audio.load();
+
+        match buffer.sample_rate {
+            0 => 0.0,
+            rate => self.get_audible_position() / rate as f64,
+        }
+    }
+
+    pub fn set_position_seconds(&self, seconds: f64) {
+        let buffer = self.shared_audio.load();
+        let position = (seconds * buffer.sample_rate as f64).clamp(0.0, buffer.duration() as f64);
+
+        self.props.position.store(position, Ordering::SeqCst);
+    }
+
+    pub fn flags(&self) -> PlayerFlags {
+        PlayerFlags::from_bits_truncate(self.props.flags.load(Ordering::Relaxed))
+    }
+
+    /// Replaces every flag but `IS_PLAYING`, which only `Play` and `Pause` change.
+    pub fn set_flags(&self, flags: PlayerFlags) {
+        let playing = self.flags() & PlayerFlags::IS_PLAYING;
+        let flags = (flags - PlayerFlags::IS_PLAYING) | playing;
+
+        self.props.flags.store(flags.bits(), Ordering::SeqCst);
+    }
+
     pub fn get_is_playing(&self) -> bool {
         self.props
             .get_flag(PlayerFlags::IS_PLAYING, Ordering::Relaxed)
@@ -2,24 +2,47 @@ use arc_swap::ArcSwap;
 use assert_no_alloc::*;
 use crossbeam_channel::Receiver;
 use std::sync::{Arc, atomic::Ordering};
+use std::time::Duration;
 
 use super::bus::Bus;
+use super::channels::MAX_CHANNELS;
+use super::dither::Dither;
 use super::resample::interpolate;
+use crate::config::DitherConfig;
 use crate::player::{AudioEvent, PlayerFlags, PlayerProps, SharedAudioBuffer};
 
 #[cfg(debug_assertions)]
 #[global_allocator]
 static A: AllocDisabler = AllocDisabler;
 
-pub fn audio_loop<S>(data: &mut [S], state: AudioLoopState)
-where
+pub fn audio_loop<S>(
+    data: &mut [S],
+    state: AudioLoopState,
+    dither: &mut Option<Dither>,
+    latency: Option<Duration>,
+) where
     S: cpal::Sample + cpal::FromSample<f32>,
 {
     let bus = state.bus;
     let shared = state.shared.load();
     let volume = state.props.volume.load(Ordering::Relaxed);
+    let window_size = state.props.resampler_window.load(Ordering::Relaxed);
+
+    let output_channels = state.props.output_channels.load(Ordering::Relaxed).max(1);
+    state
+        .props
+        .callback_frames
+        .store(data.len() as u32 / output_channels, Ordering::Relaxed);
+    state.props.device_latency.store(
+        latency.map_or(0.0, |latency| latency.as_secs_f64()),
+        Ordering::Relaxed,
+    );
 
     let ratio = state.props.get_playback_rate(shared.sample_rate);
+    // Samples are copied as they are at the output rate and normal speed.
+    let direct = ratio == 1.0;
+    let bit_exact = state.props.is_bit_exact(&shared);
+    let channel_map = state.props.channel_map(&shared);
 
     if let Ok(msg) = state.rx.try_recv()
         && let AudioEvent::Stop = msg
@@ -41,20 +64,38 @@ where
             return;
         }
 
-        let channels = shared.channels.len();
-        if channels == 0 {
+        if shared.channels.is_empty() {
             data.fill(S::EQUILIBRIUM);
             return;
         }
 
         let len = shared.channels[0].len() as f64;
+        if direct {
+            pos = pos.round();
+        }
+
+        let mut source = [0.0; MAX_CHANNELS];
+
+        for frame in data.chunks_mut(output_channels as usize) {
+            for (ch, chan_data) in shared.channels.iter().take(MAX_CHANNELS).enumerate() {
+                source[ch] = match direct {
+                    true => chan_data.get(pos as usize).copied().unwrap_or(0.0),
+                    false => interpolate(chan_data, pos, window_size),
+                } * volume;
+            }
 
-        for frame in data.chunks_mut(channels) {
             for (ch, out_sample) in frame.iter_mut().enumerate() {
-                let chan_data = &shared.channels[ch];
-                let sample = interpolate(chan_data, pos) * volume;
+                if ch >= MAX_CHANNELS {
+                    *out_sample = S::EQUILIBRIUM;
+                    continue;
+                }
 
-                *out_sample = S::from_sample(sample);
+                let sample = channel_map.mix(ch, &source);
+
+                *out_sample = match dither {
+                    Some(dither) if !bit_exact => S::from_sample(dither.apply(ch, sample)),
+                    _ => S::from_sample(sample),
+                };
 
                 if ch == 0 {
                     bus.send(sample);
@@ -79,21 +120,50 @@ pub(super) struct AudioLoopState {
     pub props: Arc<PlayerProps>,
 }
 
+/// Fills the buffers of one output stream.
+pub(super) struct Renderer {
+    state: AudioLoopState,
+    dither: Option<Dither>,
+}
+
+impl Renderer {
+    pub fn new(state: AudioLoopState, format: cpal::SampleFormat, dither: &DitherConfig) -> Self {
+        Self {
+            state,
+            dither: Dither::for_format(format, dither.mode(format)),
+        }
+    }
+
+    /// `latency` is how long until the first frame of `data` is heard, when known.
+    pub fn render<S>(&mut self, data: &mut [S], latency: Option<Duration>)
+    where
+        S: cpal::Sample + cpal::FromSample<f32>,
+    {
+        audio_loop(data, self.state.clone(), &mut self.dither, latency)
+    }
+}
+
 #[macro_pub::macro_pub(super)]
 macro_rules! build_stream_match {
-    ($device:expr, $props: expr, $config:expr, $state:expr, $err_fn:expr, { $( $fmt:path => $ty:ty ),* $(,)? }) => {{
-        use crate::player::audio_loop::audio_loop;
-
-        match $device.default_output_config().unwrap().sample_format() {
+    ($device:expr, $config:expr, $format:expr, $renderer:expr, $err_fn:expr, { $( $fmt:path => $ty:ty ),* $(,)? }) => {{
+        match $format {
             $(
-                $fmt => $device.build_output_stream(
-                    $config,
-                    move |data: &mut [$ty], _| audio_loop(data, $props),
-                    $err_fn,
-                    None,
-                ),
+                $fmt => {
+                    let mut renderer = $renderer;
+                    $device.build_output_stream(
+                        $config,
+                        move |data: &mut [$ty], info: &cpal::OutputCallbackInfo| {
+                            let timestamp = info.timestamp();
+                            let latency = timestamp.playback.duration_since(&timestamp.callback);
+
+                            renderer.render(data, latency)
+                        },
+                        $err_fn,
+                        None,
+                    )
+                }
             )*
-            other => panic!("Unsupported sample format {:?}", other),
+            _ => Err(cpal::BuildStreamError::StreamConfigNotSupported),
         }
     }};
 }
@@ -0,0 +1,52 @@
+use super::audio_loop::AudioLoopState;
+use super::device::DeviceId;
+use super::error::AudioError;
+use crate::config::AudioConfig;
+
+mod cpal_backend;
+#[cfg(test)]
+mod null;
+#[cfg(test)]
+mod wav;
+
+pub(super) use cpal_backend::CpalBackend;
+#[cfg(test)]
+pub(super) use null::{NullBackend, VirtualClock};
+#[cfg(test)]
+pub(super) use wav::WavBackend;
+
+/// Called with a description when an open stream fails.
+pub(super) type ErrorCallback = Box<dyn FnMut(String) + Send>;
+
+/// How an output plays, picked for a set of audio settings.
+#[derive(Debug, Clone)]
+pub(super) struct OutputSpec {
+    pub id: DeviceId,
+    /// The configured host or device isn't available, this is the default one.
+    pub is_fallback: bool,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: cpal::SampleFormat,
+    /// Frames per callback, when it isn't left to the driver.
+    pub buffer_frames: Option<u32>,
+}
+
+/// An opened output that doesn't play yet, so the player can adopt its format first.
+/// Dropping it closes the output.
+pub(super) trait OutputStream {
+    fn play(&mut self) -> Result<(), AudioError>;
+}
+
+/// Where rendered audio goes: the sound card, or a virtual device in tests.
+pub(super) trait Backend: Send + 'static {
+    /// What `settings` would open, without opening it.
+    fn probe(&self, settings: &AudioConfig) -> Result<OutputSpec, AudioError>;
+
+    /// Opens the output for `settings`, which renders from `state` once playing.
+    fn open(
+        &mut self,
+        settings: &AudioConfig,
+        state: AudioLoopState,
+        on_error: ErrorCallback,
+    ) -> Result<(OutputSpec, Box<dyn OutputStream>), AudioError>;
+}
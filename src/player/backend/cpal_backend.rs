@@ -0,0 +1,66 @@
+use cpal::traits::{DeviceTrait, StreamTrait};
+
+use super::{Backend, ErrorCallback, OutputSpec, OutputStream};
+use crate::config::AudioConfig;
+use crate::player::audio_loop::{AudioLoopState, Renderer, build_stream_match};
+use crate::player::device::{DeviceId, select_output};
+use crate::player::error::*;
+
+/// Plays on the system's audio devices.
+pub struct CpalBackend;
+
+/// Keeps the device's name for errors.
+struct CpalStream {
+    stream: cpal::Stream,
+    id: DeviceId,
+}
+
+impl OutputStream for CpalStream {
+    fn play(&mut self) -> Result<(), AudioError> {
+        self.stream
+            .play()
+            .map_err(|err| StreamError::StreamPlayFailed(self.id.clone(), err.to_string()).into())
+    }
+}
+
+impl Backend for CpalBackend {
+    fn probe(&self, settings: &AudioConfig) -> Result<OutputSpec, AudioError> {
+        select_output(settings).map(|target| target.spec())
+    }
+
+    fn open(
+        &mut self,
+        settings: &AudioConfig,
+        state: AudioLoopState,
+        mut on_error: ErrorCallback,
+    ) -> Result<(OutputSpec, Box<dyn OutputStream>), AudioError> {
+        let target = select_output(settings)?;
+        let renderer = Renderer::new(state, target.sample_format, &settings.dither);
+
+        let stream = build_stream_match!(
+            target.device,
+            &target.config,
+            target.sample_format,
+            renderer,
+            move |err| on_error(err.to_string()),
+            {
+                cpal::SampleFormat::F32 => f32,
+                cpal::SampleFormat::I16 => i16,
+                cpal::SampleFormat::I24 => cpal::I24,
+                cpal::SampleFormat::I32 => i32,
+                cpal::SampleFormat::I8 => i8,
+                cpal::SampleFormat::U16 => u16,
+                cpal::SampleFormat::U32 => u32,
+                cpal::SampleFormat::U8 => u8,
+            }
+        )
+        .map_err(|err| StreamError::StreamBuildFailed(target.id.clone(), err.to_string()))?;
+
+        let stream = CpalStream {
+            stream,
+            id: target.id.clone(),
+        };
+
+        Ok((target.spec(), Box::new(stream)))
+    }
+}
@@ -0,0 +1,223 @@
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::{Backend, ErrorCallback, OutputSpec, OutputStream};
+use crate::config::AudioConfig;
+use crate::player::audio_loop::{AudioLoopState, Renderer};
+use crate::player::device::DeviceId;
+use crate::player::error::*;
+
+/// Frames per callback when the settings leave it to the device.
+const BUFFER_FRAMES: u32 = 512;
+
+/// Takes what a virtual device plays.
+pub trait Sink: Send {
+    fn write(&mut self, renderer: &mut Renderer, frames: usize, channels: usize) -> io::Result<()>;
+}
+
+/// A device that plays into nothing, and only as far as its clock is advanced.
+pub struct NullBackend {
+    id: DeviceId,
+    sample_rate: u32,
+    /// Opened as asked for, any other rate falls back to `sample_rate`.
+    rates: Vec<u32>,
+    channels: u16,
+    sample_format: cpal::SampleFormat,
+    clock: VirtualClock,
+}
+
+/// Drives a [`NullBackend`] from the test, so playback is deterministic.
+#[derive(Clone)]
+pub struct VirtualClock(Arc<Mutex<Device>>);
+
+struct Device {
+    connected: bool,
+    playing: bool,
+    renderer: Option<Renderer>,
+    on_error: Option<ErrorCallback>,
+    channels: usize,
+    buffer_frames: usize,
+    rendered: u64,
+    /// The last callback's samples, when there's no sink.
+    output: Vec<f32>,
+    sink: Option<Box<dyn Sink>>,
+}
+
+struct NullStream(VirtualClock);
+
+impl NullBackend {
+    pub fn new(sample_rate: u32, channels: u16) -> (Self, VirtualClock) {
+        Self::create(sample_rate, channels, cpal::SampleFormat::F32, None)
+    }
+
+    pub fn with_sink(
+        sample_rate: u32,
+        channels: u16,
+        sample_format: cpal::SampleFormat,
+        sink: impl Sink + 'static,
+    ) -> (Self, VirtualClock) {
+        Self::create(sample_rate, channels, sample_format, Some(Box::new(sink)))
+    }
+
+    fn create(
+        sample_rate: u32,
+        channels: u16,
+        sample_format: cpal::SampleFormat,
+        sink: Option<Box<dyn Sink>>,
+    ) -> (Self, VirtualClock) {
+        let clock = VirtualClock(Arc::new(Mutex::new(Device {
+            connected: true,
+            playing: false,
+            renderer: None,
+            on_error: None,
+            channels: channels as usize,
+            buffer_frames: BUFFER_FRAMES as usize,
+            rendered: 0,
+            output: Vec::new(),
+            sink,
+        })));
+
+        let backend = Self {
+            id: DeviceId {
+                host: "Null".to_string(),
+                name: "null".to_string(),
+            },
+            sample_rate,
+            rates: vec![sample_rate],
+            channels,
+            sample_format,
+            clock: clock.clone(),
+        };
+
+        (backend, clock)
+    }
+
+    /// Also opens at these rates when asked to.
+    pub fn with_rates(mut self, rates: &[u32]) -> Self {
+        self.rates.extend_from_slice(rates);
+        self
+    }
+}
+
+impl Backend for NullBackend {
+    fn probe(&self, settings: &AudioConfig) -> Result<OutputSpec, AudioError> {
+        if !self.clock.device().connected {
+            return Err(ConfigError::NoOutputDevice(self.id.host.clone()).into());
+        }
+
+        Ok(OutputSpec {
+            id: self.id.clone(),
+            is_fallback: settings
+                .device
+                .as_ref()
+                .is_some_and(|name| *name != self.id.name),
+            sample_rate: settings
+                .sample_rate
+                .filter(|rate| self.rates.contains(rate))
+                .unwrap_or(self.sample_rate),
+            channels: self.channels,
+            sample_format: self.sample_format,
+            buffer_frames: Some(settings.buffer_size.unwrap_or(BUFFER_FRAMES)),
+        })
+    }
+
+    fn open(
+        &mut self,
+        settings: &AudioConfig,
+        state: AudioLoopState,
+        on_error: ErrorCallback,
+    ) -> Result<(OutputSpec, Box<dyn OutputStream>), AudioError> {
+        let spec = self.probe(settings)?;
+        let mut device = self.clock.device();
+
+        device.renderer = Some(Renderer::new(state, spec.sample_format, &settings.dither));
+        device.on_error = Some(on_error);
+        device.buffer_frames = spec.buffer_frames.unwrap_or(BUFFER_FRAMES) as usize;
+
+        Ok((spec, Box::new(NullStream(self.clock.clone()))))
+    }
+}
+
+impl VirtualClock {
+    fn device(&self) -> MutexGuard<'_, Device> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Plays `frames` frames, one callback's worth at a time, if a stream is playing.
+    pub fn advance(&self, frames: usize) {
+        let mut device = self.device();
+        let device = &mut *device;
+        let mut left = frames;
+
+        while left > 0 && device.playing {
+            let Some(renderer) = device.renderer.as_mut() else {
+                return;
+            };
+
+            let frames = left.min(device.buffer_frames);
+            let written = match device.sink.as_mut() {
+                Some(sink) => sink.write(renderer, frames, device.channels),
+                None => {
+                    device.output.resize(frames * device.channels, 0.0);
+                    renderer.render(&mut device.output, None);
+                    Ok(())
+                }
+            };
+
+            if let Err(err) = written
+                && let Some(on_error) = device.on_error.as_mut()
+            {
+                on_error(err.to_string());
+            }
+
+            device.rendered += frames as u64;
+            left -= frames;
+        }
+    }
+
+    /// Frames played since the device was created.
+    pub fn rendered(&self) -> u64 {
+        self.device().rendered
+    }
+
+    /// Interleaved samples of the last callback.
+    pub fn last_output(&self) -> Vec<f32> {
+        self.device().output.clone()
+    }
+
+    /// Whether a stream is open and playing.
+    pub fn is_playing(&self) -> bool {
+        let device = self.device();
+        device.playing && device.renderer.is_some()
+    }
+
+    /// Fails the open stream and the ones after it, like pulling out a USB device.
+    pub fn unplug(&self) {
+        let mut device = self.device();
+        device.connected = false;
+
+        if let Some(on_error) = device.on_error.as_mut() {
+            on_error("The device was unplugged".to_string());
+        }
+    }
+
+    pub fn plug_in(&self) {
+        self.device().connected = true;
+    }
+}
+
+impl OutputStream for NullStream {
+    fn play(&mut self) -> Result<(), AudioError> {
+        self.0.device().playing = true;
+        Ok(())
+    }
+}
+
+impl Drop for NullStream {
+    fn drop(&mut self) {
+        let mut device = self.0.device();
+        device.playing = false;
+        device.renderer = None;
+        device.on_error = None;
+    }
+}
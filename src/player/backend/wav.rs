@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::null::Sink;
+use super::{Backend, ErrorCallback, NullBackend, OutputSpec, OutputStream, VirtualClock};
+use crate::config::AudioConfig;
+use crate::player::audio_loop::{AudioLoopState, Renderer};
+use crate::player::error::AudioError;
+
+const HEADER_LEN: u32 = 44;
+
+/// A virtual device that writes what it plays to a 16-bit PCM WAV file.
+pub struct WavBackend(NullBackend);
+
+struct WavWriter {
+    file: BufWriter<File>,
+    data_len: u32,
+    samples: Vec<i16>,
+}
+
+impl WavBackend {
+    pub fn create(
+        path: impl AsRef<Path>,
+        sample_rate: u32,
+        channels: u16,
+    ) -> io::Result<(Self, VirtualClock)> {
+        let writer = WavWriter::create(path.as_ref(), sample_rate, channels)?;
+        let (backend, clock) =
+            NullBackend::with_sink(sample_rate, channels, cpal::SampleFormat::I16, writer);
+
+        Ok((Self(backend), clock))
+    }
+}
+
+impl Backend for WavBackend {
+    fn probe(&self, settings: &AudioConfig) -> Result<OutputSpec, AudioError> {
+        self.0.probe(settings)
+    }
+
+    fn open(
+        &mut self,
+        settings: &AudioConfig,
+        state: AudioLoopState,
+        on_error: ErrorCallback,
+    ) -> Result<(OutputSpec, Box<dyn OutputStream>), AudioError> {
+        self.0.open(settings, state, on_error)
+    }
+}
+
+impl WavWriter {
+    fn create(path: &Path, sample_rate: u32, channels: u16) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let block_align = channels * 2;
+
+        file.write_all(b"RIFF")?;
+        file.write_all(&(HEADER_LEN - 8).to_le_bytes())?;
+        file.write_all(b"WAVEfmt ")?;
+        file.write_all(&16u32.to_le_bytes())?;
+        file.write_all(&1u16.to_le_bytes())?;
+        file.write_all(&channels.to_le_bytes())?;
+        file.write_all(&sample_rate.to_le_bytes())?;
+        file.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+        file.write_all(&block_align.to_le_bytes())?;
+        file.write_all(&16u16.to_le_bytes())?;
+        file.write_all(b"data")?;
+        file.write_all(&0u32.to_le_bytes())?;
+
+        Ok(Self {
+            file,
+            data_len: 0,
+            samples: Vec::new(),
+        })
+    }
+
+    /// Keeps the sizes in the header right after every write, so the file is complete
+    /// whenever the test stops.
+    fn update_header(&mut self) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file
+            .write_all(&(HEADER_LEN - 8 + self.data_len).to_le_bytes())?;
+        self.file.seek(SeekFrom::Start(HEADER_LEN as u64 - 4))?;
+        self.file.write_all(&self.data_len.to_le_bytes())?;
+        self.file.seek(SeekFrom::End(0))?;
+        self.file.flush()
+    }
+}
+
+impl Sink for WavWriter {
+    fn write(&mut self, renderer: &mut Renderer, frames: usize, channels: usize) -> io::Result<()> {
+        self.samples.resize(frames * channels, 0);
+        renderer.render(&mut self.samples, None);
+
+        for sample in &self.samples {
+            self.file.write_all(&sample.to_le_bytes())?;
+        }
+
+        self.data_len += self.samples.len() as u32 * 2;
+        self.update_header()
+    }
+}
@@ -0,0 +1,201 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use symphonia::core::audio::Channels;
+
+/// Channels past this are dropped on both sides of the mix.
+pub const MAX_CHANNELS: usize = 8;
+
+/// Where a channel is meant to be played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speaker {
+    FrontLeft,
+    FrontRight,
+    FrontCenter,
+    Lfe,
+    BackLeft,
+    BackRight,
+    BackCenter,
+    SideLeft,
+    SideRight,
+    Other,
+}
+
+/// Gains from every source channel to every output channel, built for each callback so
+/// the audio thread doesn't allocate.
+#[derive(Debug, Clone)]
+pub struct ChannelMap {
+    gains: [[f32; MAX_CHANNELS]; MAX_CHANNELS],
+    identity: bool,
+}
+
+impl From<Channels> for Speaker {
+    fn from(value: Channels) -> Self {
+        match value {
+            Channels::FRONT_LEFT => Self::FrontLeft,
+            Channels::FRONT_RIGHT => Self::FrontRight,
+            Channels::FRONT_CENTRE => Self::FrontCenter,
+            Channels::LFE1 | Channels::LFE2 => Self::Lfe,
+            Channels::REAR_LEFT => Self::BackLeft,
+            Channels::REAR_RIGHT => Self::BackRight,
+            Channels::REAR_CENTRE => Self::BackCenter,
+            Channels::SIDE_LEFT => Self::SideLeft,
+            Channels::SIDE_RIGHT => Self::SideRight,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl Speaker {
+    /// The usual layout for files and devices that only give a channel count.
+    pub fn layout(channels: usize) -> &'static [Speaker] {
+        use Speaker::*;
+
+        match channels {
+            0 => &[],
+            1 => &[FrontCenter],
+            2 => &[FrontLeft, FrontRight],
+            3 => &[FrontLeft, FrontRight, FrontCenter],
+            4 => &[FrontLeft, FrontRight, BackLeft, BackRight],
+            5 => &[FrontLeft, FrontRight, FrontCenter, BackLeft, BackRight],
+            6 => &[FrontLeft, FrontRight, FrontCenter, Lfe, BackLeft, BackRight],
+            7 => &[
+                FrontLeft,
+                FrontRight,
+                FrontCenter,
+                Lfe,
+                BackCenter,
+                SideLeft,
+                SideRight,
+            ],
+            _ => &[
+                FrontLeft,
+                FrontRight,
+                FrontCenter,
+                Lfe,
+                BackLeft,
+                BackRight,
+                SideLeft,
+                SideRight,
+            ],
+        }
+    }
+}
+
+impl ChannelMap {
+    /// `source` is the layout of the decoded channels, ignored when it doesn't match
+    /// their count.
+    pub fn new(source: &[Speaker], source_channels: usize, output_channels: usize) -> Self {
+        let source = match source.len() == source_channels {
+            true => source,
+            false => Speaker::layout(source_channels),
+        };
+        let source = &source[..source.len().min(MAX_CHANNELS)];
+        let output = Speaker::layout(output_channels.min(MAX_CHANNELS));
+        let output = &output[..output_channels.min(output.len())];
+
+        let mut map = Self {
+            gains: [[0.0; MAX_CHANNELS]; MAX_CHANNELS],
+            identity: source == output,
+        };
+
+        // Mono is played on every front speaker at full level.
+        if source.len() == 1 {
+            for (out, speaker) in output.iter().enumerate() {
+                if matches!(
+                    speaker,
+                    Speaker::FrontLeft | Speaker::FrontRight | Speaker::FrontCenter
+                ) {
+                    map.gains[out][0] = 1.0;
+                }
+            }
+
+            return map;
+        }
+
+        for (src, &speaker) in source.iter().enumerate() {
+            map.route(src, speaker, 1.0, output);
+        }
+
+        // Downmixing adds channels together, scale down so a full mix can't clip.
+        let loudest = map
+            .gains
+            .iter()
+            .map(|row| row.iter().sum::<f32>())
+            .fold(0.0, f32::max);
+
+        if loudest > 1.0 {
+            map.gains
+                .iter_mut()
+                .flatten()
+                .for_each(|gain| *gain /= loudest);
+        }
+
+        map
+    }
+
+    /// Nothing is mixed, every channel goes out where it came in.
+    pub fn is_identity(&self) -> bool {
+        self.identity
+    }
+
+    /// Output channel `out` for one frame of source samples.
+    #[inline]
+    pub fn mix(&self, out: usize, frame: &[f32; MAX_CHANNELS]) -> f32 {
+        if self.identity {
+            return frame[out];
+        }
+
+        self.gains[out]
+            .iter()
+            .zip(frame)
+            .map(|(gain, sample)| gain * sample)
+            .sum()
+    }
+
+    /// Standard downmix: centre and surrounds fold into the front pair at -3 dB, the LFE
+    /// is left out.
+    fn route(&mut self, src: usize, speaker: Speaker, gain: f32, output: &[Speaker]) {
+        if let Some(out) = output.iter().position(|&s| s == speaker) {
+            self.gains[out][src] += gain;
+            return;
+        }
+
+        let has = |speaker| output.contains(&speaker);
+
+        match speaker {
+            Speaker::FrontLeft | Speaker::FrontRight if has(Speaker::FrontCenter) => {
+                self.route(src, Speaker::FrontCenter, gain * 0.5, output)
+            }
+            Speaker::FrontCenter | Speaker::BackCenter | Speaker::Other
+                if has(Speaker::FrontLeft) =>
+            {
+                let gain = match speaker {
+                    Speaker::FrontCenter => gain * FRAC_1_SQRT_2,
+                    _ => gain * 0.5,
+                };
+
+                self.route(src, Speaker::FrontLeft, gain, output);
+                self.route(src, Speaker::FrontRight, gain, output);
+            }
+            Speaker::BackLeft if has(Speaker::SideLeft) => {
+                self.route(src, Speaker::SideLeft, gain, output)
+            }
+            Speaker::BackRight if has(Speaker::SideRight) => {
+                self.route(src, Speaker::SideRight, gain, output)
+            }
+            Speaker::SideLeft if has(Speaker::BackLeft) => {
+                self.route(src, Speaker::BackLeft, gain, output)
+            }
+            Speaker::SideRight if has(Speaker::BackRight) => {
+                self.route(src, Speaker::BackRight, gain, output)
+            }
+            Speaker::BackLeft | Speaker::SideLeft => {
+                self.route(src, Speaker::FrontLeft, gain * FRAC_1_SQRT_2, output)
+            }
+            Speaker::BackRight | Speaker::SideRight => {
+                self.route(src, Speaker::FrontRight, gain * FRAC_1_SQRT_2, output)
+            }
+            _ => {}
+        }
+    }
+}
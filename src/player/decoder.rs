@@ -1,21 +1,29 @@
 use cfg_if::cfg_if;
-use std::{path::Path, sync::Arc};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::player::SharedAudioBuffer;
+use crate::player::{SharedAudioBuffer, Speaker};
 
 #[cfg(feature = "opus")]
 mod opus;
 mod sym;
 
+#[cfg(test)]
+mod tests;
+
 #[derive(Debug, thiserror::Error, serde::Serialize)]
 pub enum DecodingError {
-    #[error("")]
+    #[error("The file has no audio track.")]
     NoTrack,
 
-    #[error("Unsupported format {0}.")]
+    #[error("{} is not an audio file that can be recognized.", .0.display())]
+    UnrecognizedFile(PathBuf),
+
+    /// The mime type of a file that isn't audio, or whose codec isn't built in.
+    #[error("Unsupported format: {0}.")]
     UnsupportedFormat(String),
 
-    #[error("{0}")]
+    #[error("Could not read the file: {0}")]
     Io(
         #[from]
         #[serde(skip)]
@@ -23,28 +31,55 @@ pub enum DecodingError {
     ),
 
     #[cfg(feature = "opus")]
-    #[error("{0}")]
+    #[error("Could not decode the Opus stream: {0}")]
     Opus(
         #[from]
         #[serde(skip)]
         ogg_opus::Error,
     ),
 
-    #[error("{0}")]
+    #[error("Could not decode the file: {0}")]
     Symphonia(
         #[from]
         #[serde(skip)]
         symphonia::core::errors::Error,
     ),
 
-    #[error("Failed to decode {0} file.")]
-    Path(String),
+    #[error("Could not open {path}: {1}", path = .0.display())]
+    Open(
+        PathBuf,
+        #[source]
+        #[serde(skip)]
+        std::io::Error,
+    ),
+
+    #[error("No packet could be decoded.")]
+    NoAudio,
+}
+
+/// Something that went wrong while decoding without losing the whole track.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum DecodingWarning {
+    #[error("Skipped {0} damaged packets.")]
+    SkippedPackets(usize),
+
+    #[error("Skipped {0} packets with a different number of channels.")]
+    ChannelsChanged(usize),
+
+    #[error("Stopped reading early: {0}")]
+    ReadStopped(String),
+
+    #[error("The file ends {0:.1} s early.")]
+    Truncated(f64),
 }
 
 #[derive(Debug, Clone)]
 pub struct DecoderResult {
     pub channels: Arc<Vec<Vec<f32>>>,
     pub sample_rate: u32,
+    pub layout: Vec<Speaker>,
+    pub bits_per_sample: Option<u32>,
+    pub warnings: Vec<DecodingWarning>,
 }
 
 impl From<DecoderResult> for SharedAudioBuffer {
@@ -52,6 +87,8 @@ impl From<DecoderResult> for SharedAudioBuffer {
         Self {
             sample_rate: value.sample_rate,
             channels: value.channels,
+            layout: value.layout,
+            bits_per_sample: value.bits_per_sample,
         }
     }
 }
@@ -62,15 +99,17 @@ fn get_mime_type<P>(path: &P) -> Result<String, DecodingError>
 where
     P: AsRef<Path> + ?Sized,
 {
-    let kind = infer::get_from_path(path)
-        .map_err(|_| DecodingError::Path(path.as_ref().to_string_lossy().to_string()))?;
-
-    if let Some(k) = kind {
-        Ok(k.mime_type().to_string())
-    } else {
-        Err(DecodingError::UnsupportedFormat(
-            path.as_ref().to_string_lossy().to_string(),
-        ))
+    let mut info = infer::Infer::new();
+    // Not among infer's matchers.
+    info.add("audio/x-caf", "caf", |buf| buf.starts_with(b"caff"));
+
+    let kind = info
+        .get_from_path(path)
+        .map_err(|err| DecodingError::Open(path.as_ref().to_path_buf(), err))?;
+
+    match kind {
+        Some(k) => Ok(k.mime_type().to_string()),
+        None => Err(DecodingError::UnrecognizedFile(path.as_ref().to_path_buf())),
     }
 }
 
@@ -79,10 +118,10 @@ where
     P: AsRef<Path> + ?Sized,
 {
     let mime = get_mime_type(&path)?;
-    println!("mime: {mime}");
+    log::debug!("Decoding {} as {mime}", path.as_ref().display());
 
     match mime.as_ref() {
-        "audio/x-opus+ogg" => {
+        "audio/opus" | "audio/x-opus+ogg" => {
             cfg_if! {
                 if #[cfg(feature="opus")] { opus::decode_audio(&path) }
                 else {
@@ -90,9 +129,9 @@ where
                 }
             }
         }
-        "audio/aac" | "audio/flac" | "audio/mp2" | "audio/mp4" | "audio/mpeg" | "audio/ogg"
-        | "audio/x-aiff" | "audio/x-caf" | "audio/x-vorbis+ogg" | "audio/x-wav"
-        | "audio/vnd.wave" => sym::decode_audio(&path),
+        "audio/aac" | "audio/flac" | "audio/x-flac" | "audio/m4a" | "audio/mp2" | "audio/mp4"
+        | "audio/mpeg" | "audio/ogg" | "audio/x-aiff" | "audio/x-caf" | "audio/x-vorbis+ogg"
+        | "audio/x-wav" | "audio/vnd.wave" => sym::decode_audio(&path),
         mime => Err(DecodingError::UnsupportedFormat(mime.to_string())),
     }
 }
@@ -1,5 +1,6 @@
 use std::fs::File;
 use std::path::Path;
+use std::sync::Arc;
 
 use super::{DecoderResult, DecodingError, DecodingResult};
 
@@ -22,6 +23,9 @@ pub fn decode_audio<P: AsRef<Path>>(path: &P) -> DecodingResult {
 
     Ok(DecoderResult {
         sample_rate: 48_000,
-        channels: channels_data,
+        channels: Arc::new(channels_data),
+        layout: Vec::new(),
+        bits_per_sample: None,
+        warnings: Vec::new(),
     })
 }
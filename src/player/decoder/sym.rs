@@ -1,21 +1,28 @@
 use std::fs::File;
+use std::io::ErrorKind;
 use std::path::Path;
 use std::sync::Arc;
 use symphonia::core::audio::SampleBuffer;
-use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::codecs::{CODEC_TYPE_NULL, DecoderOptions};
+use symphonia::core::errors::Error;
 use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::{MediaSourceStream, MediaSourceStreamOptions};
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::{Hint, ProbeResult};
 use symphonia::default::{get_codecs, get_probe};
 
+use crate::player::Speaker;
 use crate::player::device::SAMPLE_RATE;
 
-use super::{DecoderResult, DecodingError, DecodingResult};
+use super::{DecoderResult, DecodingError, DecodingResult, DecodingWarning};
+
+/// Frames a decode may come up short of the announced length, as codecs pad and trim the
+/// last packet.
+const TRUNCATION_TOLERANCE: u64 = 4096;
 
 fn create_probe<P: AsRef<Path>>(path: &P) -> Result<ProbeResult, DecodingError> {
     let probe = get_probe();
-    let file = File::open(path).unwrap();
+    let file = File::open(path)?;
     let media_source = MediaSourceStream::new(Box::new(file), MediaSourceStreamOptions::default());
 
     let probe_result = probe.format(
@@ -30,32 +37,81 @@ fn create_probe<P: AsRef<Path>>(path: &P) -> Result<ProbeResult, DecodingError>
 
 pub fn decode_audio<P: AsRef<Path>>(path: &P) -> DecodingResult {
     let mut probe = create_probe(path)?;
-    let track = probe.format.default_track().ok_or(DecodingError::NoTrack)?;
-    let codec_registry = get_codecs();
+    let track = probe
+        .format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(DecodingError::NoTrack)?;
 
-    let mut decoder = codec_registry
-        .make(&track.codec_params, &DecoderOptions::default())
-        .unwrap();
+    let track_id = track.id;
+    let params = track.codec_params.clone();
+    let mut decoder = get_codecs().make(&params, &DecoderOptions::default())?;
 
-    let sample_rate = track.codec_params.sample_rate.unwrap_or(SAMPLE_RATE);
+    let sample_rate = params.sample_rate.unwrap_or(SAMPLE_RATE);
+    let bits_per_sample = params.bits_per_sample;
     let mut channels_data: Vec<Vec<f32>> = Vec::new();
+    let mut layout = Vec::new();
     let mut sample_buf: Option<SampleBuffer<f32>> = None;
+    let mut skipped = 0;
+    let mut mismatched = 0;
+    let mut stopped = None;
+
+    loop {
+        let packet = match probe.format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::ResetRequired) => {
+                // The stream changed in a way the decoder has to start over for.
+                decoder = get_codecs().make(&params, &DecoderOptions::default())?;
+                continue;
+            }
+            Err(Error::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) if channels_data.is_empty() => return Err(err.into()),
+            Err(err) => {
+                log::warn!("Stopped reading {}: {err}", path.as_ref().display());
+                stopped = Some(err.to_string());
+                break;
+            }
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let audio_buf = match decoder.decode(&packet) {
+            Ok(audio_buf) => audio_buf,
+            Err(Error::DecodeError(err)) => {
+                log::debug!("Skipping a packet of {}: {err}", path.as_ref().display());
+                skipped += 1;
+                continue;
+            }
+            Err(Error::ResetRequired) => {
+                decoder.reset();
+                skipped += 1;
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        };
 
-    while let Ok(packet) = probe.format.next_packet() {
-        let audio_buf = decoder.decode(&packet)?;
         let spec = *audio_buf.spec();
-        let duration = audio_buf.capacity() as u64;
+        let duration = audio_buf.capacity();
         let ch_count = spec.channels.count();
 
         if channels_data.is_empty() {
             channels_data = vec![Vec::with_capacity(1_000_000); ch_count];
+            layout = spec.channels.iter().map(Speaker::from).collect();
         }
 
-        if sample_buf.is_none() || sample_buf.as_ref().unwrap().capacity() < duration as usize {
-            sample_buf = Some(SampleBuffer::<f32>::new(duration, spec));
+        // Can't be spliced into the channels decoded so far.
+        if ch_count != channels_data.len() {
+            mismatched += 1;
+            continue;
         }
 
-        let buf = sample_buf.as_mut().unwrap();
+        let buf = match &mut sample_buf {
+            Some(buf) if buf.capacity() >= duration => buf,
+            buf => buf.insert(SampleBuffer::<f32>::new(duration as u64, spec)),
+        };
         buf.copy_interleaved_ref(audio_buf);
 
         for frame in buf.samples().chunks_exact(ch_count) {
@@ -65,8 +121,37 @@ pub fn decode_audio<P: AsRef<Path>>(path: &P) -> DecodingResult {
         }
     }
 
+    if channels_data.is_empty() {
+        return Err(DecodingError::NoAudio);
+    }
+
+    let mut warnings = Vec::new();
+
+    if skipped > 0 {
+        warnings.push(DecodingWarning::SkippedPackets(skipped));
+    }
+
+    if mismatched > 0 {
+        warnings.push(DecodingWarning::ChannelsChanged(mismatched));
+    }
+
+    if let Some(err) = stopped {
+        warnings.push(DecodingWarning::ReadStopped(err));
+    }
+
+    let decoded = channels_data[0].len() as u64;
+    if let Some(expected) = params.n_frames
+        && expected > decoded + TRUNCATION_TOLERANCE
+    {
+        let missing = (expected - decoded) as f64 / sample_rate as f64;
+        warnings.push(DecodingWarning::Truncated(missing));
+    }
+
     Ok(DecoderResult {
         sample_rate,
         channels: Arc::new(channels_data),
+        layout,
+        bits_per_sample,
+        warnings,
     })
 }
@@ -0,0 +1,299 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{DecoderResult, DecodingError, DecodingWarning, decode_samples, get_mime_type};
+use crate::test_util::TempDir;
+
+mod fixtures;
+
+use fixtures::*;
+
+/// Decoded audio is allowed to start late or run long by this much with lossy codecs,
+/// which add priming and padding.
+const LOSSY_TOLERANCE: f64 = 0.1;
+
+/// `sweep` written to a file of its own, in a folder removed when the test ends.
+fn fixture(
+    name: &str,
+    write: fn(&Path, &Sweep) -> std::io::Result<()>,
+    sweep: &Sweep,
+) -> (TempDir, PathBuf) {
+    let dir = TempDir::new(name);
+    let path = dir.join(name);
+    write(&path, sweep).unwrap();
+    (dir, path)
+}
+
+/// Through ffmpeg from a WAV of `sweep`. Tests using it are ignored unless asked for, as
+/// ffmpeg isn't always installed. CI installs it and runs them with `--include-ignored`.
+fn encoded(name: &str, sweep: &Sweep, args: &[&str]) -> (TempDir, PathBuf) {
+    let (dir, wav) = fixture(&format!("{name}.wav"), write_wav, sweep);
+    let path = dir.join(name);
+
+    encode_with_ffmpeg(&wav, &path, args)
+        .unwrap_or_else(|| panic!("ffmpeg could not write {name}"));
+    (dir, path)
+}
+
+/// Normalized cross-correlation of a stretch of `expected` with `decoded`, at the best
+/// lag within `max_lag` frames.
+fn correlation(expected: &[i16], decoded: &[f32], max_lag: usize) -> f64 {
+    let start = expected.len() / 4;
+    let window = &expected[start..start + 4096];
+    let energy: f64 = window.iter().map(|&s| (s as f64).powi(2)).sum();
+
+    (0..=2 * max_lag)
+        .filter_map(|offset| {
+            let from = (start + offset).checked_sub(max_lag)?;
+            let other = decoded.get(from..from + window.len())?;
+
+            let dot: f64 = window
+                .iter()
+                .zip(other)
+                .map(|(&a, &b)| a as f64 * b as f64)
+                .sum();
+            let other_energy: f64 = other.iter().map(|&s| (s as f64).powi(2)).sum();
+
+            Some(dot / (energy * other_energy).sqrt())
+        })
+        .fold(0.0, f64::max)
+}
+
+fn assert_lossless(decoded: &DecoderResult, sweep: &Sweep) {
+    assert_eq!(decoded.sample_rate, sweep.sample_rate);
+    assert_eq!(decoded.channels.len(), sweep.channels.len());
+    assert_eq!(decoded.bits_per_sample, Some(16));
+
+    for (decoded, expected) in decoded.channels.iter().zip(&sweep.channels) {
+        assert_eq!(decoded.len(), expected.len());
+
+        let exact = decoded
+            .iter()
+            .zip(expected)
+            .all(|(&d, &e)| (d * 32768.0).round() as i16 == e);
+        assert!(exact, "decoded samples differ");
+    }
+}
+
+fn assert_lossy(decoded: &DecoderResult, sweep: &Sweep) {
+    let tolerance = (LOSSY_TOLERANCE * sweep.sample_rate as f64) as usize;
+
+    assert_eq!(decoded.sample_rate, sweep.sample_rate);
+    assert_eq!(decoded.channels.len(), sweep.channels.len());
+
+    for (decoded, expected) in decoded.channels.iter().zip(&sweep.channels) {
+        assert!(
+            decoded.len().abs_diff(expected.len()) <= tolerance,
+            "{} frames decoded, {} expected",
+            decoded.len(),
+            expected.len()
+        );
+
+        let correlation = correlation(expected, decoded, tolerance);
+        assert!(correlation > 0.9, "correlation {correlation:.3}");
+    }
+}
+
+#[test]
+fn decodes_wav() {
+    let sweep = Sweep::new(48_000, 2, 0.5);
+    let (_dir, file) = fixture("sweep.wav", write_wav, &sweep);
+
+    assert_lossless(&decode_samples(&file).unwrap(), &sweep);
+}
+
+#[test]
+fn decodes_flac() {
+    let sweep = Sweep::new(44_100, 2, 0.5);
+    let (_dir, file) = fixture("sweep.flac", write_flac, &sweep);
+
+    assert_lossless(&decode_samples(&file).unwrap(), &sweep);
+}
+
+#[test]
+fn decodes_aiff() {
+    let sweep = Sweep::new(22_050, 1, 0.5);
+    let (_dir, file) = fixture("sweep.aiff", write_aiff, &sweep);
+
+    assert_lossless(&decode_samples(&file).unwrap(), &sweep);
+}
+
+#[test]
+fn decodes_caf() {
+    let sweep = Sweep::new(32_000, 2, 0.5);
+    let (_dir, file) = fixture("sweep.caf", write_caf, &sweep);
+
+    assert_lossless(&decode_samples(&file).unwrap(), &sweep);
+}
+
+#[test]
+#[ignore = "needs ffmpeg"]
+fn decodes_mp3() {
+    let sweep = Sweep::new(44_100, 2, 1.0);
+    let (_dir, file) = encoded("sweep.mp3", &sweep, &["-b:a", "192k"]);
+
+    assert_lossy(&decode_samples(&file).unwrap(), &sweep);
+}
+
+#[test]
+#[ignore = "needs ffmpeg"]
+fn decodes_vorbis() {
+    let sweep = Sweep::new(48_000, 2, 1.0);
+    let (_dir, file) = encoded("sweep.ogg", &sweep, &["-c:a", "libvorbis", "-q:a", "6"]);
+
+    assert_lossy(&decode_samples(&file).unwrap(), &sweep);
+}
+
+#[cfg(feature = "opus")]
+#[test]
+#[ignore = "needs ffmpeg"]
+fn decodes_opus() {
+    let sweep = Sweep::new(48_000, 2, 1.0);
+    let (_dir, file) = encoded("sweep.opus", &sweep, &["-c:a", "libopus", "-b:a", "128k"]);
+
+    assert_lossy(&decode_samples(&file).unwrap(), &sweep);
+}
+
+/// Only the header, which is all the format is told apart by.
+#[test]
+fn opus_is_recognized() {
+    let mut page = b"OggS".to_vec();
+    page.resize(28, 0);
+    page.extend(b"OpusHead");
+    page.resize(64, 0);
+
+    let dir = TempDir::new("header");
+    let file = dir.join("header.opus");
+    fs::write(&file, &page).unwrap();
+
+    assert_eq!(get_mime_type(&file).unwrap(), "audio/opus");
+
+    // With the feature it reaches the Opus decoder, which needs more than a header.
+    let err = decode_samples(&file).unwrap_err();
+    match cfg!(feature = "opus") {
+        true => assert!(
+            !matches!(err, DecodingError::UnsupportedFormat(_)),
+            "{err:?}"
+        ),
+        false => assert!(
+            matches!(&err, DecodingError::UnsupportedFormat(mime) if mime == "audio/opus"),
+            "{err:?}"
+        ),
+    }
+}
+
+#[test]
+fn truncated_data_decodes_what_is_there() {
+    let sweep = Sweep::new(44_100, 2, 0.5);
+    let (_dir, file) = fixture("cut.wav", write_wav, &sweep);
+
+    let bytes = fs::read(&file).unwrap();
+    fs::write(&file, &bytes[..bytes.len() / 2]).unwrap();
+
+    let decoded = decode_samples(&file).unwrap();
+    let frames = decoded.channels[0].len();
+    assert!(frames > 0 && frames < sweep.frames(), "{frames} frames");
+    assert!(
+        matches!(decoded.warnings[..], [DecodingWarning::Truncated(s)] if s > 0.2),
+        "{:?}",
+        decoded.warnings
+    );
+}
+
+#[test]
+fn damaged_packets_are_skipped() {
+    let sweep = Sweep::new(44_100, 2, 0.5);
+    let (_dir, file) = fixture("damaged.flac", write_flac, &sweep);
+
+    // Gives the first subframe of the second frame a reserved type, with a checksum that
+    // still matches so the frame reaches the decoder.
+    let mut bytes = fs::read(&file).unwrap();
+    let frames: Vec<_> = bytes
+        .windows(3)
+        .enumerate()
+        .filter(|(_, w)| *w == [0xFF, 0xF8, 0x70])
+        .map(|(i, _)| i)
+        .collect();
+    let (start, end) = (frames[1], frames[2]);
+    bytes[start + 8] = 0x7E;
+    let crc = crc16(&bytes[start..end - 2]);
+    bytes[end - 2..end].copy_from_slice(&crc.to_be_bytes());
+    fs::write(&file, &bytes).unwrap();
+
+    let decoded = decode_samples(&file).unwrap();
+    assert!(
+        decoded
+            .warnings
+            .contains(&DecodingWarning::SkippedPackets(1)),
+        "{:?}",
+        decoded.warnings
+    );
+    assert!(decoded.channels[0].len() < sweep.frames());
+}
+
+#[test]
+fn truncated_header_is_a_symphonia_error() {
+    let sweep = Sweep::new(44_100, 2, 0.5);
+    let (_dir, file) = fixture("header.wav", write_wav, &sweep);
+
+    let bytes = fs::read(&file).unwrap();
+    fs::write(&file, &bytes[..20]).unwrap();
+
+    let err = decode_samples(&file).unwrap_err();
+    assert!(matches!(err, DecodingError::Symphonia(_)), "{err:?}");
+}
+
+#[test]
+fn corrupt_flac_is_a_symphonia_error() {
+    let sweep = Sweep::new(44_100, 2, 0.5);
+    let (_dir, file) = fixture("corrupt.flac", write_flac, &sweep);
+
+    // Garbles the STREAMINFO block, leaving the signature.
+    let mut bytes = fs::read(&file).unwrap();
+    bytes[8..42].fill(0xFF);
+    fs::write(&file, &bytes).unwrap();
+
+    let err = decode_samples(&file).unwrap_err();
+    assert!(matches!(err, DecodingError::Symphonia(_)), "{err:?}");
+}
+
+#[test]
+fn unknown_files_are_unrecognized() {
+    let dir = TempDir::new("notes");
+    let file = dir.join("notes.txt");
+    fs::write(&file, "not audio at all").unwrap();
+
+    let err = decode_samples(&file).unwrap_err();
+    assert!(
+        matches!(&err, DecodingError::UnrecognizedFile(path) if *path == file),
+        "{err:?}"
+    );
+    assert!(err.to_string().contains("notes.txt"), "{err}");
+}
+
+#[test]
+fn other_media_is_unsupported() {
+    let dir = TempDir::new("image");
+    let file = dir.join("image.png");
+    fs::write(&file, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+
+    let err = decode_samples(&file).unwrap_err();
+    assert!(
+        matches!(&err, DecodingError::UnsupportedFormat(mime) if mime == "image/png"),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn missing_files_keep_the_cause() {
+    let dir = TempDir::new("missing");
+    let file = dir.join("missing.wav");
+
+    let err = decode_samples(&file).unwrap_err();
+    assert!(
+        matches!(&err, DecodingError::Open(path, source)
+            if *path == file && source.kind() == std::io::ErrorKind::NotFound),
+        "{err:?}"
+    );
+    assert!(std::error::Error::source(&err).is_some());
+}
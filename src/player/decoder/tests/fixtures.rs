@@ -0,0 +1,220 @@
+use std::f64::consts::TAU;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const FLAC_BLOCK_SIZE: usize = 4096;
+
+/// A logarithmic sweep from 200 Hz to 4 kHz at half scale, inverted on every other channel.
+pub struct Sweep {
+    pub sample_rate: u32,
+    pub channels: Vec<Vec<i16>>,
+}
+
+impl Sweep {
+    pub fn new(sample_rate: u32, channels: usize, seconds: f64) -> Self {
+        let (from, to) = (200.0f64, 4000.0f64);
+        let ratio = (to / from).ln();
+        let frames = (seconds * sample_rate as f64) as usize;
+
+        let left: Vec<i16> = (0..frames)
+            .map(|n| {
+                let t = n as f64 / sample_rate as f64;
+                let phase = TAU * from * seconds / ratio * ((t / seconds * ratio).exp() - 1.0);
+                (0.5 * phase.sin() * 32767.0).round() as i16
+            })
+            .collect();
+
+        let channels = (0..channels)
+            .map(|ch| match ch % 2 {
+                0 => left.clone(),
+                _ => left.iter().map(|&s| -s).collect(),
+            })
+            .collect();
+
+        Self {
+            sample_rate,
+            channels,
+        }
+    }
+
+    pub fn frames(&self) -> usize {
+        self.channels[0].len()
+    }
+
+    fn interleaved(&self) -> impl Iterator<Item = i16> + '_ {
+        (0..self.frames()).flat_map(|n| self.channels.iter().map(move |ch| ch[n]))
+    }
+}
+
+pub fn write_wav(path: &Path, sweep: &Sweep) -> io::Result<()> {
+    let channels = sweep.channels.len() as u16;
+    let data: Vec<u8> = sweep.interleaved().flat_map(i16::to_le_bytes).collect();
+
+    let mut file = Vec::new();
+    file.extend(b"RIFF");
+    file.extend((36 + data.len() as u32).to_le_bytes());
+    file.extend(b"WAVEfmt ");
+    file.extend(16u32.to_le_bytes());
+    file.extend(1u16.to_le_bytes());
+    file.extend(channels.to_le_bytes());
+    file.extend(sweep.sample_rate.to_le_bytes());
+    file.extend((sweep.sample_rate * channels as u32 * 2).to_le_bytes());
+    file.extend((channels * 2).to_le_bytes());
+    file.extend(16u16.to_le_bytes());
+    file.extend(b"data");
+    file.extend((data.len() as u32).to_le_bytes());
+    file.extend(data);
+
+    fs::write(path, file)
+}
+
+pub fn write_aiff(path: &Path, sweep: &Sweep) -> io::Result<()> {
+    let data: Vec<u8> = sweep.interleaved().flat_map(i16::to_be_bytes).collect();
+
+    let mut file = Vec::new();
+    file.extend(b"FORM");
+    file.extend((4 + 26 + 16 + data.len() as u32).to_be_bytes());
+    file.extend(b"AIFFCOMM");
+    file.extend(18u32.to_be_bytes());
+    file.extend((sweep.channels.len() as u16).to_be_bytes());
+    file.extend((sweep.frames() as u32).to_be_bytes());
+    file.extend(16u16.to_be_bytes());
+    file.extend(extended(sweep.sample_rate));
+    file.extend(b"SSND");
+    file.extend((8 + data.len() as u32).to_be_bytes());
+    file.extend(0u32.to_be_bytes());
+    file.extend(0u32.to_be_bytes());
+    file.extend(data);
+
+    fs::write(path, file)
+}
+
+pub fn write_caf(path: &Path, sweep: &Sweep) -> io::Result<()> {
+    let channels = sweep.channels.len() as u32;
+    let data: Vec<u8> = sweep.interleaved().flat_map(i16::to_be_bytes).collect();
+
+    let mut file = Vec::new();
+    file.extend(b"caff");
+    file.extend(1u16.to_be_bytes());
+    file.extend(0u16.to_be_bytes());
+    file.extend(b"desc");
+    file.extend(32i64.to_be_bytes());
+    file.extend((sweep.sample_rate as f64).to_be_bytes());
+    file.extend(b"lpcm");
+    // Big-endian integers.
+    file.extend(0u32.to_be_bytes());
+    file.extend((channels * 2).to_be_bytes());
+    file.extend(1u32.to_be_bytes());
+    file.extend(channels.to_be_bytes());
+    file.extend(16u32.to_be_bytes());
+    file.extend(b"data");
+    file.extend((4 + data.len() as i64).to_be_bytes());
+    // Edit count.
+    file.extend(0u32.to_be_bytes());
+    file.extend(data);
+
+    fs::write(path, file)
+}
+
+/// FLAC with every subframe stored verbatim, which is valid if not much of a compression.
+pub fn write_flac(path: &Path, sweep: &Sweep) -> io::Result<()> {
+    let channels = sweep.channels.len();
+    let frames = sweep.frames();
+
+    let mut file = Vec::new();
+    file.extend(b"fLaC");
+    // Last metadata block, STREAMINFO, 34 bytes.
+    file.extend([0x80, 0, 0, 34]);
+    file.extend((FLAC_BLOCK_SIZE as u16).to_be_bytes());
+    file.extend((FLAC_BLOCK_SIZE as u16).to_be_bytes());
+    file.extend([0; 6]);
+
+    // 20 bits of sample rate, 3 of channels - 1, 5 of bits - 1, 36 of total samples.
+    let info = (sweep.sample_rate as u64) << 44
+        | ((channels as u64 - 1) << 41)
+        | (15 << 36)
+        | frames as u64;
+    file.extend(info.to_be_bytes());
+    file.extend([0; 16]);
+
+    for (number, start) in (0..frames).step_by(FLAC_BLOCK_SIZE).enumerate() {
+        let len = FLAC_BLOCK_SIZE.min(frames - start);
+        let mut frame = vec![0xFF, 0xF8];
+
+        // Block size in 16 bits after the header, rate from STREAMINFO, independent
+        // channels, 16 bits per sample.
+        frame.push(0x70);
+        frame.push(((channels as u8 - 1) << 4) | 0x08);
+        frame.extend(utf8_number(number as u32));
+        frame.extend((len as u16 - 1).to_be_bytes());
+        frame.push(crc8(&frame));
+
+        for channel in &sweep.channels {
+            // Verbatim subframe, no wasted bits.
+            frame.push(0x02);
+            frame.extend(
+                channel[start..start + len]
+                    .iter()
+                    .flat_map(|s| s.to_be_bytes()),
+            );
+        }
+
+        frame.extend(crc16(&frame).to_be_bytes());
+        file.extend(frame);
+    }
+
+    fs::write(path, file)
+}
+
+/// Encodes `wav` with ffmpeg, `None` when it isn't installed or can't write the format.
+pub fn encode_with_ffmpeg(wav: &Path, out: &Path, args: &[&str]) -> Option<()> {
+    let status = Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(wav)
+        .args(args)
+        .arg(out)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok()?;
+
+    status.success().then_some(())
+}
+
+/// 80-bit IEEE extended float, as AIFF stores its sample rate.
+fn extended(value: u32) -> [u8; 10] {
+    let exponent = 31 - value.leading_zeros();
+    let mantissa = (value as u64) << (63 - exponent);
+
+    let mut bytes = [0; 10];
+    bytes[..2].copy_from_slice(&(16383 + exponent as u16).to_be_bytes());
+    bytes[2..].copy_from_slice(&mantissa.to_be_bytes());
+    bytes
+}
+
+fn utf8_number(number: u32) -> Vec<u8> {
+    match number {
+        0..0x80 => vec![number as u8],
+        _ => vec![0xC0 | (number >> 6) as u8, 0x80 | (number & 0x3F) as u8],
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ byte, |crc, _| match crc & 0x80 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x07,
+        })
+    })
+}
+
+pub fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| match crc & 0x8000 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x8005,
+        })
+    })
+}
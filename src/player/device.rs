@@ -1,103 +1,201 @@
-use arc_swap::ArcSwap;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use crossbeam_channel::bounded;
-use std::sync::Arc;
-use std::thread;
-use std::time::Duration;
+use std::fmt;
 
-use super::error::*;
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::{Deserialize, Serialize};
 
-use crate::player::audio_loop::{AudioLoopState, build_stream_match};
-use crate::player::bus::Bus;
-use crate::player::{PlayerProps, SharedAudioBuffer};
+use super::backend::OutputSpec;
+use super::error::*;
 
-use super::AudioController;
+use crate::config::AudioConfig;
 
 pub const SAMPLE_RATE: u32 = 44_100;
 
-impl AudioController {
-    pub fn create() -> Result<Self, AudioError> {
-        let host = cpal::default_host();
-        let device = host
-            .default_output_device()
-            .ok_or(ConfigError::NoOutputDevice)?;
-
-        let mut supported_configs = device
-            .supported_output_configs()
-            .map_err(|_| ConfigError::ConfigQueryFailed)?;
-
-        let config: cpal::StreamConfig = pick_config(&mut supported_configs)
-            .ok_or(ConfigError::NoConfigAvailable)?
-            .into();
-
-        let shared_audio = Arc::new(ArcSwap::from_pointee(SharedAudioBuffer::default()));
-        let bus = Arc::new(Bus::default());
-        let (tx, rx) = bounded(128);
-        let rx = Arc::new(rx);
-
-        let sample_rate: u32 = config.sample_rate.0;
-        let props = Arc::new(PlayerProps {
-            sample_rate,
-            ..Default::default()
-        });
-
-        let state = AudioLoopState {
-            rx: Arc::clone(&rx),
-            bus: Arc::clone(&bus),
-            shared: Arc::clone(&shared_audio),
-            props: Arc::clone(&props),
-        };
+/// Names a device on a host, such as "default" on "ALSA".
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DeviceId {
+    pub host: String,
+    pub name: String,
+}
 
-        let stream = build_stream_match!(
-            device,
-            state.clone(),
-            &config,
-            state_for_thread,
-            |err| eprintln!("Audio stream error: {err}"),
-            {
-                cpal::SampleFormat::F32 => f32,
-                cpal::SampleFormat::I16 => i16,
-                cpal::SampleFormat::I24 => cpal::I24,
-                cpal::SampleFormat::I32 => i32,
-                cpal::SampleFormat::I8 => i8,
-                cpal::SampleFormat::U16 => u16,
-                cpal::SampleFormat::U32 => u32,
-                cpal::SampleFormat::U8 => u8,
-            }
-        )
-        .map_err(|_| StreamError::StreamBuildFailed)?;
-
-        stream.play().map_err(|_| StreamError::StreamPlayFailed)?;
-
-        thread::spawn(move || {
-            let _keep_alive = stream;
-            loop {
-                thread::sleep(Duration::from_secs(60));
-            }
-        });
-
-        Ok(AudioController {
-            _bus: bus,
-            event_sender: tx,
-            shared_audio,
-            props,
+/// An output device and what it can play, as listed by [`list_output_devices`].
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputDevice {
+    pub id: DeviceId,
+    /// The host's default output device.
+    pub is_default: bool,
+    pub configs: Vec<OutputConfig>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputConfig {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub sample_format: String,
+}
+
+/// The device picked for a set of audio settings, and how to open it.
+pub(super) struct OutputTarget {
+    pub id: DeviceId,
+    /// The configured host or device isn't available, this is the default one.
+    pub is_fallback: bool,
+    pub device: cpal::Device,
+    pub config: cpal::StreamConfig,
+    pub sample_format: cpal::SampleFormat,
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.host)
+    }
+}
+
+impl OutputTarget {
+    pub fn spec(&self) -> OutputSpec {
+        OutputSpec {
+            id: self.id.clone(),
+            is_fallback: self.is_fallback,
+            sample_rate: self.config.sample_rate.0,
+            channels: self.config.channels,
+            sample_format: self.sample_format,
+            buffer_frames: match self.config.buffer_size {
+                cpal::BufferSize::Fixed(frames) => Some(frames),
+                cpal::BufferSize::Default => None,
+            },
+        }
+    }
+}
+
+impl From<cpal::SupportedStreamConfigRange> for OutputConfig {
+    fn from(value: cpal::SupportedStreamConfigRange) -> Self {
+        Self {
+            channels: value.channels(),
+            min_sample_rate: value.min_sample_rate().0,
+            max_sample_rate: value.max_sample_rate().0,
+            sample_format: value.sample_format().to_string(),
+        }
+    }
+}
+
+/// Every output device on every available host.
+pub fn list_output_devices() -> Vec<OutputDevice> {
+    cpal::available_hosts()
+        .into_iter()
+        .filter_map(|id| cpal::host_from_id(id).ok())
+        .flat_map(|host| {
+            let host_name = host.id().name().to_string();
+            let default = host.default_output_device().and_then(|d| d.name().ok());
+
+            host.output_devices()
+                .into_iter()
+                .flatten()
+                .filter_map(|device| {
+                    let name = device.name().ok()?;
+                    let configs = device
+                        .supported_output_configs()
+                        .map(|configs| configs.map(OutputConfig::from).collect())
+                        .unwrap_or_default();
+
+                    Some(OutputDevice {
+                        is_default: default.as_ref() == Some(&name),
+                        id: DeviceId {
+                            host: host_name.clone(),
+                            name,
+                        },
+                        configs,
+                    })
+                })
+                .collect::<Vec<_>>()
         })
+        .collect()
+}
+
+/// Finds the configured device, falling back to the default one when it isn't connected.
+pub(super) fn select_output(settings: &AudioConfig) -> Result<OutputTarget, AudioError> {
+    let host = settings.host.as_deref().and_then(find_host);
+    let mut is_fallback = settings.host.is_some() && host.is_none();
+    let host = host.unwrap_or_else(cpal::default_host);
+
+    let device = settings
+        .device
+        .as_deref()
+        .and_then(|name| find_device(&host, name));
+    is_fallback |= settings.device.is_some() && device.is_none();
+
+    let device = device
+        .or_else(|| host.default_output_device())
+        .ok_or_else(|| ConfigError::NoOutputDevice(host.id().name().to_string()))?;
+
+    let id = DeviceId {
+        host: host.id().name().to_string(),
+        name: device.name().unwrap_or_default(),
+    };
+
+    let mut supported_configs = device
+        .supported_output_configs()
+        .map_err(|err| ConfigError::ConfigQueryFailed(id.clone(), err.to_string()))?;
+
+    let supported = pick_config(&mut supported_configs, settings.sample_rate)
+        .ok_or_else(|| ConfigError::NoConfigAvailable(id.clone()))?;
+    let sample_format = supported.sample_format();
+    let buffer_range = *supported.buffer_size();
+    let mut config: cpal::StreamConfig = supported.into();
+
+    if let Some(frames) = settings.buffer_size {
+        let frames = match buffer_range {
+            cpal::SupportedBufferSize::Range { min, max } => frames.max(min).min(max),
+            cpal::SupportedBufferSize::Unknown => frames,
+        };
+
+        config.buffer_size = cpal::BufferSize::Fixed(frames);
     }
+
+    Ok(OutputTarget {
+        id,
+        is_fallback,
+        device,
+        config,
+        sample_format,
+    })
+}
+
+fn find_host(name: &str) -> Option<cpal::Host> {
+    cpal::available_hosts()
+        .into_iter()
+        .find(|id| id.name().eq_ignore_ascii_case(name))
+        .and_then(|id| cpal::host_from_id(id).ok())
+}
+
+fn find_device(host: &cpal::Host, name: &str) -> Option<cpal::Device> {
+    host.output_devices()
+        .ok()?
+        .find(|d| d.name().is_ok_and(|n| n == name))
 }
 
-fn pick_config(configs: &mut cpal::SupportedOutputConfigs) -> Option<cpal::SupportedStreamConfig> {
+fn pick_config(
+    configs: &mut cpal::SupportedOutputConfigs,
+    preferred_rate: Option<u32>,
+) -> Option<cpal::SupportedStreamConfig> {
     let configs: Vec<_> = configs.collect();
-    const PREFERRED_RATE: u32 = 44_100;
 
-    // 1. Stereo F32 at 44.1k
-    if let Some(config) = configs
-        .iter()
-        .filter(|c| c.channels() >= 2 && c.sample_format() == cpal::SampleFormat::F32)
-        .find(|c| {
-            c.min_sample_rate().0 <= PREFERRED_RATE && c.max_sample_rate().0 >= PREFERRED_RATE
-        })
+    let stereo_at = |rate: u32, f32_only: bool| {
+        configs
+            .iter()
+            .filter(|c| c.channels() >= 2)
+            .filter(|c| !f32_only || c.sample_format() == cpal::SampleFormat::F32)
+            .find(|c| c.min_sample_rate().0 <= rate && c.max_sample_rate().0 >= rate)
+            .map(|c| c.with_sample_rate(cpal::SampleRate(rate)))
+    };
+
+    // 1. Stereo at the preferred rate, F32 if possible, then stereo F32 at 44.1k
+    if let Some(rate) = preferred_rate
+        && let Some(config) = stereo_at(rate, true).or_else(|| stereo_at(rate, false))
     {
-        return Some(config.with_sample_rate(cpal::SampleRate(PREFERRED_RATE)));
+        return Some(config);
+    }
+
+    if let Some(config) = stereo_at(SAMPLE_RATE, true) {
+        return Some(config);
     }
 
     // 2. Stereo F32 at any rate
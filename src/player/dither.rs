@@ -0,0 +1,222 @@
+use cpal::SampleFormat;
+use serde::{Deserialize, Serialize};
+
+use super::channels::MAX_CHANNELS;
+
+/// Error feedback filter for noise shaping, moving the requantization noise up to where
+/// hearing is less sensitive (Lipshitz, Wannamaker and Vanderkooy's 3-tap design).
+const SHAPING: [f32; 3] = [1.623, -0.982, 0.109];
+
+/// What happens to samples rounded to an integer output format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DitherMode {
+    /// Plain conversion, the rounding error follows the signal as distortion.
+    Off,
+    /// Flat noise at the level of the least significant bit instead.
+    #[default]
+    Tpdf,
+    /// TPDF with the noise shaped away from the most audible frequencies.
+    Shaped,
+}
+
+/// Bits of an integer sample the output format holds exactly, the mantissa for floats.
+pub(super) fn exact_bits(format: SampleFormat) -> u32 {
+    match format {
+        SampleFormat::I8 | SampleFormat::U8 => 8,
+        SampleFormat::I16 | SampleFormat::U16 => 16,
+        SampleFormat::I24 => 24,
+        SampleFormat::I32 | SampleFormat::U32 => 32,
+        SampleFormat::I64 | SampleFormat::U64 => 64,
+        SampleFormat::F64 => 53,
+        _ => 24,
+    }
+}
+
+/// Triangular (TPDF) dither, added before rounding to an integer format so the rounding
+/// error turns into a steady noise floor instead of distortion on quiet passages.
+#[derive(Debug, Clone)]
+pub(super) struct Dither {
+    /// One step of the output format, in the -1.0..1.0 range.
+    lsb: f32,
+    shaped: bool,
+    seed: u32,
+    /// Last rounding errors of each output channel, newest first.
+    errors: [[f32; SHAPING.len()]; MAX_CHANNELS],
+}
+
+impl Dither {
+    /// `None` when off, for float formats and integer ones wider than an `f32` mantissa.
+    pub fn for_format(format: SampleFormat, mode: DitherMode) -> Option<Self> {
+        if mode == DitherMode::Off || format.is_float() {
+            return None;
+        }
+
+        match exact_bits(format) {
+            bits @ 1..=24 => Some(Self::new(bits, mode == DitherMode::Shaped)),
+            _ => None,
+        }
+    }
+
+    fn new(bits: u32, shaped: bool) -> Self {
+        Self {
+            lsb: 1.0 / (1u32 << (bits - 1)) as f32,
+            shaped,
+            seed: 0x9E37_79B9,
+            errors: [[0.0; SHAPING.len()]; MAX_CHANNELS],
+        }
+    }
+
+    /// Rounds `sample` to a step of the output format, which then converts it exactly.
+    #[inline]
+    pub fn apply(&mut self, channel: usize, sample: f32) -> f32 {
+        let noise = (self.next() - self.next()) * self.lsb;
+        let errors = &mut self.errors[channel];
+
+        let wanted = match self.shaped {
+            true => {
+                sample
+                    - SHAPING
+                        .iter()
+                        .zip(&*errors)
+                        .map(|(c, e)| c * e)
+                        .sum::<f32>()
+            }
+            false => sample,
+        };
+
+        let quantized = ((wanted + noise) / self.lsb).round() * self.lsb;
+        let quantized = quantized.clamp(-1.0, 1.0 - self.lsb);
+
+        if self.shaped {
+            // Limited so clipping can't make the feedback run away.
+            let limit = 2.0 * self.lsb;
+            errors.rotate_right(1);
+            errors[0] = (quantized - wanted).clamp(-limit, limit);
+        }
+
+        quantized
+    }
+
+    /// Uniform in 0.0..1.0, from a xorshift so the audio thread doesn't allocate or lock.
+    #[inline]
+    fn next(&mut self) -> f32 {
+        let mut x = self.seed;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.seed = x;
+
+        (x >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 44_100.0;
+
+    /// One second of a 1 kHz sine at `level` dBFS, returned with its 16-bit rendering.
+    fn render(mode: DitherMode, level: f32) -> (Vec<f32>, Vec<f32>) {
+        let amplitude = 10f32.powf(level / 20.0);
+        let input: Vec<f32> = (0..SAMPLE_RATE as usize)
+            .map(|n| {
+                let t = n as f32 / SAMPLE_RATE;
+                amplitude * (std::f32::consts::TAU * 1000.0 * t).sin()
+            })
+            .collect();
+
+        let output = match Dither::for_format(SampleFormat::I16, mode) {
+            Some(mut dither) => input.iter().map(|&s| dither.apply(0, s)).collect(),
+            None => input
+                .iter()
+                .map(|&s| (s * 32768.0).round() / 32768.0)
+                .collect(),
+        };
+
+        (input, output)
+    }
+
+    fn error(input: &[f32], output: &[f32]) -> Vec<f32> {
+        input.iter().zip(output).map(|(i, o)| o - i).collect()
+    }
+
+    fn rms_db(signal: &[f32]) -> f32 {
+        let power = signal.iter().map(|s| s * s).sum::<f32>() / signal.len() as f32;
+        10.0 * power.log10()
+    }
+
+    /// How much of the error follows the signal, 1.0 when it's all distortion.
+    fn correlation(signal: &[f32], error: &[f32]) -> f32 {
+        let dot = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+        dot(signal, error) / (dot(signal, signal) * dot(error, error)).sqrt()
+    }
+
+    /// Power of `signal` summed over DFT bins from `from` to `to` Hz, in dB.
+    fn band_db(signal: &[f32], from: u32, to: u32) -> f32 {
+        let power: f64 = (from..=to)
+            .step_by(100)
+            .map(|freq| {
+                let w = std::f64::consts::TAU * freq as f64 / SAMPLE_RATE as f64;
+                let (re, im) = signal
+                    .iter()
+                    .enumerate()
+                    .fold((0.0, 0.0), |(re, im), (n, &s)| {
+                        let phase = w * n as f64;
+                        (re + s as f64 * phase.cos(), im + s as f64 * phase.sin())
+                    });
+
+                re * re + im * im
+            })
+            .sum();
+
+        10.0 * power.log10() as f32
+    }
+
+    #[test]
+    fn tpdf_noise_floor() {
+        let (input, output) = render(DitherMode::Tpdf, -60.0);
+        let noise = rms_db(&error(&input, &output));
+
+        // Rounding plus TPDF adds up to half a step RMS, -96.3 dBFS at 16 bits.
+        assert!(
+            (-98.0..-94.0).contains(&noise),
+            "noise floor at {noise:.1} dBFS"
+        );
+    }
+
+    #[test]
+    fn output_is_on_the_format_grid() {
+        let (_, output) = render(DitherMode::Shaped, -60.0);
+
+        assert!(output.iter().all(|s| (s * 32768.0).fract() == 0.0));
+    }
+
+    #[test]
+    fn dither_decorrelates_the_error() {
+        // Below half a step, plain rounding outputs silence and the error is the signal.
+        let (input, output) = render(DitherMode::Off, -100.0);
+        let plain = correlation(&input, &error(&input, &output));
+
+        let (input, output) = render(DitherMode::Tpdf, -100.0);
+        let dithered = correlation(&input, &error(&input, &output));
+
+        assert!(plain.abs() > 0.9, "plain rounding correlation {plain:.3}");
+        assert!(dithered.abs() < 0.05, "dithered correlation {dithered:.3}");
+    }
+
+    #[test]
+    fn noise_shaping_lowers_the_audible_noise() {
+        let (input, output) = render(DitherMode::Tpdf, -60.0);
+        let flat = band_db(&error(&input, &output), 100, 4000);
+
+        let (input, output) = render(DitherMode::Shaped, -60.0);
+        let shaped = band_db(&error(&input, &output), 100, 4000);
+
+        assert!(
+            shaped < flat - 6.0,
+            "shaped {shaped:.1} dB, flat {flat:.1} dB below 4 kHz"
+        );
+    }
+}
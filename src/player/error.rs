@@ -1,5 +1,6 @@
 use serde::Serialize;
 
+use super::device::DeviceId;
 use super::{DecodingError, event::ChannelError};
 
 #[derive(Debug, thiserror::Error, Serialize)]
@@ -23,21 +24,28 @@ pub enum AudioError {
 
 #[derive(Debug, thiserror::Error, Serialize)]
 pub enum ConfigError {
-    #[error("")]
-    NoOutputDevice,
+    /// On the named host.
+    #[error("No output device found on {0}.")]
+    NoOutputDevice(String),
 
-    #[error("")]
-    ConfigQueryFailed,
+    #[error("Could not list the formats {0} supports: {1}")]
+    ConfigQueryFailed(DeviceId, String),
 
-    #[error("")]
-    NoConfigAvailable,
+    #[error("{0} supports no format that can be played.")]
+    NoConfigAvailable(DeviceId),
 }
 
 #[derive(Debug, thiserror::Error, Serialize)]
 pub enum StreamError {
-    #[error("")]
-    StreamBuildFailed,
+    #[error("Could not open an output stream on {0}: {1}")]
+    StreamBuildFailed(DeviceId, String),
 
-    #[error("")]
-    StreamPlayFailed,
+    #[error("Could not start playback on {0}: {1}")]
+    StreamPlayFailed(DeviceId, String),
+
+    #[error("Could not start the audio output thread: {0}")]
+    ThreadFailed(#[serde(skip)] std::io::Error),
+
+    #[error("The audio output thread stopped.")]
+    ThreadStopped,
 }
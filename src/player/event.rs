@@ -23,10 +23,10 @@ impl From<AtomicEvent> for AudioEvent {
 
 #[derive(Debug, thiserror::Error)]
 pub enum ChannelError {
-    #[error("{0}")]
+    #[error("The audio thread stopped taking commands.")]
     Send(#[from] crossbeam_channel::SendError<AudioEvent>),
 
-    #[error("{0}")]
+    #[error("The audio thread stopped responding.")]
     Recv(#[from] crossbeam_channel::RecvError),
 }
 
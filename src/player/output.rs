@@ -0,0 +1,369 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use arc_swap::{ArcSwap, ArcSwapOption};
+use atomic_float::{AtomicF32, AtomicF64};
+use crossbeam_channel::{Receiver, Sender, bounded, select, unbounded};
+use tokio::sync::broadcast;
+
+use super::AudioController;
+use super::audio_loop::AudioLoopState;
+use super::backend::{Backend, CpalBackend, OutputStream};
+use super::bus::Bus;
+use super::device::DeviceId;
+use super::dither::exact_bits;
+use super::error::*;
+use super::{PlayerProps, SharedAudioBuffer};
+use crate::config::AudioConfig;
+
+/// How often to look for the configured device while playing on another one.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+
+type Reply = Sender<Result<DeviceId, AudioError>>;
+
+enum OutputCommand {
+    Reopen(AudioConfig, Reply),
+    /// A track at this sample rate was loaded.
+    TrackRate(u32),
+}
+
+/// What happened to the output, for the app to tell the user.
+#[derive(Debug, Clone)]
+pub enum OutputEvent {
+    /// The stream on the device failed, usually because it was unplugged.
+    Lost(Option<DeviceId>, String),
+    /// Playing on the default device because the configured one is gone.
+    FellBack(DeviceId),
+    /// Back on the configured device.
+    Reconnected(DeviceId),
+    /// No device could be opened, retrying in the background.
+    Unavailable(String),
+}
+
+/// Handle to the thread that owns the output stream, which some platforms can't move
+/// between threads. The thread also reopens the stream when it fails.
+#[derive(Debug, Clone)]
+pub(super) struct Output {
+    commands: Sender<OutputCommand>,
+    active: Arc<ArcSwapOption<DeviceId>>,
+    events: broadcast::Sender<OutputEvent>,
+}
+
+/// Runs on the output thread.
+struct Supervisor<B> {
+    backend: B,
+    state: AudioLoopState,
+    settings: AudioConfig,
+    /// Of the loaded track, preferred over the configured rate in bit-perfect mode.
+    track_rate: Option<u32>,
+    stream: Option<Box<dyn OutputStream>>,
+    /// Bumped for every stream opened, so errors from closed ones are ignored.
+    generation: u64,
+    is_fallback: bool,
+    failures: Sender<(u64, String)>,
+    active: Arc<ArcSwapOption<DeviceId>>,
+    events: broadcast::Sender<OutputEvent>,
+}
+
+impl AudioController {
+    pub fn create(settings: &AudioConfig) -> Result<Self, AudioError> {
+        Self::with_backend(settings, CpalBackend)
+    }
+
+    pub(super) fn with_backend(
+        settings: &AudioConfig,
+        backend: impl Backend,
+    ) -> Result<Self, AudioError> {
+        let shared_audio = Arc::new(ArcSwap::from_pointee(SharedAudioBuffer::default()));
+        let bus = Arc::new(Bus::default());
+        let (tx, rx) = bounded(128);
+        let rx = Arc::new(rx);
+
+        let props = Arc::new(PlayerProps {
+            volume: AtomicF32::new(settings.volume),
+            playback_speed: AtomicF64::new(settings.speed),
+            resampler_window: AtomicIsize::new(settings.resampler.window_size()),
+            ..Default::default()
+        });
+
+        let state = AudioLoopState {
+            rx: Arc::clone(&rx),
+            bus: Arc::clone(&bus),
+            shared: Arc::clone(&shared_audio),
+            props: Arc::clone(&props),
+        };
+
+        Ok(AudioController {
+            _bus: bus,
+            event_sender: tx,
+            shared_audio,
+            props,
+            output: Output::spawn(backend, state, settings.clone())?,
+        })
+    }
+
+    /// Moves playback to the device `settings` pick, keeping the position and whether it's
+    /// playing. Stays on the current device when the new one can't be opened.
+    pub fn switch_output(&self, settings: &AudioConfig) -> Result<DeviceId, AudioError> {
+        self.output.reopen(settings)
+    }
+
+    /// With `audio.bit_perfect`, reopens the output at the loaded track's sample rate when
+    /// the device supports it, without waiting for the reopen.
+    pub fn match_track_rate(&self) {
+        let rate = self.shared_audio.load().sample_rate;
+        self.output
+            .commands
+            .send(OutputCommand::TrackRate(rate))
+            .ok();
+    }
+
+    pub fn output_device(&self) -> Option<DeviceId> {
+        self.output.active.load_full().map(|id| (*id).clone())
+    }
+
+    pub fn output_events(&self) -> broadcast::Receiver<OutputEvent> {
+        self.output.events.subscribe()
+    }
+}
+
+impl Output {
+    fn spawn(
+        backend: impl Backend,
+        state: AudioLoopState,
+        settings: AudioConfig,
+    ) -> Result<Self, AudioError> {
+        let (commands, commands_rx) = unbounded();
+        let (ready_tx, ready_rx) = bounded(1);
+        let (events, _) = broadcast::channel(16);
+        let active = Arc::new(ArcSwapOption::empty());
+
+        let output = Self {
+            commands,
+            active: Arc::clone(&active),
+            events: events.clone(),
+        };
+
+        thread::Builder::new()
+            .name("audio-output".to_string())
+            .spawn(move || {
+                let (failures, failures_rx) = unbounded();
+                let mut supervisor = Supervisor {
+                    backend,
+                    state,
+                    settings,
+                    track_rate: None,
+                    stream: None,
+                    generation: 0,
+                    is_fallback: false,
+                    failures,
+                    active,
+                    events,
+                };
+
+                let opened = supervisor.open_configured();
+                let failed = opened.is_err();
+                ready_tx.send(opened).ok();
+
+                if !failed {
+                    supervisor.run(commands_rx, failures_rx);
+                }
+            })
+            .map_err(StreamError::ThreadFailed)?;
+
+        ready_rx.recv().map_err(|_| StreamError::ThreadStopped)??;
+
+        Ok(output)
+    }
+
+    fn reopen(&self, settings: &AudioConfig) -> Result<DeviceId, AudioError> {
+        let (reply, response) = bounded(1);
+
+        self.commands
+            .send(OutputCommand::Reopen(settings.clone(), reply))
+            .map_err(|_| StreamError::ThreadStopped)?;
+
+        response.recv().map_err(|_| StreamError::ThreadStopped)?
+    }
+}
+
+impl<B: Backend> Supervisor<B> {
+    /// Runs until the controller, and with it the command sender, is dropped.
+    fn run(&mut self, commands: Receiver<OutputCommand>, failures: Receiver<(u64, String)>) {
+        loop {
+            select! {
+                recv(commands) -> command => match command {
+                    Ok(OutputCommand::Reopen(settings, reply)) => {
+                        reply.send(self.switch(settings)).ok();
+                    }
+                    Ok(OutputCommand::TrackRate(rate)) => self.follow_track_rate(rate),
+                    Err(_) => return,
+                },
+                recv(failures) -> failure => {
+                    if let Ok((generation, err)) = failure
+                        && generation == self.generation
+                    {
+                        self.on_failure(err);
+                    }
+                },
+                default(RECONNECT_INTERVAL) => self.retry(),
+            }
+        }
+    }
+
+    fn switch(&mut self, settings: AudioConfig) -> Result<DeviceId, AudioError> {
+        let previous = std::mem::replace(&mut self.settings, settings);
+
+        match self.open(&self.effective_settings()) {
+            Ok(id) => Ok(id),
+            Err(err) => {
+                self.settings = previous;
+                self.recover();
+                Err(err)
+            }
+        }
+    }
+
+    /// The configured settings, at the loaded track's rate in bit-perfect mode.
+    fn effective_settings(&self) -> AudioConfig {
+        match self.track_rate {
+            Some(rate) if self.settings.bit_perfect => AudioConfig {
+                sample_rate: Some(rate),
+                ..self.settings.clone()
+            },
+            _ => self.settings.clone(),
+        }
+    }
+
+    /// Reopens the stream when the new track would play at a different rate.
+    fn follow_track_rate(&mut self, rate: u32) {
+        self.track_rate = Some(rate);
+
+        if !self.settings.bit_perfect || self.stream.is_none() {
+            return;
+        }
+
+        let current = self.state.props.sample_rate.load(Ordering::Relaxed);
+        let wanted = self
+            .backend
+            .probe(&self.effective_settings())
+            .map(|spec| spec.sample_rate);
+
+        if wanted.is_ok_and(|wanted| wanted != current) {
+            log::info!("Reopening the output at {rate} Hz");
+            self.recover();
+        }
+    }
+
+    fn on_failure(&mut self, err: String) {
+        log::warn!("Audio output failed: {err}");
+
+        let device = self.active.load_full().map(|id| (*id).clone());
+        self.events.send(OutputEvent::Lost(device, err)).ok();
+
+        self.recover();
+    }
+
+    /// Goes back to the configured device once it's available again, or keeps trying to
+    /// open any device after everything failed.
+    fn retry(&mut self) {
+        let missing = self.stream.is_none();
+        let reappeared = self.is_fallback
+            && self
+                .backend
+                .probe(&self.settings)
+                .is_ok_and(|spec| !spec.is_fallback);
+
+        if missing || reappeared {
+            self.recover();
+        }
+    }
+
+    /// Reopens the configured device, or the default one, and tells the app how it went.
+    fn recover(&mut self) {
+        let was_fallback = self.is_fallback;
+        let was_missing = self.stream.is_none();
+
+        match self.open_configured() {
+            Ok(id) if self.is_fallback => {
+                if !was_fallback || was_missing {
+                    log::warn!("Configured output device not available, playing on {id}");
+                    self.events.send(OutputEvent::FellBack(id)).ok();
+                }
+            }
+            Ok(id) => {
+                if was_fallback || was_missing {
+                    log::info!("Audio output back on {id}");
+                    self.events.send(OutputEvent::Reconnected(id)).ok();
+                }
+            }
+            Err(err) => {
+                if !was_missing {
+                    self.events
+                        .send(OutputEvent::Unavailable(err.to_string()))
+                        .ok();
+                }
+            }
+        }
+    }
+
+    /// Opens the configured device, or the default one when that fails.
+    fn open_configured(&mut self) -> Result<DeviceId, AudioError> {
+        let settings = self.effective_settings();
+
+        self.open(&settings).or_else(|err| {
+            let default = AudioConfig {
+                host: None,
+                device: None,
+                ..settings
+            };
+
+            let id = self.open(&default).map_err(|_| err)?;
+            self.is_fallback = true;
+            Ok(id)
+        })
+    }
+
+    fn open(&mut self, settings: &AudioConfig) -> Result<DeviceId, AudioError> {
+        // Two streams on the same state would both advance the position.
+        drop(self.stream.take());
+        self.active.store(None);
+        self.generation += 1;
+
+        let generation = self.generation;
+        let failures = self.failures.clone();
+
+        let (spec, mut stream) = self.backend.open(
+            settings,
+            self.state.clone(),
+            Box::new(move |err| {
+                failures.send((generation, err)).ok();
+            }),
+        )?;
+
+        let props = &self.state.props;
+        props.sample_rate.store(spec.sample_rate, Ordering::SeqCst);
+        props
+            .output_bits
+            .store(exact_bits(spec.sample_format), Ordering::SeqCst);
+        props
+            .output_channels
+            .store(spec.channels as u32, Ordering::SeqCst);
+        props.callback_frames.store(0, Ordering::SeqCst);
+
+        stream.play()?;
+
+        match spec.buffer_frames {
+            Some(frames) => log::info!("Playing on {} with {frames} frame buffers", spec.id),
+            None => log::info!("Playing on {}", spec.id),
+        }
+
+        self.stream = Some(stream);
+        self.is_fallback = spec.is_fallback;
+        self.active.store(Some(Arc::new(spec.id.clone())));
+
+        Ok(spec.id)
+    }
+}
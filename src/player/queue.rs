@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayQueue {
+    tracks: Vec<PathBuf>,
+    current: Option<usize>,
+    #[serde(default)]
+    loop_mode: LoopMode,
+    #[serde(default)]
+    shuffle: bool,
+}
+
+/// What happens when a track ends on its own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoopMode {
+    /// Playback stops after the last track.
+    #[default]
+    None,
+    Track,
+    /// The queue starts over after the last track.
+    Playlist,
+}
+
+impl LoopMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            LoopMode::None => "Repeat off",
+            LoopMode::Track => "Repeat track",
+            LoopMode::Playlist => "Repeat all",
+        }
+    }
+
+    /// The mode after this one when cycling through them.
+    pub fn cycle(self) -> Self {
+        match self {
+            LoopMode::None => LoopMode::Playlist,
+            LoopMode::Playlist => LoopMode::Track,
+            LoopMode::Track => LoopMode::None,
+        }
+    }
+}
+
+impl PlayQueue {
+    pub fn current(&self) -> Option<&Path> {
+        self.current
+            .and_then(|idx| self.tracks.get(idx))
+            .map(PathBuf::as_path)
+    }
+
+    pub fn tracks(&self) -> &[PathBuf] {
+        &self.tracks
+    }
+
+    pub fn loop_mode(&self) -> LoopMode {
+        self.loop_mode
+    }
+
+    pub fn set_loop_mode(&mut self, mode: LoopMode) {
+        self.loop_mode = mode;
+    }
+
+    pub fn shuffle(&self) -> bool {
+        self.shuffle
+    }
+
+    /// Shuffles the tracks after the current one. Turning it off keeps them in their new order.
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        if shuffle && !self.shuffle {
+            let start = self.upcoming_start();
+            fastrand::shuffle(&mut self.tracks[start..]);
+        }
+
+        self.shuffle = shuffle;
+    }
+
+    pub fn replace(&mut self, tracks: Vec<PathBuf>) {
+        self.tracks = tracks;
+        self.current = None;
+
+        if self.shuffle {
+            fastrand::shuffle(&mut self.tracks);
+        }
+    }
+
+    pub fn enqueue(&mut self, path: PathBuf) {
+        match self.shuffle {
+            true => {
+                let idx = fastrand::usize(self.upcoming_start()..=self.tracks.len());
+                self.tracks.insert(idx, path);
+            }
+            false => self.tracks.push(path),
+        }
+    }
+
+    /// Inserts `path` right after the current track and makes it current.
+    pub fn play_now(&mut self, path: PathBuf) {
+        let idx = self.current.map(|i| i + 1).unwrap_or(self.tracks.len());
+
+        self.tracks.insert(idx, path);
+        self.current = Some(idx);
+    }
+
+    /// Makes the track at `idx` current.
+    pub fn jump(&mut self, idx: usize) -> Option<&Path> {
+        if idx >= self.tracks.len() {
+            return None;
+        }
+
+        self.current = Some(idx);
+        self.current()
+    }
+
+    /// Removes the track at `idx`. Removing the current one lets it finish, then moves on to
+    /// the track that followed it.
+    pub fn remove(&mut self, idx: usize) -> Option<PathBuf> {
+        if idx >= self.tracks.len() {
+            return None;
+        }
+
+        self.current = match self.current {
+            Some(current) if current >= idx => current.checked_sub(1),
+            current => current,
+        };
+
+        Some(self.tracks.remove(idx))
+    }
+
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+        self.current = None;
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.upcoming_start() < self.tracks.len()
+            || (self.loop_mode == LoopMode::Playlist && !self.tracks.is_empty())
+    }
+
+    pub fn has_previous(&self) -> bool {
+        self.current.is_some_and(|idx| idx > 0)
+            || (self.loop_mode == LoopMode::Playlist && self.current.is_some())
+    }
+
+    pub fn next(&mut self) -> Option<&Path> {
+        let mut idx = self.upcoming_start();
+
+        if idx >= self.tracks.len() {
+            if self.loop_mode != LoopMode::Playlist || self.tracks.is_empty() {
+                return None;
+            }
+
+            if self.shuffle {
+                fastrand::shuffle(&mut self.tracks);
+            }
+
+            idx = 0;
+        }
+
+        self.current = Some(idx);
+        self.current()
+    }
+
+    pub fn previous(&mut self) -> Option<&Path> {
+        let idx = match self.current?.checked_sub(1) {
+            Some(idx) => idx,
+            None if self.loop_mode == LoopMode::Playlist => self.tracks.len().checked_sub(1)?,
+            None => return None,
+        };
+
+        self.current = Some(idx);
+        self.current()
+    }
+
+    /// Index of the first track that hasn't been played yet.
+    fn upcoming_start(&self) -> usize {
+        self.current.map(|i| i + 1).unwrap_or(0)
+    }
+}
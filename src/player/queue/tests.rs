@@ -0,0 +1,222 @@
+use std::path::{Path, PathBuf};
+
+use super::*;
+
+fn paths(names: &[&str]) -> Vec<PathBuf> {
+    names.iter().map(PathBuf::from).collect()
+}
+
+fn queue(names: &[&str]) -> PlayQueue {
+    let mut queue = PlayQueue::default();
+    queue.replace(paths(names));
+    queue
+}
+
+fn next(queue: &mut PlayQueue) -> Option<PathBuf> {
+    queue.next().map(Path::to_path_buf)
+}
+
+fn previous(queue: &mut PlayQueue) -> Option<PathBuf> {
+    queue.previous().map(Path::to_path_buf)
+}
+
+#[test]
+fn next_plays_the_tracks_in_order() {
+    let mut queue = queue(&["a", "b", "c"]);
+
+    assert_eq!(queue.current(), None);
+    assert_eq!(next(&mut queue), Some("a".into()));
+    assert_eq!(next(&mut queue), Some("b".into()));
+    assert_eq!(next(&mut queue), Some("c".into()));
+    assert_eq!(queue.current(), Some(Path::new("c")));
+}
+
+#[test]
+fn the_end_stops_without_repeat() {
+    let mut queue = queue(&["a", "b"]);
+    queue.jump(1);
+
+    assert!(!queue.has_next());
+    assert_eq!(next(&mut queue), None);
+    // The last track stays current rather than starting over.
+    assert_eq!(queue.current(), Some(Path::new("b")));
+}
+
+#[test]
+fn the_end_wraps_when_repeating_all() {
+    let mut queue = queue(&["a", "b"]);
+    queue.set_loop_mode(LoopMode::Playlist);
+    queue.jump(1);
+
+    assert!(queue.has_next());
+    assert_eq!(next(&mut queue), Some("a".into()));
+}
+
+#[test]
+fn repeating_a_track_still_skips_on_request() {
+    let mut queue = queue(&["a", "b"]);
+    queue.set_loop_mode(LoopMode::Track);
+    queue.jump(0);
+
+    // Repeating the track when it ends is up to the player, next moves on.
+    assert_eq!(next(&mut queue), Some("b".into()));
+    assert_eq!(next(&mut queue), None);
+}
+
+#[test]
+fn previous_goes_back() {
+    let mut queue = queue(&["a", "b", "c"]);
+    queue.jump(2);
+
+    assert!(queue.has_previous());
+    assert_eq!(previous(&mut queue), Some("b".into()));
+    assert_eq!(previous(&mut queue), Some("a".into()));
+    assert!(!queue.has_previous());
+    assert_eq!(previous(&mut queue), None);
+    assert_eq!(queue.current(), Some(Path::new("a")));
+}
+
+#[test]
+fn previous_wraps_when_repeating_all() {
+    let mut queue = queue(&["a", "b", "c"]);
+    queue.set_loop_mode(LoopMode::Playlist);
+    queue.jump(0);
+
+    assert!(queue.has_previous());
+    assert_eq!(previous(&mut queue), Some("c".into()));
+}
+
+#[test]
+fn previous_needs_a_current_track() {
+    let mut queue = queue(&["a", "b"]);
+    queue.set_loop_mode(LoopMode::Playlist);
+
+    assert!(!queue.has_previous());
+    assert_eq!(previous(&mut queue), None);
+}
+
+#[test]
+fn empty_queue_has_nothing_to_play() {
+    let mut queue = PlayQueue::default();
+    queue.set_loop_mode(LoopMode::Playlist);
+
+    assert!(!queue.has_next());
+    assert_eq!(next(&mut queue), None);
+}
+
+#[test]
+fn loop_modes_cycle() {
+    let mut mode = LoopMode::None;
+    let mut seen = Vec::new();
+
+    for _ in 0..3 {
+        mode = mode.cycle();
+        seen.push(mode);
+    }
+
+    assert_eq!(seen, [LoopMode::Playlist, LoopMode::Track, LoopMode::None]);
+}
+
+#[test]
+fn removing_an_earlier_track_keeps_the_current_one() {
+    let mut queue = queue(&["a", "b", "c"]);
+    queue.jump(1);
+
+    assert_eq!(queue.remove(0), Some("a".into()));
+    assert_eq!(queue.current(), Some(Path::new("b")));
+    assert_eq!(next(&mut queue), Some("c".into()));
+}
+
+#[test]
+fn removing_the_current_track_moves_on_to_the_following_one() {
+    let mut queue = queue(&["a", "b", "c"]);
+    queue.jump(1);
+
+    assert_eq!(queue.remove(1), Some("b".into()));
+    assert_eq!(next(&mut queue), Some("c".into()));
+}
+
+#[test]
+fn removing_the_first_track_while_it_plays() {
+    let mut queue = queue(&["a", "b"]);
+    queue.jump(0);
+
+    queue.remove(0);
+    assert_eq!(next(&mut queue), Some("b".into()));
+}
+
+#[test]
+fn removing_a_later_track_skips_it() {
+    let mut queue = queue(&["a", "b", "c"]);
+    queue.jump(0);
+
+    queue.remove(1);
+    assert_eq!(next(&mut queue), Some("c".into()));
+    assert_eq!(queue.remove(5), None);
+}
+
+#[test]
+fn play_now_goes_after_the_current_track() {
+    let mut queue = queue(&["a", "b"]);
+    queue.jump(0);
+
+    queue.play_now("x".into());
+
+    assert_eq!(queue.current(), Some(Path::new("x")));
+    assert_eq!(queue.tracks(), paths(&["a", "x", "b"]));
+    assert_eq!(next(&mut queue), Some("b".into()));
+}
+
+#[test]
+fn shuffle_keeps_the_played_tracks_in_place() {
+    let names: Vec<String> = (0..50).map(|n| n.to_string()).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let mut queue = queue(&names);
+    queue.jump(9);
+
+    queue.set_shuffle(true);
+
+    assert!(queue.shuffle());
+    assert_eq!(queue.tracks()[..10], paths(&names[..10]));
+    assert_eq!(queue.current(), Some(Path::new("9")));
+
+    let mut upcoming = queue.tracks()[10..].to_vec();
+    upcoming.sort();
+    let mut expected = paths(&names[10..]);
+    expected.sort();
+    assert_eq!(upcoming, expected);
+}
+
+#[test]
+fn shuffled_enqueue_goes_among_the_upcoming_tracks() {
+    let mut queue = queue(&["a", "b", "c"]);
+    queue.jump(1);
+    queue.set_shuffle(true);
+
+    for _ in 0..20 {
+        queue.enqueue("x".into());
+    }
+
+    assert_eq!(queue.tracks()[..2], paths(&["a", "b"]));
+    assert_eq!(queue.tracks().len(), 23);
+}
+
+#[test]
+fn enqueue_appends_without_shuffle() {
+    let mut queue = queue(&["a"]);
+
+    queue.enqueue("b".into());
+
+    assert_eq!(queue.tracks(), paths(&["a", "b"]));
+}
+
+#[test]
+fn clear_forgets_the_current_track() {
+    let mut queue = queue(&["a", "b"]);
+    queue.jump(1);
+
+    queue.clear();
+
+    assert_eq!(queue.current(), None);
+    assert!(queue.tracks().is_empty());
+}
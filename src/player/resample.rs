@@ -1,4 +1,25 @@
-pub const WINDOW_SIZE: isize = 24;
+use serde::{Deserialize, Serialize};
+
+/// Trades CPU time for fewer artifacts when resampling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResamplerQuality {
+    Low,
+    Medium,
+    #[default]
+    High,
+}
+
+impl ResamplerQuality {
+    /// Samples taken into account on each side of the interpolated one.
+    pub fn window_size(self) -> isize {
+        match self {
+            ResamplerQuality::Low => 8,
+            ResamplerQuality::Medium => 16,
+            ResamplerQuality::High => 24,
+        }
+    }
+}
 
 fn sinc(x: f32) -> f32 {
     if x.abs() < 1e-8 {
@@ -13,7 +34,7 @@ fn hann_window(x: f32, half_width: f32) -> f32 {
     0.5 * (1.0 + (std::f32::consts::PI * x / half_width).cos())
 }
 
-pub fn interpolate(samples: &[f32], pos: f64) -> f32 {
+pub fn interpolate(samples: &[f32], pos: f64, window_size: isize) -> f32 {
     let len = samples.len() as isize;
     let idx = pos.floor() as isize;
     let frac = (pos - idx as f64) as f32;
@@ -21,10 +42,10 @@ pub fn interpolate(samples: &[f32], pos: f64) -> f32 {
     let mut acc = 0.0;
     let mut norm = 0.0;
 
-    for i in -WINDOW_SIZE..=WINDOW_SIZE {
+    for i in -window_size..=window_size {
         let sample_idx = (idx + i).clamp(0, len - 1) as usize;
         let x = i as f32 - frac;
-        let weight = sinc(x) * hann_window(x, WINDOW_SIZE as f32);
+        let weight = sinc(x) * hann_window(x, window_size as f32);
         acc += samples[sample_idx] * weight;
         norm += weight;
     }
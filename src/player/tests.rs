@@ -0,0 +1,233 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::backend::{NullBackend, VirtualClock, WavBackend};
+use super::event::{AtomicEvent, AudioEvent};
+use super::{AudioController, OutputEvent, SharedAudioBuffer};
+use crate::config::AudioConfig;
+use crate::test_util::TempDir;
+
+const RATE: u32 = 48_000;
+
+fn settings() -> AudioConfig {
+    AudioConfig {
+        volume: 1.0,
+        speed: 1.0,
+        ..Default::default()
+    }
+}
+
+fn null_player(settings: &AudioConfig) -> (AudioController, VirtualClock) {
+    let (backend, clock) = NullBackend::new(RATE, 2);
+    let player = AudioController::with_backend(settings, backend).unwrap();
+
+    (player, clock)
+}
+
+/// A 440 Hz tone at half scale, on the 16-bit grid like a decoded CD.
+fn tone(seconds: f64, sample_rate: u32, channels: usize) -> SharedAudioBuffer {
+    let samples: Vec<f32> = (0..(seconds * sample_rate as f64) as usize)
+        .map(|n| {
+            let t = n as f32 / sample_rate as f32;
+            let sample = 0.5 * (std::f32::consts::TAU * 440.0 * t).sin();
+            (sample * 32768.0).round() / 32768.0
+        })
+        .collect();
+
+    SharedAudioBuffer {
+        sample_rate,
+        channels: Arc::new(vec![samples; channels]),
+        layout: Vec::new(),
+        bits_per_sample: Some(16),
+    }
+}
+
+fn load(player: &AudioController, buffer: SharedAudioBuffer) {
+    player.shared_audio.store(Arc::new(buffer));
+}
+
+/// Waits for the output thread to get somewhere, failing the test after a while.
+fn wait_for(mut done: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+
+    while !done() {
+        assert!(Instant::now() < deadline, "timed out");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn position_follows_the_clock() {
+    let (player, clock) = null_player(&settings());
+    load(&player, tone(1.0, RATE, 2));
+
+    clock.advance(4800);
+    assert_eq!(player.get_song_position(), 0.0);
+
+    player.send_event(AtomicEvent::Play);
+    clock.advance(4800);
+
+    assert_eq!(player.get_song_position(), 4800.0);
+    assert_eq!(clock.rendered(), 9600);
+}
+
+#[test]
+fn pause_keeps_the_position() {
+    let (player, clock) = null_player(&settings());
+    load(&player, tone(1.0, RATE, 2));
+
+    player.send_event(AtomicEvent::Play);
+    clock.advance(1000);
+    player.send_event(AtomicEvent::Pause);
+    clock.advance(1000);
+
+    assert_eq!(player.get_song_position(), 1000.0);
+    assert!(clock.last_output().iter().all(|&s| s == 0.0));
+}
+
+#[test]
+fn stop_rewinds() {
+    let (player, clock) = null_player(&settings());
+    load(&player, tone(1.0, RATE, 2));
+
+    player.send_event(AtomicEvent::Play);
+    clock.advance(4800);
+    player.send_event(AudioEvent::Stop);
+    clock.advance(512);
+
+    assert_eq!(player.get_song_position(), 0.0);
+    assert!(!player.get_is_playing());
+}
+
+#[test]
+fn seeking_moves_the_position() {
+    let (player, clock) = null_player(&settings());
+    load(&player, tone(2.0, RATE, 2));
+
+    player.set_position_seconds(1.5);
+    assert_eq!(player.get_position_seconds(), 1.5);
+
+    player.send_event(AtomicEvent::Play);
+    clock.advance(480);
+
+    assert_eq!(player.get_song_position(), 1.5 * RATE as f64 + 480.0);
+}
+
+#[test]
+fn speed_scales_the_position() {
+    let (player, clock) = null_player(&settings());
+    load(&player, tone(1.0, RATE, 2));
+
+    player.send_event(AtomicEvent::SetSpeed(0.5));
+    player.send_event(AtomicEvent::Play);
+    clock.advance(4800);
+
+    assert_eq!(player.get_song_position(), 2400.0);
+}
+
+#[test]
+fn track_loops_at_the_end() {
+    let (player, clock) = null_player(&settings());
+    load(&player, tone(0.1, RATE, 2));
+
+    player.send_event(AtomicEvent::Play);
+    clock.advance(6000);
+
+    assert_eq!(player.get_song_position(), 1200.0);
+}
+
+#[test]
+fn mono_plays_on_both_channels() {
+    let (player, clock) = null_player(&settings());
+    load(&player, tone(1.0, RATE, 1));
+
+    player.send_event(AtomicEvent::Play);
+    clock.advance(512);
+
+    let output = clock.last_output();
+    assert!(output.iter().any(|&s| s != 0.0));
+    assert!(output.chunks(2).all(|frame| frame[0] == frame[1]));
+}
+
+#[test]
+fn wav_output_is_bit_exact() {
+    let dir = TempDir::new("bit-exact");
+    let path = dir.join("bit-exact.wav");
+    let (backend, clock) = WavBackend::create(&path, RATE, 2).unwrap();
+    let player = AudioController::with_backend(&settings(), backend).unwrap();
+
+    let buffer = tone(0.5, RATE, 2);
+    load(&player, buffer.clone());
+    player.send_event(AtomicEvent::Play);
+    clock.advance(RATE as usize / 4);
+
+    assert!(player.is_bit_perfect());
+    drop(player);
+
+    let bytes = std::fs::read(&path).unwrap();
+
+    let written: Vec<i16> = bytes[44..]
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    let expected: Vec<i16> = buffer.channels[0][..RATE as usize / 4]
+        .iter()
+        .flat_map(|&s| [(s * 32768.0) as i16; 2])
+        .collect();
+
+    assert_eq!(written, expected);
+}
+
+#[test]
+fn bit_perfect_opens_at_the_track_rate() {
+    let (backend, _clock) = NullBackend::new(RATE, 2);
+    let backend = backend.with_rates(&[44_100, 96_000]);
+    let settings = AudioConfig {
+        bit_perfect: true,
+        ..settings()
+    };
+    let player = AudioController::with_backend(&settings, backend).unwrap();
+    assert_eq!(player.sample_rate(), RATE);
+
+    load(&player, tone(0.1, 96_000, 2));
+    player.match_track_rate();
+    wait_for(|| player.sample_rate() == 96_000);
+    assert!(player.is_bit_perfect());
+
+    // Not supported by the device, back at its own rate.
+    load(&player, tone(0.1, 88_200, 2));
+    player.match_track_rate();
+    wait_for(|| player.sample_rate() == RATE);
+    assert!(!player.is_bit_perfect());
+}
+
+#[test]
+fn recovers_when_the_device_comes_back() {
+    let (player, clock) = null_player(&settings());
+    let mut events = player.output_events();
+    load(&player, tone(10.0, RATE, 2));
+
+    player.send_event(AtomicEvent::Play);
+    clock.advance(4800);
+
+    clock.unplug();
+    wait_for(|| matches!(events.try_recv(), Ok(OutputEvent::Unavailable(_))));
+    assert!(player.output_device().is_none());
+
+    clock.plug_in();
+    wait_for(|| matches!(events.try_recv(), Ok(OutputEvent::Reconnected(_))));
+    assert!(clock.is_playing());
+
+    clock.advance(4800);
+    assert_eq!(player.get_song_position(), 9600.0);
+}
+
+#[test]
+fn create_fails_with_the_reason() {
+    let (backend, clock) = NullBackend::new(RATE, 2);
+    clock.unplug();
+
+    let err = AudioController::with_backend(&settings(), backend).unwrap_err();
+    assert_eq!(err.to_string(), "No output device found on Null.");
+}
@@ -0,0 +1,7 @@
+mod error;
+mod file;
+mod smart;
+
+pub use error::*;
+pub use file::*;
+pub use smart::*;
@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PlaylistError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    Xml(#[from] quick_xml::Error),
+
+    #[error("Invalid rule: {0}.")]
+    InvalidRule(String),
+
+    #[error("A smart playlist needs a name.")]
+    MissingName,
+
+    #[error("The name \"{0}\" is too close to another smart playlist's.")]
+    NameTaken(String),
+
+    #[error("Unsupported playlist format: {}.", .0.display())]
+    UnsupportedFormat(PathBuf),
+}
@@ -0,0 +1,222 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use url::Url;
+
+use super::PlaylistError;
+use crate::library::Track;
+
+mod m3u;
+mod pls;
+mod xspf;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    M3u,
+    M3u8,
+    Pls,
+    Xspf,
+}
+
+/// A track in a playlist file that points at a local file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlaylistEntry {
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// Seconds.
+    pub duration: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistFile {
+    pub entries: Vec<PlaylistEntry>,
+    /// Locations that don't point at an existing local file, as written in the playlist.
+    pub unresolved: Vec<String>,
+}
+
+/// An entry as written in the file, before its location is resolved.
+#[derive(Debug, Default)]
+struct RawEntry {
+    location: String,
+    title: Option<String>,
+    artist: Option<String>,
+    duration: Option<f64>,
+}
+
+impl PlaylistFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match ext.as_str() {
+            "m3u" => Some(Self::M3u),
+            "m3u8" => Some(Self::M3u8),
+            "pls" => Some(Self::Pls),
+            "xspf" => Some(Self::Xspf),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Track> for PlaylistEntry {
+    fn from(track: &Track) -> Self {
+        Self {
+            path: track.path.clone(),
+            title: track.title.clone(),
+            artist: track.artist.clone(),
+            duration: Some(track.duration).filter(|d| *d > 0.0),
+        }
+    }
+}
+
+impl From<PathBuf> for PlaylistEntry {
+    fn from(path: PathBuf) -> Self {
+        Self {
+            path,
+            ..Default::default()
+        }
+    }
+}
+
+impl PlaylistEntry {
+    /// `artist - title`, the way M3U and PLS players expect it.
+    fn display_name(&self) -> Option<String> {
+        match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) => Some(format!("{artist} - {title}")),
+            (None, Some(title)) => Some(title.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl PlaylistFile {
+    pub fn load(path: &Path) -> Result<Self, PlaylistError> {
+        let format = PlaylistFormat::from_path(path)
+            .ok_or_else(|| PlaylistError::UnsupportedFormat(path.to_path_buf()))?;
+        let content = decode(fs::read(path)?);
+        let base = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+
+        let raw = match format {
+            PlaylistFormat::M3u | PlaylistFormat::M3u8 => m3u::parse(&content),
+            PlaylistFormat::Pls => pls::parse(&content),
+            PlaylistFormat::Xspf => xspf::parse(&content)?,
+        };
+
+        let mut playlist = Self::default();
+
+        for entry in raw {
+            match resolve(&entry.location, base, format) {
+                Some(path) => playlist.entries.push(PlaylistEntry {
+                    path,
+                    title: entry.title,
+                    artist: entry.artist,
+                    duration: entry.duration,
+                }),
+                None => playlist.unresolved.push(entry.location),
+            }
+        }
+
+        Ok(playlist)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), PlaylistError> {
+        let format = PlaylistFormat::from_path(path)
+            .ok_or_else(|| PlaylistError::UnsupportedFormat(path.to_path_buf()))?;
+        let base = path.parent().unwrap_or(Path::new(""));
+
+        let raw: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| RawEntry {
+                location: location(&entry.path, base, format),
+                title: match format {
+                    PlaylistFormat::Xspf => entry.title.clone(),
+                    _ => entry.display_name(),
+                },
+                artist: entry.artist.clone(),
+                duration: entry.duration,
+            })
+            .collect();
+
+        let content = match format {
+            PlaylistFormat::M3u | PlaylistFormat::M3u8 => m3u::write(&raw),
+            PlaylistFormat::Pls => pls::write(&raw),
+            PlaylistFormat::Xspf => xspf::write(&raw),
+        };
+
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.entries.iter().map(|e| e.path.clone()).collect()
+    }
+}
+
+/// Playlists from older players are often Latin-1 rather than UTF-8.
+fn decode(bytes: Vec<u8>) -> String {
+    let content = String::from_utf8(bytes)
+        .unwrap_or_else(|err| err.into_bytes().iter().map(|&b| b as char).collect());
+
+    match content.strip_prefix('\u{feff}') {
+        Some(content) => content.to_string(),
+        None => content,
+    }
+}
+
+fn resolve(location: &str, base: &Path, format: PlaylistFormat) -> Option<PathBuf> {
+    let path = match format {
+        // XSPF locations are URIs, relative ones are relative to the playlist.
+        PlaylistFormat::Xspf => {
+            let base = Url::from_directory_path(fs::canonicalize(base).ok()?).ok()?;
+            base.join(location).ok()?.to_file_path().ok()?
+        }
+        _ => match Url::parse(location) {
+            Ok(url) if url.scheme() == "file" => url.to_file_path().ok()?,
+            // Single letter schemes are Windows drive letters.
+            Ok(url) if url.scheme().len() > 1 => return None,
+            _ => base.join(location),
+        },
+    };
+
+    if path.is_file() {
+        return Some(path);
+    }
+
+    // Playlists written on Windows use backslashes in relative paths.
+    if cfg!(not(windows)) && location.contains('\\') && !location.contains("://") {
+        let path = base.join(location.replace('\\', "/"));
+        return path.is_file().then_some(path);
+    }
+
+    None
+}
+
+/// Paths under the playlist's folder are stored relative to it so the folder can be moved.
+fn location(path: &Path, base: &Path, format: PlaylistFormat) -> String {
+    let relative = path.strip_prefix(base).ok().filter(|_| base.is_absolute());
+
+    match format {
+        PlaylistFormat::Xspf => match relative {
+            Some(relative) => relative
+                .iter()
+                .map(|part| {
+                    url::form_urlencoded::byte_serialize(part.as_encoded_bytes())
+                        .collect::<String>()
+                        .replace('+', "%20")
+                })
+                .collect::<Vec<_>>()
+                .join("/"),
+            None => Url::from_file_path(path)
+                .map(String::from)
+                .unwrap_or_else(|_| path.to_string_lossy().into_owned()),
+        },
+        _ => relative.unwrap_or(path).to_string_lossy().into_owned(),
+    }
+}
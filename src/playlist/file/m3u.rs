@@ -0,0 +1,58 @@
+use std::fmt::Write;
+
+use super::RawEntry;
+
+pub(super) fn parse(content: &str) -> Vec<RawEntry> {
+    let mut entries = Vec::new();
+    let mut info = None;
+
+    for line in content.lines().map(str::trim) {
+        if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            let (duration, title) = extinf.split_once(',').unwrap_or((extinf, ""));
+            // Attributes such as `tvg-id="…"` may follow the duration.
+            let duration = duration
+                .split_whitespace()
+                .next()
+                .and_then(|d| d.parse().ok())
+                .filter(|d: &f64| *d >= 0.0);
+            let title = Some(title.trim())
+                .filter(|t| !t.is_empty())
+                .map(str::to_string);
+
+            info = Some((duration, title));
+            continue;
+        }
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (duration, title) = info.take().unwrap_or_default();
+
+        entries.push(RawEntry {
+            location: line.to_string(),
+            title,
+            artist: None,
+            duration,
+        });
+    }
+
+    entries
+}
+
+pub(super) fn write(entries: &[RawEntry]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+
+    for entry in entries {
+        if entry.title.is_some() || entry.duration.is_some() {
+            let duration = entry.duration.map(|d| d.round() as i64).unwrap_or(-1);
+            let title = entry.title.as_deref().unwrap_or_default();
+
+            writeln!(out, "#EXTINF:{duration},{title}").ok();
+        }
+
+        writeln!(out, "{}", entry.location).ok();
+    }
+
+    out
+}
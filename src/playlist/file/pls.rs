@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::RawEntry;
+
+pub(super) fn parse(content: &str) -> Vec<RawEntry> {
+    let mut entries: BTreeMap<u32, RawEntry> = BTreeMap::new();
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let (key, value) = (key.trim(), value.trim());
+        let Some(split) = key.find(|c: char| c.is_ascii_digit()) else {
+            continue;
+        };
+        let Ok(idx) = key[split..].parse() else {
+            continue;
+        };
+
+        let entry = entries.entry(idx).or_default();
+
+        match key[..split].to_ascii_lowercase().as_str() {
+            "file" => entry.location = value.to_string(),
+            "title" => entry.title = Some(value.to_string()).filter(|t| !t.is_empty()),
+            "length" => entry.duration = value.parse().ok().filter(|d: &f64| *d >= 0.0),
+            _ => {}
+        }
+    }
+
+    entries
+        .into_values()
+        .filter(|entry| !entry.location.is_empty())
+        .collect()
+}
+
+pub(super) fn write(entries: &[RawEntry]) -> String {
+    let mut out = String::from("[playlist]\n");
+
+    for (idx, entry) in entries.iter().enumerate() {
+        let n = idx + 1;
+        let duration = entry.duration.map(|d| d.round() as i64).unwrap_or(-1);
+
+        writeln!(out, "File{n}={}", entry.location).ok();
+
+        if let Some(title) = &entry.title {
+            writeln!(out, "Title{n}={title}").ok();
+        }
+
+        writeln!(out, "Length{n}={duration}").ok();
+    }
+
+    writeln!(out, "NumberOfEntries={}", entries.len()).ok();
+    writeln!(out, "Version=2").ok();
+
+    out
+}
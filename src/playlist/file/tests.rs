@@ -0,0 +1,185 @@
+use std::fs;
+use std::path::Path;
+
+use super::*;
+use crate::test_util::TempDir;
+
+/// A folder with a few empty tracks.
+fn with_tracks(name: &str, tracks: &[&str]) -> TempDir {
+    let folder = TempDir::new(name);
+
+    for track in tracks {
+        folder.touch(track);
+    }
+
+    folder
+}
+
+fn entries(folder: &TempDir) -> Vec<PlaylistEntry> {
+    vec![
+        PlaylistEntry {
+            path: folder.join("Artist/01 First.flac"),
+            title: Some("First & Best".to_string()),
+            artist: Some("Artist".to_string()),
+            duration: Some(215.0),
+        },
+        PlaylistEntry {
+            path: folder.join("second.mp3"),
+            title: None,
+            artist: None,
+            duration: None,
+        },
+    ]
+}
+
+fn round_trip(name: &str, playlist_name: &str) -> (Vec<PlaylistEntry>, PlaylistFile) {
+    let folder = with_tracks(name, &["Artist/01 First.flac", "second.mp3"]);
+    let path = folder.join(playlist_name);
+    let entries = entries(&folder);
+
+    let playlist = PlaylistFile {
+        entries: entries.clone(),
+        unresolved: Vec::new(),
+    };
+    playlist.save(&path).unwrap();
+
+    (entries, PlaylistFile::load(&path).unwrap())
+}
+
+#[test]
+fn m3u_round_trips() {
+    let (entries, loaded) = round_trip("m3u", "list.m3u8");
+
+    assert!(loaded.unresolved.is_empty());
+    assert_eq!(
+        loaded.paths(),
+        entries.iter().map(|e| e.path.clone()).collect::<Vec<_>>()
+    );
+    // M3U has no artist field, it's written into the title.
+    assert_eq!(
+        loaded.entries[0].title.as_deref(),
+        Some("Artist - First & Best")
+    );
+    assert_eq!(loaded.entries[0].duration, Some(215.0));
+    assert_eq!(loaded.entries[1].title, None);
+    assert_eq!(loaded.entries[1].duration, None);
+}
+
+#[test]
+fn pls_round_trips() {
+    let (entries, loaded) = round_trip("pls", "list.pls");
+
+    assert!(loaded.unresolved.is_empty());
+    assert_eq!(
+        loaded.paths(),
+        entries.iter().map(|e| e.path.clone()).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        loaded.entries[0].title.as_deref(),
+        Some("Artist - First & Best")
+    );
+    assert_eq!(loaded.entries[0].duration, Some(215.0));
+    // `Length=-1` stands for an unknown length.
+    assert_eq!(loaded.entries[1].duration, None);
+}
+
+#[test]
+fn xspf_round_trips() {
+    let (entries, loaded) = round_trip("xspf", "list.xspf");
+
+    assert!(loaded.unresolved.is_empty());
+    assert_eq!(loaded.entries, entries);
+}
+
+#[test]
+fn paths_under_the_playlist_folder_are_relative() {
+    let folder = with_tracks("relative", &["Artist/01 First.flac"]);
+    let playlist = PlaylistFile {
+        entries: vec![folder.join("Artist/01 First.flac").into()],
+        unresolved: Vec::new(),
+    };
+
+    for (name, expected) in [
+        ("list.m3u", "\nArtist/01 First.flac\n"),
+        ("list.pls", "File1=Artist/01 First.flac\n"),
+        ("list.xspf", "<location>Artist/01%20First.flac</location>"),
+    ] {
+        let path = folder.join(name);
+        playlist.save(&path).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains(expected), "{name}: {content}");
+        assert!(
+            !content.contains(folder.path().to_str().unwrap()),
+            "{name}: {content}"
+        );
+    }
+}
+
+#[test]
+fn paths_outside_the_playlist_folder_stay_absolute() {
+    let music = with_tracks("outside-music", &["song.flac"]);
+    let lists = with_tracks("outside-lists", &[]);
+    let song = music.join("song.flac");
+
+    let playlist = PlaylistFile {
+        entries: vec![song.clone().into()],
+        unresolved: Vec::new(),
+    };
+
+    for name in ["list.m3u", "list.pls", "list.xspf"] {
+        let path = lists.join(name);
+        playlist.save(&path).unwrap();
+
+        assert_eq!(
+            PlaylistFile::load(&path).unwrap().paths(),
+            std::slice::from_ref(&song),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn relative_locations_follow_the_playlist() {
+    let folder = with_tracks("follow", &["a/song.flac"]);
+    fs::write(folder.join("a/list.m3u"), "song.flac\n../a/song.flac\n").unwrap();
+
+    let loaded = PlaylistFile::load(&folder.join("a/list.m3u")).unwrap();
+
+    assert_eq!(loaded.entries.len(), 2);
+    assert!(loaded.paths().iter().all(|p| p.is_file()));
+}
+
+#[test]
+fn windows_separators_are_accepted() {
+    let folder = with_tracks("windows", &["Artist/song.flac"]);
+    fs::write(folder.join("list.m3u"), "Artist\\song.flac\r\n").unwrap();
+
+    let loaded = PlaylistFile::load(&folder.join("list.m3u")).unwrap();
+
+    assert_eq!(loaded.paths(), [folder.join("Artist/song.flac")]);
+}
+
+#[test]
+fn missing_and_remote_entries_are_unresolved() {
+    let folder = with_tracks("missing", &["song.flac"]);
+    fs::write(
+        folder.join("list.m3u"),
+        "#EXTM3U\nsong.flac\ngone.flac\nhttp://radio.example/stream\n",
+    )
+    .unwrap();
+
+    let loaded = PlaylistFile::load(&folder.join("list.m3u")).unwrap();
+
+    assert_eq!(loaded.paths(), [folder.join("song.flac")]);
+    assert_eq!(
+        loaded.unresolved,
+        ["gone.flac", "http://radio.example/stream"]
+    );
+}
+
+#[test]
+fn unknown_extensions_are_rejected() {
+    let result = PlaylistFile::load(Path::new("list.txt"));
+    assert!(matches!(result, Err(PlaylistError::UnsupportedFormat(_))));
+}
@@ -0,0 +1,110 @@
+use std::fmt::Write;
+
+use quick_xml::Reader;
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+
+use super::RawEntry;
+use crate::playlist::PlaylistError;
+
+#[derive(Clone, Copy)]
+enum Field {
+    Location,
+    Title,
+    Creator,
+    Duration,
+}
+
+pub(super) fn parse(content: &str) -> Result<Vec<RawEntry>, PlaylistError> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut entries = Vec::new();
+    let mut track: Option<RawEntry> = None;
+    let mut field = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(tag) => {
+                field = match tag.local_name().as_ref() {
+                    b"track" => {
+                        track = Some(RawEntry::default());
+                        None
+                    }
+                    // Only the first location is used, the rest are alternatives.
+                    b"location" if track.as_ref().is_some_and(|t| t.location.is_empty()) => {
+                        Some(Field::Location)
+                    }
+                    b"title" => Some(Field::Title),
+                    b"creator" => Some(Field::Creator),
+                    b"duration" => Some(Field::Duration),
+                    _ => None,
+                };
+            }
+            Event::Text(text) => {
+                if let (Some(track), Some(field)) = (track.as_mut(), field) {
+                    let text = text.unescape()?.into_owned();
+
+                    match field {
+                        Field::Location => track.location = text,
+                        Field::Title => track.title = Some(text),
+                        Field::Creator => track.artist = Some(text),
+                        Field::Duration => {
+                            track.duration = text.parse::<f64>().ok().map(|ms| ms / 1000.0)
+                        }
+                    }
+                }
+            }
+            Event::End(tag) => {
+                field = None;
+
+                if tag.local_name().as_ref() == b"track"
+                    && let Some(track) = track.take()
+                    && !track.location.is_empty()
+                {
+                    entries.push(track);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+pub(super) fn write(entries: &[RawEntry]) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n",
+        "  <trackList>\n",
+    ));
+
+    for entry in entries {
+        out.push_str("    <track>\n");
+        writeln!(
+            out,
+            "      <location>{}</location>",
+            escape(&entry.location)
+        )
+        .ok();
+
+        if let Some(title) = &entry.title {
+            writeln!(out, "      <title>{}</title>", escape(title)).ok();
+        }
+
+        if let Some(artist) = &entry.artist {
+            writeln!(out, "      <creator>{}</creator>", escape(artist)).ok();
+        }
+
+        if let Some(duration) = entry.duration {
+            let ms = (duration * 1000.0).round() as u64;
+            writeln!(out, "      <duration>{ms}</duration>").ok();
+        }
+
+        out.push_str("    </track>\n");
+    }
+
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
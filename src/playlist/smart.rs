@@ -0,0 +1,452 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::PlaylistError;
+use crate::library::{LibraryIndex, PlayStats, SortColumn, Track};
+
+#[cfg(test)]
+mod tests;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// A playlist defined by rules, re-evaluated against the library every time it is used.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmartPlaylist {
+    pub name: String,
+    #[serde(default)]
+    pub match_mode: MatchMode,
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub order: Order,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    #[default]
+    All,
+    Any,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    pub field: RuleField,
+    pub op: RuleOp,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleField {
+    Title,
+    Artist,
+    Album,
+    Genre,
+    Year,
+    Rating,
+    PlayCount,
+    /// Seconds.
+    Duration,
+    LastPlayed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleOp {
+    Contains,
+    NotContains,
+    Is,
+    IsNot,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    InLastDays,
+    NotInLastDays,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+    #[default]
+    Random,
+    Artist,
+    Album,
+    Title,
+    Year,
+    Rating,
+    MostPlayed,
+    RecentlyPlayed,
+    LeastRecentlyPlayed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Number,
+    Date,
+}
+
+impl RuleField {
+    pub const ALL: [RuleField; 9] = [
+        Self::Title,
+        Self::Artist,
+        Self::Album,
+        Self::Genre,
+        Self::Year,
+        Self::Rating,
+        Self::PlayCount,
+        Self::Duration,
+        Self::LastPlayed,
+    ];
+
+    pub fn kind(&self) -> FieldKind {
+        match self {
+            Self::Title | Self::Artist | Self::Album | Self::Genre => FieldKind::Text,
+            Self::Year | Self::Rating | Self::PlayCount | Self::Duration => FieldKind::Number,
+            Self::LastPlayed => FieldKind::Date,
+        }
+    }
+}
+
+impl FieldKind {
+    pub fn ops(&self) -> &'static [RuleOp] {
+        match self {
+            Self::Text => &[
+                RuleOp::Contains,
+                RuleOp::NotContains,
+                RuleOp::Is,
+                RuleOp::IsNot,
+            ],
+            Self::Number => &[
+                RuleOp::Is,
+                RuleOp::IsNot,
+                RuleOp::Lt,
+                RuleOp::Le,
+                RuleOp::Gt,
+                RuleOp::Ge,
+            ],
+            Self::Date => &[RuleOp::InLastDays, RuleOp::NotInLastDays],
+        }
+    }
+}
+
+impl Order {
+    pub const ALL: [Order; 9] = [
+        Self::Random,
+        Self::Artist,
+        Self::Album,
+        Self::Title,
+        Self::Year,
+        Self::Rating,
+        Self::MostPlayed,
+        Self::RecentlyPlayed,
+        Self::LeastRecentlyPlayed,
+    ];
+}
+
+impl fmt::Display for RuleField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Title => "Title",
+            Self::Artist => "Artist",
+            Self::Album => "Album",
+            Self::Genre => "Genre",
+            Self::Year => "Year",
+            Self::Rating => "Rating",
+            Self::PlayCount => "Play count",
+            Self::Duration => "Duration (s)",
+            Self::LastPlayed => "Last played",
+        })
+    }
+}
+
+impl fmt::Display for RuleOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Contains => "contains",
+            Self::NotContains => "does not contain",
+            Self::Is => "is",
+            Self::IsNot => "is not",
+            Self::Lt => "<",
+            Self::Le => "≤",
+            Self::Gt => ">",
+            Self::Ge => "≥",
+            Self::InLastDays => "in the last (days)",
+            Self::NotInLastDays => "not in the last (days)",
+        })
+    }
+}
+
+impl fmt::Display for MatchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::All => "Match all rules",
+            Self::Any => "Match any rule",
+        })
+    }
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Random => "Random",
+            Self::Artist => "Artist",
+            Self::Album => "Album",
+            Self::Title => "Title",
+            Self::Year => "Year",
+            Self::Rating => "Rating",
+            Self::MostPlayed => "Most played",
+            Self::RecentlyPlayed => "Recently played",
+            Self::LeastRecentlyPlayed => "Least recently played",
+        })
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            field: RuleField::Artist,
+            op: RuleOp::Contains,
+            value: String::new(),
+        }
+    }
+}
+
+impl Rule {
+    pub fn validate(&self) -> Result<(), PlaylistError> {
+        if !self.field.kind().ops().contains(&self.op) {
+            return Err(PlaylistError::InvalidRule(format!(
+                "{} cannot be compared with \"{}\"",
+                self.field, self.op
+            )));
+        }
+
+        if self.field.kind() != FieldKind::Text && self.number().is_none() {
+            return Err(PlaylistError::InvalidRule(format!(
+                "{} expects a number, got \"{}\"",
+                self.field, self.value
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn number(&self) -> Option<f64> {
+        self.value.trim().parse().ok()
+    }
+
+    fn matches(&self, track: &Track, stats: PlayStats, now: u64) -> bool {
+        match self.field.kind() {
+            FieldKind::Text => {
+                let value = match self.field {
+                    RuleField::Title => Some(track.display_title().into_owned()),
+                    RuleField::Artist => track.artist.clone(),
+                    RuleField::Album => track.album.clone(),
+                    RuleField::Genre => track.genre.clone(),
+                    _ => None,
+                }
+                .unwrap_or_default()
+                .to_lowercase();
+                let needle = self.value.trim().to_lowercase();
+
+                match self.op {
+                    RuleOp::Contains => value.contains(&needle),
+                    RuleOp::NotContains => !value.contains(&needle),
+                    RuleOp::Is => value == needle,
+                    RuleOp::IsNot => value != needle,
+                    _ => false,
+                }
+            }
+            FieldKind::Number => {
+                let (Some(rhs), Some(lhs)) = (self.number(), self.track_number(track, stats))
+                else {
+                    return false;
+                };
+
+                match self.op {
+                    RuleOp::Is => lhs == rhs,
+                    RuleOp::IsNot => lhs != rhs,
+                    RuleOp::Lt => lhs < rhs,
+                    RuleOp::Le => lhs <= rhs,
+                    RuleOp::Gt => lhs > rhs,
+                    RuleOp::Ge => lhs >= rhs,
+                    _ => false,
+                }
+            }
+            FieldKind::Date => {
+                let Some(days) = self.number() else {
+                    return false;
+                };
+
+                let since = now.saturating_sub((days.max(0.0) as u64) * SECONDS_PER_DAY);
+                let recent = stats.last_played.is_some_and(|t| t >= since);
+
+                match self.op {
+                    RuleOp::InLastDays => recent,
+                    RuleOp::NotInLastDays => !recent,
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    fn track_number(&self, track: &Track, stats: PlayStats) -> Option<f64> {
+        match self.field {
+            RuleField::Year => track.year.map(f64::from),
+            // Unrated tracks count as zero stars so "rating < 3" includes them.
+            RuleField::Rating => Some(track.rating.unwrap_or(0) as f64),
+            RuleField::PlayCount => Some(stats.play_count as f64),
+            RuleField::Duration => Some(track.duration),
+            _ => None,
+        }
+    }
+}
+
+impl Default for SmartPlaylist {
+    fn default() -> Self {
+        Self {
+            name: "New smart playlist".to_string(),
+            match_mode: MatchMode::All,
+            rules: vec![Rule::default()],
+            limit: None,
+            order: Order::Random,
+        }
+    }
+}
+
+impl SmartPlaylist {
+    pub fn validate(&self) -> Result<(), PlaylistError> {
+        if self.name.trim().is_empty() {
+            return Err(PlaylistError::MissingName);
+        }
+
+        self.rules.iter().try_for_each(Rule::validate)
+    }
+
+    /// `seed` fixes the random order, so it only changes when the caller picks a new seed.
+    pub fn evaluate<'a>(&self, index: &'a LibraryIndex, seed: u64) -> Vec<&'a Track> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        let mut tracks: Vec<_> = index
+            .tracks()
+            .filter(|track| {
+                let stats = index.stats(track.id);
+                let mut rules = self.rules.iter();
+
+                match self.match_mode {
+                    MatchMode::All => rules.all(|r| r.matches(track, stats, now)),
+                    MatchMode::Any => rules.any(|r| r.matches(track, stats, now)),
+                }
+            })
+            .collect();
+
+        match self.order {
+            // Each track gets its own key, so tracks added to the library don't move the rest.
+            Order::Random => {
+                tracks.sort_by_cached_key(|t| fastrand::Rng::with_seed(seed ^ t.id.0).u64(..))
+            }
+            Order::Artist => tracks.sort_by(|a, b| SortColumn::Artist.compare(a, b)),
+            Order::Album => tracks.sort_by(|a, b| SortColumn::Album.compare(a, b)),
+            Order::Title => tracks.sort_by(|a, b| SortColumn::Title.compare(a, b)),
+            Order::Year => tracks.sort_by(|a, b| SortColumn::Year.compare(a, b)),
+            Order::Rating => tracks.sort_by_key(|t| std::cmp::Reverse(t.rating)),
+            Order::MostPlayed => {
+                tracks.sort_by_key(|t| std::cmp::Reverse(index.stats(t.id).play_count))
+            }
+            Order::RecentlyPlayed => {
+                tracks.sort_by_key(|t| std::cmp::Reverse(index.stats(t.id).last_played))
+            }
+            Order::LeastRecentlyPlayed => tracks.sort_by_key(|t| index.stats(t.id).last_played),
+        }
+
+        if let Some(limit) = self.limit {
+            tracks.truncate(limit);
+        }
+
+        tracks
+    }
+
+    pub fn load<P: AsRef<Path> + ?Sized>(path: &P) -> Result<Self, PlaylistError> {
+        let data = fs::read_to_string(path)?;
+        let playlist: Self = serde_json::from_str(&data)?;
+
+        playlist.validate()?;
+        Ok(playlist)
+    }
+
+    pub fn save<P: AsRef<Path> + ?Sized>(&self, path: &P) -> Result<(), PlaylistError> {
+        self.validate()?;
+
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("cozy-music").join("smart-playlists"))
+    }
+
+    /// Where this playlist lives inside `dir`, derived from its name.
+    pub fn file_path(&self, dir: &Path) -> PathBuf {
+        let stem: String = self
+            .name
+            .trim()
+            .chars()
+            .map(|c| match c.is_alphanumeric() || c == '-' || c == '_' {
+                true => c,
+                false => '_',
+            })
+            .collect();
+
+        dir.join(format!("{stem}.json"))
+    }
+
+    /// Saves into `dir`, returning the file. `replacing` is the file the playlist was loaded
+    /// from, the only one it may overwrite, as different names can map to the same file.
+    pub fn save_in(&self, dir: &Path, replacing: Option<&Path>) -> Result<PathBuf, PlaylistError> {
+        self.validate()?;
+        let path = self.file_path(dir);
+
+        if path.exists() && replacing != Some(path.as_path()) {
+            return Err(PlaylistError::NameTaken(self.name.trim().to_string()));
+        }
+
+        self.save(&path)?;
+        Ok(path)
+    }
+
+    /// Loads every playlist in `dir`, skipping files that fail to parse.
+    pub fn load_all(dir: &Path) -> Vec<(PathBuf, Self)> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        let mut playlists: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| match Self::load(&path) {
+                Ok(playlist) => Some((path, playlist)),
+                Err(err) => {
+                    log::warn!("Skipping {}: {err}", path.display());
+                    None
+                }
+            })
+            .collect();
+
+        playlists.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name));
+        playlists
+    }
+}
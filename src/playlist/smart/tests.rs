@@ -0,0 +1,51 @@
+use super::*;
+use crate::test_util::TempDir;
+
+fn named(name: &str) -> SmartPlaylist {
+    SmartPlaylist {
+        name: name.to_string(),
+        ..SmartPlaylist::default()
+    }
+}
+
+#[test]
+fn empty_names_are_rejected() {
+    assert!(matches!(
+        named("  ").validate(),
+        Err(PlaylistError::MissingName)
+    ));
+}
+
+#[test]
+fn names_map_to_safe_file_names() {
+    let dir = Path::new("/playlists");
+
+    assert_eq!(
+        named(" Rock/Pop ").file_path(dir),
+        dir.join("Rock_Pop.json")
+    );
+    assert_eq!(named("Mix-2_b").file_path(dir), dir.join("Mix-2_b.json"));
+}
+
+#[test]
+fn names_sharing_a_file_are_refused() {
+    let dir = TempDir::new("collide");
+
+    let path = named("Rock/Pop").save_in(dir.path(), None).unwrap();
+    let err = named("Rock Pop").save_in(dir.path(), None).unwrap_err();
+
+    assert!(matches!(err, PlaylistError::NameTaken(name) if name == "Rock Pop"));
+    assert_eq!(SmartPlaylist::load(&path).unwrap().name, "Rock/Pop");
+}
+
+#[test]
+fn a_playlist_can_overwrite_its_own_file() {
+    let dir = TempDir::new("overwrite");
+
+    let path = named("Chill").save_in(dir.path(), None).unwrap();
+    let mut playlist = named("Chill");
+    playlist.limit = Some(10);
+
+    assert_eq!(playlist.save_in(dir.path(), Some(&path)).unwrap(), path);
+    assert_eq!(SmartPlaylist::load(&path).unwrap().limit, Some(10));
+}
@@ -0,0 +1,115 @@
+use std::future::Future;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+mod api;
+mod error;
+mod state;
+
+pub use error::*;
+pub use state::*;
+
+use crate::library::Library;
+use crate::player::{LoopMode, PlayQueue};
+
+/// The HTTP remote control, the `[remote]` section of the config file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteConfig {
+    #[serde(default = "RemoteConfig::default_bind")]
+    pub bind: SocketAddr,
+    /// Clients send it as `Authorization: Bearer <token>`, or `?token=` for WebSockets.
+    pub token: String,
+}
+
+/// Requests from remote clients, for the app to carry out.
+#[derive(Debug, Clone)]
+pub enum RemoteCommand {
+    Play,
+    Pause,
+    Stop,
+    Next,
+    Previous,
+    /// Seconds into the current track.
+    SeekSeconds(f64),
+    Volume(f32),
+    Speed(f64),
+    LoopMode(LoopMode),
+    Shuffle(bool),
+    PlayAll(Vec<PathBuf>),
+    Enqueue(Vec<PathBuf>),
+    PlayIndex(usize),
+    Remove(usize),
+    ClearQueue,
+}
+
+/// Returns whether the app took the command.
+type CommandHandler = Arc<dyn Fn(RemoteCommand) -> bool + Send + Sync>;
+
+/// Handle to a running server, used to publish what clients see.
+#[derive(Debug, Clone)]
+pub struct Remote {
+    status: watch::Sender<RemoteStatus>,
+    queue: watch::Sender<PlayQueue>,
+}
+
+impl RemoteConfig {
+    fn default_bind() -> SocketAddr {
+        (Ipv4Addr::LOCALHOST, 7650).into()
+    }
+}
+
+impl Remote {
+    /// Binds the listener. The returned future serves requests until it fails.
+    pub async fn bind<F>(
+        config: &RemoteConfig,
+        library: Library,
+        on_command: F,
+    ) -> Result<(Self, impl Future<Output = std::io::Result<()>> + use<F>), RemoteError>
+    where
+        F: Fn(RemoteCommand) -> bool + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind(config.bind).await?;
+        let (status, _) = watch::channel(RemoteStatus::default());
+        let (queue, _) = watch::channel(PlayQueue::default());
+
+        let router = api::router(api::ApiState {
+            token: Arc::from(config.token.as_str()),
+            library,
+            on_command: Arc::new(on_command),
+            status: status.subscribe(),
+            queue: queue.subscribe(),
+        });
+
+        let serve = async move { axum::serve(listener, router).await };
+
+        Ok((Self { status, queue }, serve))
+    }
+
+    /// Publishes the latest status. WebSocket clients hear about it when more than the
+    /// position changed, or when `seeked`.
+    pub fn update_status(&self, status: RemoteStatus, seeked: bool) {
+        self.status.send_if_modified(|current| {
+            let changed = seeked || !current.same_state(&status);
+
+            *current = status;
+            changed
+        });
+    }
+
+    pub fn update_queue(&self, queue: &PlayQueue) {
+        self.queue.send_if_modified(|current| {
+            if current == queue {
+                return false;
+            }
+
+            *current = queue.clone();
+            true
+        });
+    }
+}
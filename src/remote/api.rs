@@ -0,0 +1,320 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{MethodRouter, delete, get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::watch;
+
+use super::{CommandHandler, RemoteCommand, RemoteStatus};
+use crate::library::{Library, SearchError, SearchQuery, SortColumn, Track};
+use crate::player::{LoopMode, PlayQueue, PlaybackStatus};
+
+#[cfg(test)]
+mod tests;
+
+/// How often WebSocket clients are sent the position while playing.
+const POSITION_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+
+#[derive(Clone)]
+pub(super) struct ApiState {
+    pub token: Arc<str>,
+    pub library: Library,
+    pub on_command: CommandHandler,
+    pub status: watch::Receiver<RemoteStatus>,
+    pub queue: watch::Receiver<PlayQueue>,
+}
+
+struct ApiError(StatusCode, String);
+
+type ApiResult = Result<StatusCode, ApiError>;
+
+#[derive(Deserialize)]
+struct SeekBody {
+    /// Seconds.
+    position: f64,
+}
+
+#[derive(Deserialize)]
+struct VolumeBody {
+    volume: f32,
+}
+
+#[derive(Deserialize)]
+struct SpeedBody {
+    speed: f64,
+}
+
+#[derive(Deserialize)]
+struct LoopBody {
+    mode: LoopMode,
+}
+
+#[derive(Deserialize)]
+struct ShuffleBody {
+    shuffle: bool,
+}
+
+#[derive(Deserialize)]
+struct QueueBody {
+    paths: Vec<PathBuf>,
+    /// Replace the queue and start playing instead of adding to it.
+    #[serde(default)]
+    play: bool,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    #[serde(default)]
+    q: String,
+    limit: Option<usize>,
+}
+
+/// Messages pushed to WebSocket clients.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Update {
+    Status(Box<RemoteStatus>),
+    Queue(PlayQueue),
+    Position { position: f64 },
+}
+
+pub(super) fn router(state: ApiState) -> Router {
+    let api = Router::new()
+        .route("/status", get(status))
+        .route("/play", transport(RemoteCommand::Play))
+        .route("/pause", transport(RemoteCommand::Pause))
+        .route("/stop", transport(RemoteCommand::Stop))
+        .route("/next", transport(RemoteCommand::Next))
+        .route("/previous", transport(RemoteCommand::Previous))
+        .route("/seek", post(seek))
+        .route("/volume", post(volume))
+        .route("/speed", post(speed))
+        .route("/loop", post(loop_mode))
+        .route("/shuffle", post(shuffle))
+        .route("/queue", get(queue).post(add_to_queue).delete(clear_queue))
+        .route("/queue/{index}", delete(remove_from_queue))
+        .route("/queue/{index}/play", post(play_index))
+        .route("/search", get(search))
+        .route("/ws", get(websocket))
+        .route_layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state);
+
+    Router::new().nest("/api", api)
+}
+
+impl ApiState {
+    fn send(&self, command: RemoteCommand) -> ApiResult {
+        match (self.on_command)(command) {
+            true => Ok(StatusCode::NO_CONTENT),
+            false => Err(ApiError(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "The player is busy, try again.".into(),
+            )),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+fn bad_request(message: impl Into<String>) -> ApiError {
+    ApiError(StatusCode::BAD_REQUEST, message.into())
+}
+
+async fn authorize(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+
+    // Browsers can't set headers on WebSocket requests.
+    let query = request.uri().query().and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "token")
+            .map(|(_, value)| value.into_owned())
+    });
+
+    match header.or(query) {
+        Some(token) if constant_time_eq(token.as_bytes(), state.token.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => ApiError(StatusCode::UNAUTHORIZED, "Missing or wrong token.".into()).into_response(),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+async fn status(State(state): State<ApiState>) -> Json<RemoteStatus> {
+    Json(state.status.borrow().clone())
+}
+
+fn transport(command: RemoteCommand) -> MethodRouter<ApiState> {
+    post(move |State(state): State<ApiState>| {
+        let command = command.clone();
+        async move { state.send(command) }
+    })
+}
+
+async fn seek(State(state): State<ApiState>, Json(body): Json<SeekBody>) -> ApiResult {
+    let duration = state.status.borrow().duration;
+
+    if duration <= 0.0 {
+        return Err(ApiError(StatusCode::CONFLICT, "Nothing is loaded.".into()));
+    }
+
+    if !(0.0..=duration).contains(&body.position) {
+        return Err(bad_request(format!(
+            "Position must be between 0 and {duration:.1} seconds."
+        )));
+    }
+
+    state.send(RemoteCommand::SeekSeconds(body.position))
+}
+
+async fn volume(State(state): State<ApiState>, Json(body): Json<VolumeBody>) -> ApiResult {
+    if !(0.0..=1.0).contains(&body.volume) {
+        return Err(bad_request("Volume must be between 0 and 1."));
+    }
+
+    state.send(RemoteCommand::Volume(body.volume))
+}
+
+async fn speed(State(state): State<ApiState>, Json(body): Json<SpeedBody>) -> ApiResult {
+    if !(0.5..=2.0).contains(&body.speed) {
+        return Err(bad_request("Speed must be between 0.5 and 2."));
+    }
+
+    state.send(RemoteCommand::Speed(body.speed))
+}
+
+async fn loop_mode(State(state): State<ApiState>, Json(body): Json<LoopBody>) -> ApiResult {
+    state.send(RemoteCommand::LoopMode(body.mode))
+}
+
+async fn shuffle(State(state): State<ApiState>, Json(body): Json<ShuffleBody>) -> ApiResult {
+    state.send(RemoteCommand::Shuffle(body.shuffle))
+}
+
+async fn queue(State(state): State<ApiState>) -> Json<PlayQueue> {
+    Json(state.queue.borrow().clone())
+}
+
+async fn add_to_queue(State(state): State<ApiState>, Json(body): Json<QueueBody>) -> ApiResult {
+    if let Some(missing) = body.paths.iter().find(|p| !p.is_file()) {
+        return Err(bad_request(format!("{} is not a file.", missing.display())));
+    }
+
+    match body.play {
+        true => state.send(RemoteCommand::PlayAll(body.paths)),
+        false => state.send(RemoteCommand::Enqueue(body.paths)),
+    }
+}
+
+async fn clear_queue(State(state): State<ApiState>) -> ApiResult {
+    state.send(RemoteCommand::ClearQueue)
+}
+
+fn queue_index(state: &ApiState, index: usize) -> Result<usize, ApiError> {
+    match index < state.queue.borrow().tracks().len() {
+        true => Ok(index),
+        false => Err(ApiError(
+            StatusCode::NOT_FOUND,
+            format!("The queue has no track {index}."),
+        )),
+    }
+}
+
+async fn remove_from_queue(State(state): State<ApiState>, Path(index): Path<usize>) -> ApiResult {
+    let index = queue_index(&state, index)?;
+    state.send(RemoteCommand::Remove(index))
+}
+
+async fn play_index(State(state): State<ApiState>, Path(index): Path<usize>) -> ApiResult {
+    let index = queue_index(&state, index)?;
+    state.send(RemoteCommand::PlayIndex(index))
+}
+
+async fn search(
+    State(state): State<ApiState>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<Track>>, ApiError> {
+    let query: SearchQuery = params
+        .q
+        .parse()
+        .map_err(|err: SearchError| bad_request(err.to_string()))?;
+
+    let library = state.library.read();
+    let tracks = library
+        .search(&query, SortColumn::default(), true)
+        .into_iter()
+        .take(params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+        .cloned()
+        .collect();
+
+    Ok(Json(tracks))
+}
+
+async fn websocket(State(state): State<ApiState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| push_updates(socket, state))
+}
+
+/// Sends the status and queue whenever they change, and the position while playing.
+async fn push_updates(mut socket: WebSocket, state: ApiState) {
+    let mut status = state.status.clone();
+    let mut queue = state.queue.clone();
+    let mut ticks = tokio::time::interval(POSITION_INTERVAL);
+
+    // New clients get everything right away.
+    status.mark_changed();
+    queue.mark_changed();
+
+    loop {
+        let update = tokio::select! {
+            res = status.changed() => match res {
+                Ok(()) => Update::Status(Box::new(status.borrow_and_update().clone())),
+                Err(_) => break,
+            },
+            res = queue.changed() => match res {
+                Ok(()) => Update::Queue(queue.borrow_and_update().clone()),
+                Err(_) => break,
+            },
+            _ = ticks.tick() => {
+                let status = status.borrow();
+
+                if status.status != PlaybackStatus::Playing {
+                    continue;
+                }
+
+                Update::Position { position: status.position }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+
+        let Ok(text) = serde_json::to_string(&update) else {
+            continue;
+        };
+
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            break;
+        }
+    }
+}
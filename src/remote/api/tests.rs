@@ -0,0 +1,233 @@
+use std::sync::{Arc, Mutex};
+
+use axum::body::{Body, to_bytes};
+use axum::http::{Method, Request, StatusCode, header};
+use tokio::sync::watch;
+use tower::ServiceExt;
+
+use super::{ApiState, router};
+use crate::library::Library;
+use crate::player::PlayQueue;
+use crate::remote::{RemoteCommand, RemoteStatus};
+use crate::test_util::TempDir;
+
+const TOKEN: &str = "secret";
+
+/// A router whose commands end up in the returned list, or are refused without `accept`.
+fn api(accept: bool) -> (axum::Router, Arc<Mutex<Vec<RemoteCommand>>>) {
+    api_with_status(accept, RemoteStatus::default())
+}
+
+fn api_with_status(
+    accept: bool,
+    status: RemoteStatus,
+) -> (axum::Router, Arc<Mutex<Vec<RemoteCommand>>>) {
+    let commands = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&commands);
+
+    let state = ApiState {
+        token: Arc::from(TOKEN),
+        library: Library::new(Vec::new()),
+        on_command: Arc::new(move |command| {
+            if accept {
+                received.lock().unwrap().push(command);
+            }
+            accept
+        }),
+        status: watch::channel(status).1,
+        queue: watch::channel(PlayQueue::default()).1,
+    };
+
+    (router(state), commands)
+}
+
+fn request(method: Method, uri: &str, body: Option<serde_json::Value>) -> Request<Body> {
+    let builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {TOKEN}"));
+
+    match body {
+        Some(body) => builder
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        None => builder.body(Body::empty()),
+    }
+    .unwrap()
+}
+
+async fn error_message(response: axum::response::Response) -> String {
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    json["error"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn requests_without_the_token_are_refused() {
+    let (api, commands) = api(true);
+
+    let missing = Request::post("/api/play").body(Body::empty()).unwrap();
+    let response = api.clone().oneshot(missing).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let wrong = Request::post("/api/play")
+        .header(header::AUTHORIZATION, "Bearer guess")
+        .body(Body::empty())
+        .unwrap();
+    let response = api.oneshot(wrong).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    assert!(commands.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn the_token_can_be_a_query_parameter() {
+    let (api, _) = api(true);
+
+    let request = Request::get(format!("/api/status?token={TOKEN}"))
+        .body(Body::empty())
+        .unwrap();
+    let response = api.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn commands_reach_the_app() {
+    let (api, commands) = api(true);
+
+    let response = api
+        .oneshot(request(Method::POST, "/api/next", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(matches!(
+        commands.lock().unwrap()[..],
+        [RemoteCommand::Next]
+    ));
+}
+
+#[tokio::test]
+async fn out_of_range_values_are_bad_requests() {
+    let (api, commands) = api(true);
+
+    let body = serde_json::json!({ "volume": 1.5 });
+    let response = api
+        .clone()
+        .oneshot(request(Method::POST, "/api/volume", Some(body)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        error_message(response).await,
+        "Volume must be between 0 and 1."
+    );
+
+    let body = serde_json::json!({ "speed": 3.0 });
+    let response = api
+        .oneshot(request(Method::POST, "/api/speed", Some(body)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    assert!(commands.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn seeking_needs_a_track() {
+    let (api, _) = api(true);
+
+    let body = serde_json::json!({ "position": 10.0 });
+    let response = api
+        .oneshot(request(Method::POST, "/api/seek", Some(body)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn seeking_sends_seconds() {
+    let status = RemoteStatus {
+        duration: 200.0,
+        ..RemoteStatus::default()
+    };
+    let (api, commands) = api_with_status(true, status);
+
+    let body = serde_json::json!({ "position": 30.0 });
+    let response = api
+        .oneshot(request(Method::POST, "/api/seek", Some(body)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let commands = commands.lock().unwrap();
+    assert!(
+        matches!(&commands[..], [RemoteCommand::SeekSeconds(30.0)]),
+        "{commands:?}"
+    );
+}
+
+#[tokio::test]
+async fn enqueueing_sends_one_command() {
+    let (api, commands) = api(true);
+    let dir = TempDir::new("enqueue");
+    let files: Vec<_> = (0..150).map(|n| dir.touch(format!("{n}.flac"))).collect();
+
+    let body = serde_json::json!({ "paths": files });
+    let response = api
+        .oneshot(request(Method::POST, "/api/queue", Some(body)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let commands = commands.lock().unwrap();
+    assert!(
+        matches!(&commands[..], [RemoteCommand::Enqueue(paths)] if *paths == files),
+        "{commands:?}"
+    );
+}
+
+#[tokio::test]
+async fn enqueueing_a_missing_file_is_a_bad_request() {
+    let (api, commands) = api(true);
+
+    let body = serde_json::json!({ "paths": ["/nowhere/missing.flac"] });
+    let response = api
+        .oneshot(request(Method::POST, "/api/queue", Some(body)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(commands.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn removing_past_the_end_is_not_found() {
+    let (api, _) = api(true);
+
+    let response = api
+        .oneshot(request(Method::DELETE, "/api/queue/3", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn invalid_searches_are_bad_requests() {
+    let (api, _) = api(true);
+
+    let response = api
+        .oneshot(request(Method::GET, "/api/search?q=year:soon", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn a_busy_app_is_unavailable() {
+    let (api, _) = api(false);
+
+    let response = api
+        .oneshot(request(Method::POST, "/api/play", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
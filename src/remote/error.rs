@@ -0,0 +1,5 @@
+#[derive(Debug, thiserror::Error)]
+pub enum RemoteError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
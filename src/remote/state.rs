@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::library::Track;
+use crate::player::{LoopMode, PlaybackStatus};
+
+/// What `/api/status` returns and WebSocket clients are sent.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RemoteStatus {
+    pub status: PlaybackStatus,
+    pub path: Option<PathBuf>,
+    /// Library details of the current track, when known.
+    pub track: Option<Track>,
+    /// Seconds.
+    pub position: f64,
+    /// Seconds.
+    pub duration: f64,
+    pub volume: f32,
+    pub speed: f64,
+    pub loop_mode: LoopMode,
+    pub shuffle: bool,
+}
+
+impl RemoteStatus {
+    /// Equal apart from the position, which changes all the time while playing.
+    pub(super) fn same_state(&self, other: &RemoteStatus) -> bool {
+        self.status == other.status
+            && self.path == other.path
+            && self.track.as_ref().map(|t| &t.path) == other.track.as_ref().map(|t| &t.path)
+            && self.duration == other.duration
+            && self.volume == other.volume
+            && self.speed == other.speed
+            && self.loop_mode == other.loop_mode
+            && self.shuffle == other.shuffle
+    }
+}
@@ -0,0 +1,226 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use ureq::Agent;
+
+use super::{ListenQueue, ScrobbleError};
+use crate::library::Track;
+
+#[cfg(test)]
+mod tests;
+
+const MIN_DURATION: f64 = 30.0;
+const MAX_REQUIRED_LISTEN: f64 = 240.0;
+/// Listens sent per request when flushing the queue.
+const BATCH_SIZE: usize = 100;
+
+/// Where to submit listens. Any server speaking the ListenBrainz API works, including local
+/// ones and Last.fm bridges.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScrobbleConfig {
+    #[serde(default = "ScrobbleConfig::default_url")]
+    pub url: String,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Listen {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    /// Seconds.
+    pub duration: Option<f64>,
+    /// Unix timestamp in seconds of when playback started.
+    pub listened_at: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scrobbler {
+    config: ScrobbleConfig,
+    agent: Agent,
+    queue: Arc<Mutex<ListenQueue>>,
+    /// Held while a flush sends, which the queue itself isn't.
+    flushing: Arc<Mutex<()>>,
+}
+
+impl ScrobbleConfig {
+    fn default_url() -> String {
+        "https://api.listenbrainz.org".to_string()
+    }
+}
+
+impl Listen {
+    /// `None` for tracks without an artist or title, which servers reject.
+    pub fn from_track(track: &Track, listened_at: u64) -> Option<Self> {
+        Some(Self {
+            artist: track.artist.clone()?,
+            title: track.title.clone()?,
+            album: track.album.clone(),
+            duration: (track.duration > 0.0).then_some(track.duration),
+            listened_at,
+        })
+    }
+
+    /// The usual rules: the track is longer than 30 seconds and half of it, or four minutes,
+    /// were listened to.
+    pub fn should_scrobble(duration: f64, listened: f64) -> bool {
+        duration > MIN_DURATION && listened >= (duration / 2.0).min(MAX_REQUIRED_LISTEN)
+    }
+
+    fn payload(&self, with_timestamp: bool) -> serde_json::Value {
+        let mut additional_info = json!({
+            "media_player": env!("CARGO_PKG_NAME"),
+            "submission_client": env!("CARGO_PKG_NAME"),
+            "submission_client_version": env!("CARGO_PKG_VERSION"),
+        });
+
+        if let Some(duration) = self.duration {
+            additional_info["duration_ms"] = json!((duration * 1000.0).round() as u64);
+        }
+
+        let mut payload = json!({
+            "track_metadata": {
+                "artist_name": self.artist,
+                "track_name": self.title,
+                "additional_info": additional_info,
+            }
+        });
+
+        if let Some(album) = &self.album {
+            payload["track_metadata"]["release_name"] = json!(album);
+        }
+
+        if with_timestamp {
+            payload["listened_at"] = json!(self.listened_at);
+        }
+
+        payload
+    }
+}
+
+impl Scrobbler {
+    pub fn new(config: ScrobbleConfig, queue: ListenQueue) -> Self {
+        let agent = Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(15)))
+            .build()
+            .into();
+
+        Self {
+            config,
+            agent,
+            queue: Arc::new(Mutex::new(queue)),
+            flushing: Arc::default(),
+        }
+    }
+
+    /// Best effort, nothing is queued when it fails.
+    pub fn now_playing(&self, listen: &Listen) -> Result<(), ScrobbleError> {
+        self.submit("playing_now", vec![listen.payload(false)])
+    }
+
+    /// Queues `listen` on disk and tries to send everything that's waiting.
+    pub fn scrobble(&self, listen: Listen) -> Result<usize, ScrobbleError> {
+        self.lock_queue().push(&listen)?;
+        self.flush()
+    }
+
+    /// Sends queued listens in batches. Whatever couldn't be sent for now stays queued,
+    /// listens the server rejects are set aside so they don't hold up the rest.
+    pub fn flush(&self) -> Result<usize, ScrobbleError> {
+        // Another flush is already sending, and takes the listens queued so far with it.
+        let Ok(_flushing) = self.flushing.try_lock() else {
+            return Ok(0);
+        };
+
+        // Not locked while sending, so plays can be queued in the meantime.
+        let listens = self.lock_queue().load()?;
+        let mut sent = 0;
+        let mut rejected = Vec::new();
+        let mut failure = None;
+
+        for batch in listens.chunks(BATCH_SIZE) {
+            match self.submit_batch(batch) {
+                Ok(()) => sent += batch.len(),
+                Err(err) if err.keeps_listens() => {
+                    failure = Some(err);
+                    break;
+                }
+                // One listen the server doesn't take fails the whole batch, so they go one
+                // by one to set aside only that one.
+                Err(_) if batch.len() > 1 => {
+                    for listen in batch {
+                        match self.submit_batch(std::slice::from_ref(listen)) {
+                            Ok(()) => sent += 1,
+                            Err(err) if err.keeps_listens() => {
+                                failure = Some(err);
+                                break;
+                            }
+                            Err(err) => {
+                                log::warn!("Server rejected {listen:?}: {err}");
+                                rejected.push(listen.clone());
+                            }
+                        }
+                    }
+                }
+                Err(err) => {
+                    log::warn!("Server rejected {} listens: {err}", batch.len());
+                    rejected.extend_from_slice(batch);
+                }
+            }
+
+            if failure.is_some() {
+                break;
+            }
+        }
+
+        let handled = sent + rejected.len();
+        let mut queue = self.lock_queue();
+
+        if !rejected.is_empty() {
+            queue.set_aside(&rejected)?;
+        }
+
+        // Listens are only ever appended, so the ones handled are still at the front.
+        let current = queue.load()?;
+        queue.replace(current.get(handled..).unwrap_or_default())?;
+
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(sent),
+        }
+    }
+
+    fn submit_batch(&self, batch: &[Listen]) -> Result<(), ScrobbleError> {
+        let listen_type = match batch.len() {
+            1 => "single",
+            _ => "import",
+        };
+
+        self.submit(listen_type, batch.iter().map(|l| l.payload(true)).collect())
+    }
+
+    fn submit(
+        &self,
+        listen_type: &str,
+        payload: Vec<serde_json::Value>,
+    ) -> Result<(), ScrobbleError> {
+        let url = format!("{}/1/submit-listens", self.config.url.trim_end_matches('/'));
+
+        self.agent
+            .post(&url)
+            .header("Authorization", &format!("Token {}", self.config.token))
+            .send_json(json!({
+                "listen_type": listen_type,
+                "payload": payload,
+            }))?;
+
+        Ok(())
+    }
+
+    fn lock_queue(&self) -> std::sync::MutexGuard<'_, ListenQueue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
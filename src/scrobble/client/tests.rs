@@ -0,0 +1,202 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::Value;
+
+use super::*;
+use crate::test_util::TempDir;
+
+type Requests = Arc<Mutex<Vec<Value>>>;
+
+/// A ListenBrainz stand-in on a local port, answering each submission with the status
+/// `respond` picks for it. Returns its URL and the bodies it received.
+fn mock_server(respond: impl Fn(&Value) -> u16 + Send + Sync + 'static) -> (String, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Requests::default();
+    let received = Arc::clone(&requests);
+    let respond = Arc::new(respond);
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let received = Arc::clone(&received);
+            let respond = Arc::clone(&respond);
+
+            thread::spawn(move || serve(stream, &received, &*respond));
+        }
+    });
+
+    (url, requests)
+}
+
+/// Answers requests on one connection until the client closes it.
+fn serve(stream: TcpStream, received: &Requests, respond: &dyn Fn(&Value) -> u16) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut stream = stream;
+
+    loop {
+        let mut length = 0;
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            if line == "\r\n" {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                length = value.trim().parse().unwrap();
+            }
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+
+        let status = respond(&body);
+        received.lock().unwrap().push(body);
+
+        let response = format!("HTTP/1.1 {status} Mock\r\nContent-Length: 2\r\n\r\n{{}}");
+        if stream.write_all(response.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+fn listen(title: &str) -> Listen {
+    Listen {
+        artist: "Artist".to_string(),
+        title: title.to_string(),
+        album: None,
+        duration: Some(200.0),
+        listened_at: 1_700_000_000,
+    }
+}
+
+/// A scrobbler for `url` with `titles` waiting in its queue.
+fn scrobbler(dir: &TempDir, url: String, titles: &[&str]) -> Scrobbler {
+    let mut queue = ListenQueue::new(dir.join("queue.jsonl"));
+    for title in titles {
+        queue.push(&listen(title)).unwrap();
+    }
+
+    let config = ScrobbleConfig {
+        url,
+        token: "token".to_string(),
+    };
+    Scrobbler::new(config, queue)
+}
+
+fn queued(dir: &TempDir) -> Vec<String> {
+    let listens = ListenQueue::new(dir.join("queue.jsonl")).load().unwrap();
+    listens.into_iter().map(|listen| listen.title).collect()
+}
+
+fn set_aside(dir: &TempDir) -> Vec<String> {
+    let listens = ListenQueue::new(dir.join("queue.rejected.jsonl"))
+        .load()
+        .unwrap();
+    listens.into_iter().map(|listen| listen.title).collect()
+}
+
+fn titles(request: &Value) -> Vec<&str> {
+    request["payload"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|listen| listen["track_metadata"]["track_name"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn queued_listens_are_sent_and_removed() {
+    let dir = TempDir::new("scrobble-sent");
+    let (url, requests) = mock_server(|_| 200);
+    let scrobbler = scrobbler(&dir, url, &["One", "Two", "Three"]);
+
+    assert_eq!(scrobbler.flush().unwrap(), 3);
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["listen_type"], "import");
+    assert_eq!(titles(&requests[0]), ["One", "Two", "Three"]);
+    assert_eq!(requests[0]["payload"][0]["listened_at"], 1_700_000_000);
+    assert!(queued(&dir).is_empty());
+}
+
+#[test]
+fn listens_are_sent_in_batches() {
+    let dir = TempDir::new("scrobble-batches");
+    let (url, requests) = mock_server(|_| 200);
+    let names: Vec<_> = (0..BATCH_SIZE + 1).map(|n| n.to_string()).collect();
+    let names: Vec<_> = names.iter().map(String::as_str).collect();
+    let scrobbler = scrobbler(&dir, url, &names);
+
+    assert_eq!(scrobbler.flush().unwrap(), BATCH_SIZE + 1);
+
+    let requests = requests.lock().unwrap();
+    let sizes: Vec<_> = requests.iter().map(|r| titles(r).len()).collect();
+    assert_eq!(sizes, [BATCH_SIZE, 1]);
+    assert_eq!(requests[1]["listen_type"], "single");
+}
+
+#[test]
+fn rejected_listens_are_set_aside() {
+    let dir = TempDir::new("scrobble-rejected");
+    let (url, requests) = mock_server(|body| {
+        if titles(body).contains(&"Bad") {
+            400
+        } else {
+            200
+        }
+    });
+    let scrobbler = scrobbler(&dir, url, &["One", "Bad", "Two"]);
+
+    assert_eq!(scrobbler.flush().unwrap(), 2);
+
+    // The batch, then each listen on its own.
+    assert_eq!(requests.lock().unwrap().len(), 4);
+    assert!(queued(&dir).is_empty());
+    assert_eq!(set_aside(&dir), ["Bad"]);
+}
+
+#[test]
+fn a_refused_token_keeps_the_queue() {
+    let dir = TempDir::new("scrobble-token");
+    let (url, requests) = mock_server(|_| 401);
+    let scrobbler = scrobbler(&dir, url, &["One", "Two"]);
+
+    let err = scrobbler.flush().unwrap_err();
+
+    assert!(err.is_auth_failure(), "{err:?}");
+    assert_eq!(requests.lock().unwrap().len(), 1);
+    assert_eq!(queued(&dir), ["One", "Two"]);
+    assert!(set_aside(&dir).is_empty());
+}
+
+#[test]
+fn server_errors_are_retried_on_the_next_flush() {
+    let dir = TempDir::new("scrobble-retry");
+    let failing = Arc::new(Mutex::new(true));
+    let fail = Arc::clone(&failing);
+    let (url, requests) = mock_server(move |_| if *fail.lock().unwrap() { 503 } else { 200 });
+    let scrobbler = scrobbler(&dir, url, &["One", "Two"]);
+
+    let err = scrobbler.flush().unwrap_err();
+    assert!(err.is_transient(), "{err:?}");
+    assert_eq!(queued(&dir), ["One", "Two"]);
+
+    *failing.lock().unwrap() = false;
+    scrobbler.scrobble(listen("Three")).unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(titles(requests.last().unwrap()), ["One", "Two", "Three"]);
+    assert!(queued(&dir).is_empty());
+    assert!(set_aside(&dir).is_empty());
+}
@@ -0,0 +1,36 @@
+#[derive(Debug, thiserror::Error)]
+pub enum ScrobbleError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    Http(#[from] ureq::Error),
+}
+
+impl ScrobbleError {
+    /// Worth trying again later, as the connection or the server failed rather than the
+    /// server refusing what was sent.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Http(ureq::Error::StatusCode(status)) => {
+                matches!(status, 408 | 429) || *status >= 500
+            }
+            Self::Http(_) | Self::Io(_) => true,
+            Self::Json(_) => false,
+        }
+    }
+
+    /// The token was refused, so every listen would be.
+    pub fn is_auth_failure(&self) -> bool {
+        matches!(self, Self::Http(ureq::Error::StatusCode(401 | 403)))
+    }
+
+    /// Whether the listens should stay queued rather than be set aside. A refused token says
+    /// nothing about the listens and is fixed in the config.
+    pub fn keeps_listens(&self) -> bool {
+        self.is_transient() || self.is_auth_failure()
+    }
+}
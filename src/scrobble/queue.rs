@@ -0,0 +1,94 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use super::{Listen, ScrobbleError};
+
+/// Listens waiting to be submitted, one JSON object per line.
+#[derive(Debug)]
+pub struct ListenQueue {
+    path: PathBuf,
+}
+
+impl ListenQueue {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("cozy-music").join("scrobble-queue.jsonl"))
+    }
+
+    pub fn push(&mut self, listen: &Listen) -> Result<(), ScrobbleError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        writeln!(file, "{}", serde_json::to_string(listen)?)?;
+        Ok(())
+    }
+
+    /// Keeps listens the server refused next to the queue, where they're no longer sent.
+    pub fn set_aside(&mut self, listens: &[Listen]) -> Result<(), ScrobbleError> {
+        let path = self.path.with_extension("rejected.jsonl");
+        log::warn!(
+            "Setting {} rejected listens aside in {}",
+            listens.len(),
+            path.display()
+        );
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        for listen in listens {
+            writeln!(file, "{}", serde_json::to_string(listen)?)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn load(&self) -> Result<Vec<Listen>, ScrobbleError> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let listens = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(listen) => Some(listen),
+                Err(err) => {
+                    log::warn!("Dropping unreadable queued listen: {err}");
+                    None
+                }
+            })
+            .collect();
+
+        Ok(listens)
+    }
+
+    pub fn replace(&mut self, listens: &[Listen]) -> Result<(), ScrobbleError> {
+        if listens.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+                _ => Ok(()),
+            };
+        }
+
+        let mut content = String::new();
+
+        for listen in listens {
+            content.push_str(&serde_json::to_string(listen)?);
+            content.push('\n');
+        }
+
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+mod error;
+
+pub use error::*;
+
+use crate::player::PlayQueue;
+
+/// What was playing when the app last ran, restored paused on the next launch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub queue: PlayQueue,
+    /// Seconds into the current track.
+    #[serde(default)]
+    pub position: f64,
+    pub volume: f32,
+    pub speed: f64,
+    /// `PlayerFlags` bits, without `IS_PLAYING`.
+    #[serde(default)]
+    pub flags: u8,
+}
+
+impl Session {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("cozy-music").join("session.json"))
+    }
+
+    /// `None` on the first launch.
+    pub fn load(path: &Path) -> Result<Option<Self>, SessionError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    /// Writes a temporary file and renames it over the old one, so a crash mid-write
+    /// keeps the previous session intact.
+    pub fn save(&self, path: &Path) -> Result<(), SessionError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+
+        Ok(())
+    }
+}